@@ -9,6 +9,7 @@ mod error;
 mod h1proto;
 mod h2proto;
 mod pool;
+mod propagate;
 
 pub use actix_tls::connect::{
     Connect as TcpConnect, ConnectError as TcpConnectError, Connection as TcpConnection,
@@ -17,6 +18,7 @@ pub use actix_tls::connect::{
 pub use self::connection::{Connection, ConnectionIo};
 pub use self::connector::{Connector, ConnectorService};
 pub use self::error::{ConnectError, FreezeRequestError, InvalidUrl, SendRequestError};
+pub use self::propagate::{propagated_headers, with_propagated_header};
 pub use crate::Protocol;
 
 #[derive(Clone)]
@@ -0,0 +1,76 @@
+//! Headers propagated from the request being handled to client requests.
+
+use std::cell::RefCell;
+
+use crate::header::{HeaderMap, HeaderName, HeaderValue};
+
+thread_local! {
+    static PROPAGATED: RefCell<Vec<(HeaderName, HeaderValue)>> = RefCell::new(Vec::new());
+}
+
+/// Calls `f` with a header propagated to the client requests created on this thread until it
+/// returns.
+///
+/// Servers use this around polling the handler of a request, so that client requests created by
+/// the handler carry headers of that request, such as its ID. Calls can be nested; the innermost
+/// value of a header wins. Tasks spawned by the handler do not inherit propagated headers.
+pub fn with_propagated_header<R>(
+    name: HeaderName,
+    value: HeaderValue,
+    f: impl FnOnce() -> R,
+) -> R {
+    struct Guard;
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            PROPAGATED.with(|headers| headers.borrow_mut().pop());
+        }
+    }
+
+    PROPAGATED.with(|headers| headers.borrow_mut().push((name, value)));
+    let _guard = Guard;
+
+    f()
+}
+
+/// Returns the headers propagated to client requests created on this thread at this point.
+pub fn propagated_headers() -> HeaderMap {
+    PROPAGATED.with(|headers| {
+        let mut map = HeaderMap::new();
+
+        for (name, value) in headers.borrow().iter() {
+            map.insert(name.clone(), value.clone());
+        }
+
+        map
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_scopes() {
+        let id = HeaderName::from_static("x-request-id");
+        assert!(propagated_headers().is_empty());
+
+        with_propagated_header(id.clone(), HeaderValue::from_static("outer"), || {
+            assert_eq!(propagated_headers().get(&id).unwrap(), "outer");
+
+            with_propagated_header(
+                id.clone(),
+                HeaderValue::from_static("inner"),
+                || {
+                    let headers = propagated_headers();
+                    assert_eq!(headers.len(), 1);
+                    assert_eq!(headers.get(&id).unwrap(), "inner");
+                },
+            );
+
+            assert_eq!(propagated_headers().get(&id).unwrap(), "outer");
+        });
+
+        assert!(propagated_headers().is_empty());
+    }
+}
@@ -22,8 +22,9 @@ use crate::{
     body::{AnyBody, BodySize, MessageBody},
     config::ServiceConfig,
     error::{DispatchError, ParseError, PayloadError},
+    metrics::{self, ConnectionGuard},
     service::HttpFlow,
-    OnConnectData, Request, Response, StatusCode,
+    OnConnectData, Protocol, Request, Response, StatusCode,
 };
 
 use super::{
@@ -43,11 +44,15 @@ bitflags! {
         const SHUTDOWN           = 0b0000_0100;
         const READ_DISCONNECT    = 0b0000_1000;
         const WRITE_DISCONNECT   = 0b0001_0000;
+        const SERVED_REQUEST     = 0b0010_0000;
     }
 }
 
 #[pin_project]
 /// Dispatcher for HTTP/1.1 protocol
+///
+/// With the `tracing` feature, the dispatcher opens a span for the connection, and enters the
+/// `tracing::Span` found in the extensions of a response, if any, while sending its body.
 pub struct Dispatcher<T, S, B, X, U>
 where
     S: Service<Request>,
@@ -65,6 +70,13 @@ where
     #[pin]
     inner: DispatcherState<T, S, B, X, U>,
 
+    /// Keeps the connection counted as open, including after an upgrade.
+    _connection: ConnectionGuard,
+
+    /// Span covering the connection, entered while the dispatcher is polled.
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+
     #[cfg(test)]
     poll_count: u64,
 }
@@ -112,6 +124,11 @@ where
     #[pin]
     state: State<S, B, X>,
     payload: Option<PayloadSender>,
+
+    /// Span found in the extensions of the response whose body is being sent.
+    #[cfg(feature = "tracing")]
+    body_span: Option<tracing::Span>,
+
     messages: VecDeque<DispatcherMessage>,
 
     ka_expire: Instant,
@@ -203,12 +220,21 @@ where
             None => (config.now(), None),
         };
 
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "http_connection",
+            protocol = "HTTP/1.1",
+            peer_addr = ?peer_addr
+        );
+
         Dispatcher {
             inner: DispatcherState::Normal(InnerDispatcher {
                 read_buf: BytesMut::with_capacity(HW_BUFFER_SIZE),
                 write_buf: BytesMut::with_capacity(HW_BUFFER_SIZE),
                 payload: None,
                 state: State::None,
+                #[cfg(feature = "tracing")]
+                body_span: None,
                 error: None,
                 messages: VecDeque::new(),
                 io: Some(io),
@@ -221,6 +247,11 @@ where
                 ka_timer,
             }),
 
+            _connection: ConnectionGuard::new(Protocol::Http1),
+
+            #[cfg(feature = "tracing")]
+            span,
+
             #[cfg(test)]
             poll_count: 0,
         }
@@ -323,12 +354,23 @@ where
         message: Response<()>,
         body: B,
     ) -> Result<(), DispatchError> {
+        #[cfg(feature = "tracing")]
+        let body_span = message.extensions().get::<tracing::Span>().cloned();
+
         let size = self.as_mut().send_response_inner(message, &body)?;
         let state = match size {
             BodySize::None | BodySize::Empty => State::None,
             _ => State::SendPayload(body),
         };
-        self.project().state.set(state);
+
+        let mut this = self.project();
+
+        #[cfg(feature = "tracing")]
+        {
+            *this.body_span = if state.is_empty() { None } else { body_span };
+        }
+
+        this.state.set(state);
         Ok(())
     }
 
@@ -418,6 +460,11 @@ where
                 },
 
                 StateProj::SendPayload(mut stream) => {
+                    #[cfg(feature = "tracing")]
+                    let span = this.body_span.clone();
+                    #[cfg(feature = "tracing")]
+                    let _enter = span.as_ref().map(tracing::Span::enter);
+
                     // keep populate writer buffer until buffer size limit hit,
                     // get blocked or finished.
                     while this.write_buf.len() < super::payload::MAX_BUFFER_SIZE {
@@ -435,6 +482,12 @@ where
                                 // payload stream finished.
                                 // set state to None and handle next message
                                 this.state.set(State::None);
+
+                                #[cfg(feature = "tracing")]
+                                {
+                                    *this.body_span = None;
+                                }
+
                                 continue 'res;
                             }
 
@@ -601,6 +654,10 @@ where
                         Message::Item(mut req) => {
                             req.head_mut().peer_addr = *this.peer_addr;
 
+                            let keep_alive = this.flags.contains(Flags::SERVED_REQUEST);
+                            metrics::count_request(Protocol::Http1, keep_alive);
+                            this.flags.insert(Flags::SERVED_REQUEST);
+
                             // merge on_connect_ext data into request extensions
                             this.on_connect_data.merge_into(&mut req);
 
@@ -748,6 +805,9 @@ where
                 if timer.as_mut().poll(cx).is_ready() {
                     // got timeout during shutdown, drop connection
                     if this.flags.contains(Flags::SHUTDOWN) {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!("client disconnect timeout");
+
                         return Err(DispatchError::DisconnectTimeout);
                         // exceed deadline. check for any outstanding tasks
                     } else if timer.deadline() >= *this.ka_expire {
@@ -755,6 +815,9 @@ where
                         if this.state.is_empty() && this.write_buf.is_empty() {
                             if this.flags.contains(Flags::STARTED) {
                                 trace!("Keep-alive timeout, close connection");
+                                #[cfg(feature = "tracing")]
+                                tracing::debug!("keep-alive timeout");
+
                                 this.flags.insert(Flags::SHUTDOWN);
 
                                 // start shutdown timeout
@@ -770,6 +833,9 @@ where
                             } else {
                                 // timeout on first request (slow request) return 408
                                 trace!("Slow request timeout");
+                                #[cfg(feature = "tracing")]
+                                tracing::debug!("slow request timeout");
+
                                 let _ = self.as_mut().send_error_response(
                                     Response::with_body(StatusCode::REQUEST_TIMEOUT, ()),
                                     AnyBody::Empty,
@@ -925,6 +991,11 @@ where
 
     #[inline]
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        #[cfg(feature = "tracing")]
+        let span = self.span.clone();
+        #[cfg(feature = "tracing")]
+        let _enter = span.enter();
+
         let this = self.as_mut().project();
 
         #[cfg(test)]
@@ -1005,6 +1076,9 @@ where
                     // keep-alive and stream errors
                     if is_empty && inner_p.write_buf.is_empty() {
                         if let Some(err) = inner_p.error.take() {
+                            #[cfg(feature = "tracing")]
+                            tracing::debug!(error = %err, "connection error");
+
                             Poll::Ready(Err(err))
                         }
                         // disconnect if keep-alive is not enabled
@@ -1027,6 +1101,10 @@ where
             }
             DispatcherStateProj::Upgrade(fut) => fut.poll(cx).map_err(|e| {
                 error!("Upgrade handler error: {}", e);
+
+                #[cfg(feature = "tracing")]
+                tracing::debug!(error = %e, "upgrade handler error");
+
                 DispatchError::Upgrade
             }),
         }
@@ -17,29 +17,40 @@ use futures_core::ready;
 use h2::server::{Connection, SendResponse};
 use http::header::{HeaderValue, CONNECTION, CONTENT_LENGTH, DATE, TRANSFER_ENCODING};
 use log::{error, trace};
-use pin_project_lite::pin_project;
 
 use crate::{
     body::{AnyBody, BodySize, MessageBody},
     config::ServiceConfig,
+    metrics::{self, ConnectionGuard},
     service::HttpFlow,
-    OnConnectData, Payload, Request, Response, ResponseHead,
+    OnConnectData, Payload, Protocol, Request, Response, ResponseHead,
 };
 
 const CHUNK_SIZE: usize = 16_384;
 
-pin_project! {
-    /// Dispatcher for HTTP/2 protocol.
-    pub struct Dispatcher<T, S, B, X, U> {
-        flow: Rc<HttpFlow<S, X, U>>,
-        connection: Connection<T, Bytes>,
-        on_connect_data: OnConnectData,
-        config: ServiceConfig,
-        peer_addr: Option<net::SocketAddr>,
-        _phantom: PhantomData<B>,
-    }
+/// Dispatcher for HTTP/2 protocol.
+///
+/// With the `tracing` feature, the dispatcher opens a span for the connection, and enters the
+/// `tracing::Span` found in the extensions of a response, if any, while sending it.
+pub struct Dispatcher<T, S, B, X, U> {
+    flow: Rc<HttpFlow<S, X, U>>,
+    connection: Connection<T, Bytes>,
+    on_connect_data: OnConnectData,
+    config: ServiceConfig,
+    peer_addr: Option<net::SocketAddr>,
+    _connection: ConnectionGuard,
+
+    /// Span covering the connection, entered while the dispatcher is polled and while its
+    /// requests are handled.
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+
+    _phantom: PhantomData<B>,
 }
 
+// no fields are structurally pinned
+impl<T, S, B, X, U> Unpin for Dispatcher<T, S, B, X, U> {}
+
 impl<T, S, B, X, U> Dispatcher<T, S, B, X, U> {
     pub(crate) fn new(
         flow: Rc<HttpFlow<S, X, U>>,
@@ -54,6 +65,15 @@ impl<T, S, B, X, U> Dispatcher<T, S, B, X, U> {
             peer_addr,
             connection,
             on_connect_data,
+            _connection: ConnectionGuard::new(Protocol::Http2),
+
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!(
+                "http_connection",
+                protocol = "HTTP/2",
+                peer_addr = ?peer_addr
+            ),
+
             _phantom: PhantomData,
         }
     }
@@ -77,6 +97,9 @@ where
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.get_mut();
 
+        #[cfg(feature = "tracing")]
+        let _enter = this.span.enter();
+
         while let Some((req, tx)) =
             ready!(Pin::new(&mut this.connection).poll_accept(cx)?)
         {
@@ -92,6 +115,8 @@ where
             head.headers = parts.headers.into();
             head.peer_addr = this.peer_addr;
 
+            metrics::count_request(Protocol::Http2, false);
+
             // merge on_connect_ext data into request extensions
             this.on_connect_data.merge_into(&mut req);
 
@@ -99,13 +124,13 @@ where
             let config = this.config.clone();
 
             // multiplex request handling with spawn task
-            actix_rt::spawn(async move {
+            let task = async move {
                 // resolve service call and send response.
                 let res = match fut.await {
-                    Ok(res) => handle_response(res.into(), tx, config).await,
+                    Ok(res) => handle_traced_response(res.into(), tx, config).await,
                     Err(err) => {
                         let res: Response<AnyBody> = err.into();
-                        handle_response(res, tx, config).await
+                        handle_traced_response(res, tx, config).await
                     }
                 };
 
@@ -121,7 +146,12 @@ where
                         }
                     }
                 }
-            });
+            };
+
+            #[cfg(feature = "tracing")]
+            let task = tracing::Instrument::instrument(task, this.span.clone());
+
+            actix_rt::spawn(task);
         }
 
         Poll::Ready(Ok(()))
@@ -134,6 +164,29 @@ enum DispatchError {
     ResponseBody(Box<dyn StdError>),
 }
 
+/// Sends the response, within the span found in its extensions if tracing is enabled.
+async fn handle_traced_response<B>(
+    res: Response<B>,
+    tx: SendResponse<Bytes>,
+    config: ServiceConfig,
+) -> Result<(), DispatchError>
+where
+    B: MessageBody,
+    B::Error: Into<Box<dyn StdError>>,
+{
+    #[cfg(feature = "tracing")]
+    {
+        let span = res.extensions().get::<tracing::Span>().cloned();
+
+        if let Some(span) = span {
+            use tracing::Instrument as _;
+            return handle_response(res, tx, config).instrument(span).await;
+        }
+    }
+
+    handle_response(res, tx, config).await
+}
+
 async fn handle_response<B>(
     res: Response<B>,
     mut tx: SendResponse<Bytes>,
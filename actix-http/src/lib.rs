@@ -39,6 +39,7 @@ pub mod header;
 mod helpers;
 mod http_message;
 mod message;
+mod metrics;
 mod payload;
 mod request;
 mod response;
@@ -59,6 +60,7 @@ pub use self::header::ContentEncoding;
 pub use self::http_message::HttpMessage;
 pub use self::message::ConnectionType;
 pub use self::message::{Message, RequestHead, RequestHeadType, ResponseHead};
+pub use self::metrics::ConnectionMetrics;
 pub use self::payload::{Payload, PayloadStream};
 pub use self::request::Request;
 pub use self::response::Response;
@@ -0,0 +1,126 @@
+//! Process-wide connection metrics.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::Protocol;
+
+static H1_OPEN: AtomicUsize = AtomicUsize::new(0);
+static H2_OPEN: AtomicUsize = AtomicUsize::new(0);
+static H1_ACCEPTED: AtomicUsize = AtomicUsize::new(0);
+static H2_ACCEPTED: AtomicUsize = AtomicUsize::new(0);
+static H1_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+static H2_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+static KEEP_ALIVE_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Snapshot of the connections handled by the HTTP/1.1 and HTTP/2 dispatchers of this process.
+///
+/// Counters are shared by all servers and workers of the process.
+///
+/// # Examples
+/// ```
+/// use actix_http::ConnectionMetrics;
+///
+/// let metrics = ConnectionMetrics::snapshot();
+/// println!("{} open HTTP/2 connections", metrics.h2_open);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ConnectionMetrics {
+    /// Number of open HTTP/1.1 connections.
+    pub h1_open: usize,
+
+    /// Number of open HTTP/2 connections.
+    pub h2_open: usize,
+
+    /// Total number of HTTP/1.1 connections accepted.
+    pub h1_accepted: usize,
+
+    /// Total number of HTTP/2 connections accepted.
+    pub h2_accepted: usize,
+
+    /// Total number of requests received over HTTP/1.1.
+    pub h1_requests: usize,
+
+    /// Total number of requests received over HTTP/2.
+    pub h2_requests: usize,
+
+    /// Total number of HTTP/1.1 requests received on a connection that already served a request.
+    pub keep_alive_requests: usize,
+}
+
+impl ConnectionMetrics {
+    /// Returns the current values of the connection counters.
+    pub fn snapshot() -> ConnectionMetrics {
+        ConnectionMetrics {
+            h1_open: H1_OPEN.load(Ordering::Relaxed),
+            h2_open: H2_OPEN.load(Ordering::Relaxed),
+            h1_accepted: H1_ACCEPTED.load(Ordering::Relaxed),
+            h2_accepted: H2_ACCEPTED.load(Ordering::Relaxed),
+            h1_requests: H1_REQUESTS.load(Ordering::Relaxed),
+            h2_requests: H2_REQUESTS.load(Ordering::Relaxed),
+            keep_alive_requests: KEEP_ALIVE_REQUESTS.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Counts a connection as open for as long as it is alive.
+#[derive(Debug)]
+pub(crate) struct ConnectionGuard {
+    protocol: Protocol,
+}
+
+impl ConnectionGuard {
+    pub(crate) fn new(protocol: Protocol) -> Self {
+        let (open, accepted) = counters(protocol);
+        open.fetch_add(1, Ordering::Relaxed);
+        accepted.fetch_add(1, Ordering::Relaxed);
+
+        ConnectionGuard { protocol }
+    }
+}
+
+/// Counts a request received over `protocol`, on a reused connection if `keep_alive` is set.
+pub(crate) fn count_request(protocol: Protocol, keep_alive: bool) {
+    match protocol {
+        Protocol::Http2 => H2_REQUESTS.fetch_add(1, Ordering::Relaxed),
+        _ => H1_REQUESTS.fetch_add(1, Ordering::Relaxed),
+    };
+
+    if keep_alive {
+        KEEP_ALIVE_REQUESTS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        counters(self.protocol).0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Returns the open and accepted connection counters of `protocol`.
+fn counters(protocol: Protocol) -> (&'static AtomicUsize, &'static AtomicUsize) {
+    match protocol {
+        Protocol::Http2 => (&H2_OPEN, &H2_ACCEPTED),
+        _ => (&H1_OPEN, &H1_ACCEPTED),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_connections() {
+        let before = ConnectionMetrics::snapshot();
+
+        let guard = ConnectionGuard::new(Protocol::Http2);
+        count_request(Protocol::Http2, false);
+
+        let during = ConnectionMetrics::snapshot();
+        assert!(during.h2_accepted > before.h2_accepted);
+        assert!(during.h2_requests > before.h2_requests);
+        assert!(during.h2_open >= 1);
+
+        drop(guard);
+    }
+}
@@ -1,6 +1,6 @@
 //! Based on https://github.com/ibraheemdev/matchit/blob/master/benches/bench.rs
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
 
 macro_rules! register {
     (colon) => {{
@@ -190,5 +190,35 @@ fn compare_routers(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, compare_routers);
+fn router_scaling(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Router Scaling");
+
+    for &count in &[10, 100, 1_000] {
+        let mut router = actix_router::Router::<usize>::build();
+        for i in 0..count {
+            router.path(format!("/api/resource{}", i), i);
+            router.path(format!("/api/resource{}/{{id}}", i), i);
+            router.path(format!("/api/resource{}/{{id}}/items/{{item}}", i), i);
+        }
+        let router = router.finish();
+
+        // worst case for a linear scan; matches one of the last registered routes
+        let route = format!("/api/resource{}/123/items/456", count - 1);
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(count * 3),
+            &route,
+            |b, route| {
+                b.iter(|| {
+                    let mut path = actix_router::Path::new(route.as_str());
+                    black_box(router.recognize(&mut path).unwrap());
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, compare_routers, router_scaling);
 criterion_main!(benches);
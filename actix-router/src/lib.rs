@@ -5,11 +5,13 @@
 #![doc(html_favicon_url = "https://actix.rs/favicon.ico")]
 
 mod de;
+mod matcher;
 mod path;
 mod resource;
 mod router;
 
 pub use self::de::PathDeserializer;
+pub use self::matcher::{SegmentMatcher, SegmentMatchers};
 pub use self::path::Path;
 pub use self::resource::ResourceDef;
 pub use self::router::{ResourceInfo, Router, RouterBuilder};
@@ -0,0 +1,202 @@
+use std::{borrow::Cow, collections::HashMap, fmt, sync::Arc};
+
+const UNSIGNED: &str = "[0-9]+";
+const SIGNED: &str = "-?[0-9]+";
+const UUID: &str =
+    "[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}";
+const SLUG: &str = "[a-z0-9]+(?:-[a-z0-9]+)*";
+
+/// A named constraint for the values of dynamic segments.
+///
+/// Matchers are referenced by name from resource patterns using the `{segment:matcher}` syntax.
+/// A matcher consists of a regular expression that segment values must match and, optionally, a
+/// check function that matched values must also satisfy; for example, to reject numbers that do
+/// not fit in the target integer type.
+///
+/// The following matchers are built in:
+///
+/// | Name                                       | Matches                                     |
+/// |--------------------------------------------|---------------------------------------------|
+/// | `u8`, `u16`, `u32`, `u64`, `u128`, `usize` | Decimal digits that fit in the integer type |
+/// | `i8`, `i16`, `i32`, `i64`, `i128`, `isize` | Same as above, with an optional leading `-` |
+/// | `uuid`                                     | Hyphenated UUIDs, in either case            |
+/// | `slug`                                     | Lowercase alphanumeric words joined by `-`  |
+///
+/// Custom matchers can be registered in a [`SegmentMatchers`] registry.
+///
+/// # Examples
+/// ```
+/// use actix_router::{ResourceDef, SegmentMatcher, SegmentMatchers};
+///
+/// let resource = ResourceDef::new("/user/{id:u8}");
+/// assert!(resource.is_match("/user/255"));
+/// assert!(!resource.is_match("/user/256"));
+/// assert!(!resource.is_match("/user/abc"));
+///
+/// let mut matchers = SegmentMatchers::new();
+/// matchers.insert("even", SegmentMatcher::new("[0-9]*[02468]"));
+///
+/// let resource = ResourceDef::new("/page/{num:even}").with_matchers(&matchers);
+/// assert!(resource.is_match("/page/42"));
+/// assert!(!resource.is_match("/page/7"));
+/// ```
+#[derive(Clone)]
+pub struct SegmentMatcher {
+    regex: Cow<'static, str>,
+    check: Option<fn(&str) -> bool>,
+}
+
+impl SegmentMatcher {
+    /// Constructs a matcher for segment values matching `regex`.
+    ///
+    /// As with custom regex segments, care must be taken to avoid matching a slash `/`.
+    pub fn new(regex: impl Into<Cow<'static, str>>) -> Self {
+        SegmentMatcher {
+            regex: regex.into(),
+            check: None,
+        }
+    }
+
+    /// Sets a function that segment values matching the regex must also satisfy.
+    ///
+    /// Paths with segment values that fail the check are not matched by the resource.
+    pub fn check(mut self, check: fn(&str) -> bool) -> Self {
+        self.check = Some(check);
+        self
+    }
+
+    /// Returns the regular expression for segment values.
+    pub fn regex(&self) -> &str {
+        &self.regex
+    }
+
+    /// Returns `true` if this matcher has a check function.
+    pub(crate) fn has_check(&self) -> bool {
+        self.check.is_some()
+    }
+
+    /// Returns `true` if a value matched by the regex satisfies this matcher's check function.
+    pub(crate) fn check_value(&self, value: &str) -> bool {
+        self.check.map_or(true, |check| check(value))
+    }
+
+    /// Returns the built-in matcher with the given name.
+    fn builtin(name: &str) -> Option<Self> {
+        macro_rules! int {
+            ($ty:ty, $re:expr) => {
+                SegmentMatcher::new($re).check(|val| val.parse::<$ty>().is_ok())
+            };
+        }
+
+        Some(match name {
+            "u8" => int!(u8, UNSIGNED),
+            "u16" => int!(u16, UNSIGNED),
+            "u32" => int!(u32, UNSIGNED),
+            "u64" => int!(u64, UNSIGNED),
+            "u128" => int!(u128, UNSIGNED),
+            "usize" => int!(usize, UNSIGNED),
+            "i8" => int!(i8, SIGNED),
+            "i16" => int!(i16, SIGNED),
+            "i32" => int!(i32, SIGNED),
+            "i64" => int!(i64, SIGNED),
+            "i128" => int!(i128, SIGNED),
+            "isize" => int!(isize, SIGNED),
+            "uuid" => SegmentMatcher::new(UUID),
+            "slug" => SegmentMatcher::new(SLUG),
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Debug for SegmentMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SegmentMatcher")
+            .field("regex", &self.regex)
+            .field("check", &self.check.is_some())
+            .finish()
+    }
+}
+
+/// Registry of custom [`SegmentMatcher`]s, referenced by name from resource patterns.
+///
+/// Use [`ResourceDef::with_matchers`](crate::ResourceDef::with_matchers) to resolve the custom
+/// matchers of a resource definition. Built-in matchers are always available. Registering a
+/// matcher with the name of a built-in one replaces it.
+///
+/// Cloning a registry is cheap.
+#[derive(Clone, Debug, Default)]
+pub struct SegmentMatchers {
+    custom: Option<Arc<HashMap<String, SegmentMatcher>>>,
+}
+
+impl SegmentMatchers {
+    /// Constructs an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a custom matcher under `name`.
+    ///
+    /// Names should be valid identifiers, such as `hex_color`.
+    pub fn insert(&mut self, name: impl Into<String>, matcher: SegmentMatcher) {
+        let custom = self.custom.get_or_insert_with(Default::default);
+        Arc::make_mut(custom).insert(name.into(), matcher);
+    }
+
+    /// Returns the matcher registered under `name`, falling back to built-in matchers.
+    pub fn get(&self, name: &str) -> Option<SegmentMatcher> {
+        self.custom
+            .as_ref()
+            .and_then(|custom| custom.get(name).cloned())
+            .or_else(|| SegmentMatcher::builtin(name))
+    }
+
+    /// Returns `true` if no custom matchers are registered.
+    pub fn is_empty(&self) -> bool {
+        self.custom
+            .as_ref()
+            .map_or(true, |custom| custom.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_checks() {
+        let matchers = SegmentMatchers::new();
+
+        let u8_matcher = matchers.get("u8").unwrap();
+        assert!(u8_matcher.check_value("255"));
+        assert!(!u8_matcher.check_value("256"));
+
+        let i64_matcher = matchers.get("i64").unwrap();
+        assert!(i64_matcher.check_value("-9223372036854775808"));
+        assert!(!i64_matcher.check_value("9223372036854775808"));
+
+        assert!(matchers.get("uuid").unwrap().check_value("anything"));
+        assert!(matchers.get("float").is_none());
+    }
+
+    #[test]
+    fn custom_matchers() {
+        let mut matchers = SegmentMatchers::new();
+        assert!(matchers.is_empty());
+
+        matchers.insert("hex", SegmentMatcher::new("[0-9a-f]+"));
+        matchers.insert("u8", SegmentMatcher::new("[0-9]{3}"));
+        assert!(!matchers.is_empty());
+
+        assert_eq!(matchers.get("hex").unwrap().regex(), "[0-9a-f]+");
+        assert_eq!(matchers.get("u8").unwrap().regex(), "[0-9]{3}");
+        assert!(matchers.get("u8").unwrap().check_value("999"));
+        assert_eq!(matchers.get("u16").unwrap().regex(), UNSIGNED);
+
+        // matchers registered after cloning are not shared with the clone
+        let cloned = matchers.clone();
+        matchers.insert("oct", SegmentMatcher::new("[0-7]+"));
+        assert!(cloned.get("oct").is_none());
+        assert!(matchers.get("oct").is_some());
+    }
+}
@@ -106,6 +106,17 @@ impl<T: ResourcePath> Path<T> {
             .push((name.into(), PathItem::Static(value.into())));
     }
 
+    #[doc(hidden)]
+    pub fn insert_static(
+        &mut self,
+        index: usize,
+        name: impl Into<Cow<'static, str>>,
+        value: impl Into<Cow<'static, str>>,
+    ) {
+        self.segments
+            .insert(index, (name.into(), PathItem::Static(value.into())));
+    }
+
     /// Check if there are any matched patterns.
     #[inline]
     pub fn is_empty(&self) -> bool {
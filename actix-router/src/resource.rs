@@ -6,11 +6,11 @@ use std::{
 };
 
 use firestorm::{profile_fn, profile_method, profile_section};
-use regex::{escape, Regex, RegexSet};
+use regex::{escape, Captures, Regex, RegexSet};
 
 use crate::{
     path::{Path, PathItem},
-    IntoPatterns, Patterns, Resource, ResourcePath,
+    IntoPatterns, Patterns, Resource, ResourcePath, SegmentMatcher, SegmentMatchers,
 };
 
 const MAX_DYNAMIC_SEGMENTS: usize = 16;
@@ -159,6 +159,30 @@ const REGEX_FLAGS: &str = "(?s-m)";
 /// ```
 ///
 ///
+/// # Typed Segments
+/// Instead of a regex, dynamic segments can reference a named [`SegmentMatcher`] using the same
+/// syntax: `{name:matcher}`. For example, `/user/{id:u64}` will only match paths where the user ID
+/// is a number that fits in a `u64`. Paths with segment values rejected by a matcher are not
+/// matched, so routers fall through to the next resource.
+///
+/// Built-in matchers are listed in the [`SegmentMatcher`] docs. Custom matchers are resolved
+/// with [`with_matchers`][Self::with_matchers]. A segment pattern that does not name a matcher
+/// is treated as a regex.
+///
+/// ## Examples
+/// ```
+/// # use actix_router::ResourceDef;
+/// let resource = ResourceDef::new("/post/{slug:slug}");
+/// assert!(resource.is_match("/post/hello-world"));
+/// assert!(!resource.is_match("/post/Hello_World"));
+/// assert_eq!(resource.segment_matcher("slug"), Some("slug"));
+///
+/// let resource = ResourceDef::new("/user/{id:u64}");
+/// assert!(resource.is_match("/user/18446744073709551615"));
+/// assert!(!resource.is_match("/user/18446744073709551616"));
+/// ```
+///
+///
 /// # Tail Segments
 /// As a shortcut to defining a custom regex for matching _all_ remaining characters (not just those
 /// up until a `/` character), there is a special pattern to match (and capture) the remaining
@@ -229,6 +253,12 @@ pub struct ResourceDef {
 
     /// List of segments that compose the pattern, in order.
     segments: Vec<PatternSegment>,
+
+    /// Custom segment matchers that patterns were resolved with.
+    matchers: SegmentMatchers,
+
+    /// Named matchers constraining dynamic segments, for each regex of the pattern type.
+    constraints: Vec<Vec<SegmentConstraint>>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -240,6 +270,18 @@ enum PatternSegment {
     Var(String),
 }
 
+/// Dynamic segment constrained by a named matcher.
+#[derive(Clone, Debug)]
+struct SegmentConstraint {
+    /// Name of dynamic segment.
+    segment: String,
+
+    /// Name of matcher.
+    name: String,
+
+    matcher: SegmentMatcher,
+}
+
 #[derive(Clone, Debug)]
 #[allow(clippy::large_enum_variant)]
 enum PatternType {
@@ -279,7 +321,7 @@ impl ResourceDef {
     /// ```
     pub fn new<T: IntoPatterns>(paths: T) -> Self {
         profile_method!(new);
-        Self::new2(paths, false)
+        Self::new2(paths, false, SegmentMatchers::default())
     }
 
     /// Constructs a new resource definition using a pattern that performs prefix matching.
@@ -307,7 +349,7 @@ impl ResourceDef {
     /// ```
     pub fn prefix<T: IntoPatterns>(paths: T) -> Self {
         profile_method!(prefix);
-        ResourceDef::new2(paths, true)
+        ResourceDef::new2(paths, true, SegmentMatchers::default())
     }
 
     /// Constructs a new resource definition using a string pattern that performs prefix matching,
@@ -499,6 +541,86 @@ impl ResourceDef {
         }
     }
 
+    /// Returns iterator of dynamic segment names, in the order they appear in the pattern.
+    ///
+    /// For multi-pattern resources, the first pattern is used; the same one used to assemble
+    /// resource paths.
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix_router::ResourceDef;
+    /// let resource = ResourceDef::new("/user/{id}/post/{title:.*}");
+    /// assert_eq!(resource.segment_names().collect::<Vec<_>>(), ["id", "title"]);
+    ///
+    /// let resource = ResourceDef::new("/static/{tail}*");
+    /// assert_eq!(resource.segment_names().collect::<Vec<_>>(), ["tail"]);
+    /// ```
+    pub fn segment_names(&self) -> impl Iterator<Item = &str> {
+        self.segments.iter().filter_map(|segment| match segment {
+            PatternSegment::Const(_) => None,
+            PatternSegment::Var(name) => Some(name.as_str()),
+        })
+    }
+
+    /// Resolves the named segment matchers of this resource definition using a registry of custom
+    /// matchers, in addition to the built-in ones.
+    ///
+    /// The registry is kept so that [joined](Self::join) resources resolve matchers the same way.
+    ///
+    /// # Panics
+    /// Panics if a custom matcher's regex is malformed.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_router::{ResourceDef, SegmentMatcher, SegmentMatchers};
+    ///
+    /// let mut matchers = SegmentMatchers::new();
+    /// matchers.insert("lang", SegmentMatcher::new("[a-z]{2}"));
+    ///
+    /// let resource = ResourceDef::prefix("/{lang:lang}").with_matchers(&matchers);
+    /// assert!(resource.is_match("/en/about"));
+    /// assert!(!resource.is_match("/english/about"));
+    /// assert_eq!(resource.segment_matcher("lang"), Some("lang"));
+    /// ```
+    pub fn with_matchers(self, matchers: &SegmentMatchers) -> Self {
+        if matchers.is_empty() && self.matchers.is_empty() {
+            return self;
+        }
+
+        let mut rdef = Self::new2(self.patterns, self.is_prefix, matchers.clone());
+        rdef.id = self.id;
+        rdef.name = self.name;
+        rdef
+    }
+
+    /// Returns the name of the matcher constraining the dynamic segment `segment`, if any.
+    ///
+    /// Segments with custom regexes or the default pattern are unconstrained by matchers.
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix_router::ResourceDef;
+    /// let resource = ResourceDef::new(r"/user/{id:uuid}/post/{num:\d+}");
+    /// assert_eq!(resource.segment_matcher("id"), Some("uuid"));
+    /// assert_eq!(resource.segment_matcher("num"), None);
+    /// assert_eq!(resource.segment_matcher("user"), None);
+    /// ```
+    pub fn segment_matcher(&self, segment: &str) -> Option<&str> {
+        self.segment_matchers()
+            .find(|(name, _)| *name == segment)
+            .map(|(_, matcher)| matcher)
+    }
+
+    /// Returns iterator of dynamic segment names and the names of the matchers constraining them.
+    ///
+    /// For multi-pattern resources, segments constrained in more than one pattern are repeated.
+    pub fn segment_matchers(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.constraints
+            .iter()
+            .flatten()
+            .map(|constraint| (constraint.segment.as_str(), constraint.name.as_str()))
+    }
+
     /// Joins two resources.
     ///
     /// Resulting resource is prefix if `other` is prefix.
@@ -516,9 +638,15 @@ impl ResourceDef {
             .map(|(this, other)| [this, other].join(""))
             .collect::<Vec<_>>();
 
+        let matchers = if self.matchers.is_empty() {
+            other.matchers.clone()
+        } else {
+            self.matchers.clone()
+        };
+
         match patterns.len() {
-            1 => ResourceDef::new2(&patterns[0], other.is_prefix()),
-            _ => ResourceDef::new2(patterns, other.is_prefix()),
+            1 => ResourceDef::new2(&patterns[0], other.is_prefix(), matchers),
+            _ => ResourceDef::new2(patterns, other.is_prefix(), matchers),
         }
     }
 
@@ -563,6 +691,11 @@ impl ResourceDef {
         // `self.find_match(path).is_some()`
         // but this skips some checks and uses potentially faster regex methods
 
+        // captured values must be checked when matchers have check functions
+        if self.has_checks() {
+            return self.find_match(path).is_some();
+        }
+
         match &self.pat_type {
             PatternType::Static(pattern) => self.static_match(pattern, path).is_some(),
             PatternType::Dynamic(re, _) => re.is_match(path),
@@ -611,12 +744,18 @@ impl ResourceDef {
         match &self.pat_type {
             PatternType::Static(pattern) => self.static_match(pattern, path),
 
-            PatternType::Dynamic(re, _) => Some(re.captures(path)?[1].len()),
+            PatternType::Dynamic(re, _) => {
+                let captures = re.captures(path)?;
+                self.check_captures(0, &captures).then(|| captures[1].len())
+            }
 
             PatternType::DynamicSet(re, params) => {
-                let idx = re.matches(path).into_iter().next()?;
-                let (ref pattern, _) = params[idx];
-                Some(pattern.captures(path)?[1].len())
+                re.matches(path).into_iter().find_map(|idx| {
+                    let (ref pattern, _) = params[idx];
+                    let captures = pattern.captures(path)?;
+                    self.check_captures(idx, &captures)
+                        .then(|| captures[1].len())
+                })
             }
         }
     }
@@ -718,7 +857,7 @@ impl ResourceDef {
                     profile_section!(pattern_dynamic_regex_exec);
 
                     match re.captures(path.path()) {
-                        Some(captures) => captures,
+                        Some(captures) if self.check_captures(0, &captures) => captures,
                         _ => return false,
                     }
                 };
@@ -746,13 +885,15 @@ impl ResourceDef {
                 profile_section!(pattern_dynamic_set);
 
                 let path = path.path();
-                let (pattern, names) = match re.matches(path).into_iter().next() {
-                    Some(idx) => &params[idx],
-                    _ => return false,
-                };
-
-                let captures = match pattern.captures(path.path()) {
-                    Some(captures) => captures,
+                let matched = re.matches(path).into_iter().find_map(|idx| {
+                    let (pattern, names) = &params[idx];
+                    let captures = pattern.captures(path)?;
+                    self.check_captures(idx, &captures)
+                        .then(|| (names, captures))
+                });
+
+                let (names, captures) = match matched {
+                    Some(matched) => matched,
                     _ => return false,
                 };
 
@@ -883,31 +1024,65 @@ impl ResourceDef {
         }
     }
 
-    fn new2<T: IntoPatterns>(paths: T, is_prefix: bool) -> Self {
+    /// Returns true if any segment matcher has a check function for captured values.
+    fn has_checks(&self) -> bool {
+        self.constraints
+            .iter()
+            .flatten()
+            .any(|constraint| constraint.matcher.has_check())
+    }
+
+    /// Returns true if `captures`, from the regex at `idx`, satisfy the segment matchers' checks.
+    fn check_captures(&self, idx: usize, captures: &Captures<'_>) -> bool {
+        self.constraints.get(idx).map_or(true, |constraints| {
+            constraints.iter().all(|constraint| {
+                captures
+                    .name(&constraint.segment)
+                    .map_or(false, |m| constraint.matcher.check_value(m.as_str()))
+            })
+        })
+    }
+
+    /// Returns iterator of the constant, whole-segment prefix of each pattern.
+    ///
+    /// Any path matched by this resource starts with one of these prefixes followed by either a
+    /// path delimiter or end-of-input. Used by [`Router`](crate::Router) to index resources.
+    pub(crate) fn static_prefix_iter(&self) -> impl Iterator<Item = &str> {
+        self.pattern_iter().map(static_prefix)
+    }
+
+    fn new2<T: IntoPatterns>(paths: T, is_prefix: bool, matchers: SegmentMatchers) -> Self {
         profile_method!(new2);
 
         let patterns = paths.patterns();
-        let (pat_type, segments) = match &patterns {
-            Patterns::Single(pattern) => ResourceDef::parse(pattern, is_prefix, false),
+        let (pat_type, segments, constraints) = match &patterns {
+            Patterns::Single(pattern) => {
+                let (pat_type, segments, constraints) =
+                    ResourceDef::parse(pattern, is_prefix, false, &matchers);
+                (pat_type, segments, vec![constraints])
+            }
 
             // since zero length pattern sets are possible
             // just return a useless `ResourceDef`
             Patterns::List(patterns) if patterns.is_empty() => (
                 PatternType::DynamicSet(RegexSet::empty(), Vec::new()),
                 Vec::new(),
+                Vec::new(),
             ),
 
             Patterns::List(patterns) => {
                 let mut re_set = Vec::with_capacity(patterns.len());
                 let mut pattern_data = Vec::new();
                 let mut segments = None;
+                let mut constraints = Vec::with_capacity(patterns.len());
 
                 for pattern in patterns {
-                    match ResourceDef::parse(pattern, is_prefix, true) {
-                        (PatternType::Dynamic(re, names), segs) => {
+                    match ResourceDef::parse(pattern, is_prefix, true, &matchers) {
+                        (PatternType::Dynamic(re, names), segs, cons) => {
                             re_set.push(re.as_str().to_owned());
                             pattern_data.push((re, names));
                             segments.get_or_insert(segs);
+                            constraints.push(cons);
                         }
                         _ => unreachable!(),
                     }
@@ -919,6 +1094,7 @@ impl ResourceDef {
                 (
                     PatternType::DynamicSet(pattern_re_set, pattern_data),
                     segments,
+                    constraints,
                 )
             }
         };
@@ -930,6 +1106,8 @@ impl ResourceDef {
             is_prefix,
             pat_type,
             segments,
+            matchers,
+            constraints,
         }
     }
 
@@ -940,10 +1118,20 @@ impl ResourceDef {
     /// - the segment's regex to check values against
     /// - the remaining, unprocessed string slice
     /// - whether the parsed parameter represents a tail pattern
+    /// - the named matcher constraining the segment, if any
     ///
     /// # Panics
     /// Panics if given patterns does not contain a dynamic segment.
-    fn parse_param(pattern: &str) -> (PatternSegment, String, &str, bool) {
+    fn parse_param<'a>(
+        pattern: &'a str,
+        matchers: &SegmentMatchers,
+    ) -> (
+        PatternSegment,
+        String,
+        &'a str,
+        bool,
+        Option<SegmentConstraint>,
+    ) {
         profile_method!(parse_param);
 
         const DEFAULT_PATTERN: &str = "[^/]+";
@@ -996,10 +1184,21 @@ impl ResourceDef {
             ),
         };
 
+        let constraint = matchers.get(pattern).map(|matcher| SegmentConstraint {
+            segment: name.to_owned(),
+            name: pattern.to_owned(),
+            matcher,
+        });
+
+        let pattern = match &constraint {
+            Some(constraint) => constraint.matcher.regex(),
+            None => pattern,
+        };
+
         let segment = PatternSegment::Var(name.to_string());
         let regex = format!(r"(?P<{}>{})", &name, &pattern);
 
-        (segment, regex, unprocessed, tail)
+        (segment, regex, unprocessed, tail, constraint)
     }
 
     /// Parse `pattern` using `is_prefix` and `force_dynamic` flags.
@@ -1008,15 +1207,18 @@ impl ResourceDef {
     /// - `is_prefix`: Use `true` if `pattern` should be treated as a prefix; i.e., a conforming
     ///   path will be a match even if it has parts remaining to process
     /// - `force_dynamic`: Use `true` to disallow the return of static and prefix segments.
+    /// - `matchers`: Custom matchers that segment patterns can name.
     ///
     /// The returned tuple includes:
     /// - the pattern type detected, either `Static`, `Prefix`, or `Dynamic`
     /// - a list of segment descriptors from the pattern
+    /// - a list of segments constrained by named matchers
     fn parse(
         pattern: &str,
         is_prefix: bool,
         force_dynamic: bool,
-    ) -> (PatternType, Vec<PatternSegment>) {
+        matchers: &SegmentMatchers,
+    ) -> (PatternType, Vec<PatternSegment>, Vec<SegmentConstraint>) {
         profile_method!(parse);
 
         if !force_dynamic && pattern.find('{').is_none() && !pattern.ends_with('*') {
@@ -1024,11 +1226,13 @@ impl ResourceDef {
             return (
                 PatternType::Static(pattern.to_owned()),
                 vec![PatternSegment::Const(pattern.to_owned())],
+                Vec::new(),
             );
         }
 
         let mut unprocessed = pattern;
         let mut segments = Vec::new();
+        let mut constraints = Vec::new();
         let mut re = format!("{}^", REGEX_FLAGS);
         let mut dyn_segment_count = 0;
         let mut has_tail_segment = false;
@@ -1039,12 +1243,15 @@ impl ResourceDef {
             segments.push(PatternSegment::Const(prefix.to_owned()));
             re.push_str(&escape(prefix));
 
-            let (param_pattern, re_part, rem, tail) = Self::parse_param(rem);
+            let (param_pattern, re_part, rem, tail, constraint) =
+                Self::parse_param(rem, matchers);
 
             if tail {
                 has_tail_segment = true;
             }
 
+            constraints.extend(constraint);
+
             segments.push(param_pattern);
             re.push_str(&re_part);
 
@@ -1120,7 +1327,7 @@ impl ResourceDef {
             .filter_map(|name| name.map(|name| Box::leak(Box::new(name.to_owned())).as_str()))
             .collect();
 
-        (PatternType::Dynamic(re, names), segments)
+        (PatternType::Dynamic(re, names), segments, constraints)
     }
 }
 
@@ -1163,6 +1370,23 @@ pub(crate) fn insert_slash(path: &str) -> Cow<'_, str> {
     }
 }
 
+/// Returns the part of `pattern` made up of whole constant segments.
+///
+/// Static patterns are returned unchanged. Dynamic patterns are cut at the last path delimiter
+/// before their first dynamic segment.
+pub(crate) fn static_prefix(pattern: &str) -> &str {
+    let dyn_idx = match pattern.find('{') {
+        Some(idx) => idx,
+        None if pattern.ends_with('*') => pattern.len() - 1,
+        None => return pattern,
+    };
+
+    match pattern[..dyn_idx].rfind('/') {
+        Some(idx) => &pattern[..idx],
+        None => "",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1623,6 +1847,105 @@ mod tests {
         assert!(path.get("uid").is_some());
     }
 
+    #[test]
+    fn typed_segments() {
+        let re = ResourceDef::new("/user/{id:u64}/post/{post:uuid}");
+        assert!(re.is_match("/user/42/post/67e55044-10b1-426f-9247-bb680e5fe0c8"));
+        assert!(!re.is_match("/user/42/post/67e55044"));
+        assert!(!re.is_match("/user/abc/post/67e55044-10b1-426f-9247-bb680e5fe0c8"));
+        assert!(!re.is_match("/user/-1/post/67e55044-10b1-426f-9247-bb680e5fe0c8"));
+        assert_eq!(re.segment_matcher("id"), Some("u64"));
+        assert_eq!(re.segment_matcher("post"), Some("uuid"));
+
+        let mut path = Path::new("/user/42/post/67E55044-10B1-426F-9247-BB680E5FE0C8");
+        assert!(re.capture_match_info(&mut path));
+        assert_eq!(path.get("id").unwrap(), "42");
+        assert_eq!(
+            path.get("post").unwrap(),
+            "67E55044-10B1-426F-9247-BB680E5FE0C8"
+        );
+
+        // values that match the regex but fail the check are not matched
+        let re = ResourceDef::prefix("/page/{num:i8}");
+        assert!(re.is_match("/page/-128/next"));
+        assert_eq!(re.find_match("/page/127/next"), Some(9));
+        assert!(!re.is_match("/page/128/next"));
+        assert_eq!(re.find_match("/page/128/next"), None);
+
+        let mut path = Path::new("/page/128/next");
+        assert!(!re.capture_match_info(&mut path));
+        assert_eq!(path.unprocessed(), "/page/128/next");
+
+        let re = ResourceDef::new("/post/{slug:slug}");
+        assert!(re.is_match("/post/hello-world-2"));
+        assert!(!re.is_match("/post/hello--world"));
+        assert!(!re.is_match("/post/-hello"));
+        assert!(!re.is_match("/post/Hello"));
+
+        // regex segments are not constrained by matchers
+        let re = ResourceDef::new(r"/{id:\d+}");
+        assert_eq!(re.segment_matcher("id"), None);
+        assert_eq!(re.segment_matchers().count(), 0);
+    }
+
+    #[test]
+    fn typed_segments_multi_pattern() {
+        let re = ResourceDef::new(["/item/{id:u8}", "/item/{id:slug}", "/item/{id}/edit"]);
+        assert_eq!(
+            re.segment_matchers().collect::<Vec<_>>(),
+            vec![("id", "u8"), ("id", "slug")]
+        );
+        assert_eq!(re.segment_matcher("id"), Some("u8"));
+
+        // fails check of first pattern but matches second
+        assert!(re.is_match("/item/300"));
+        assert_eq!(re.find_match("/item/300"), Some(9));
+
+        let mut path = Path::new("/item/300");
+        assert!(re.capture_match_info(&mut path));
+        assert_eq!(path.get("id").unwrap(), "300");
+
+        assert!(re.is_match("/item/ABC/edit"));
+        assert!(!re.is_match("/item/ABC"));
+    }
+
+    #[test]
+    fn custom_segment_matchers() {
+        let mut matchers = SegmentMatchers::new();
+        matchers.insert(
+            "even",
+            SegmentMatcher::new("[0-9]+")
+                .check(|val| val.ends_with(&['0', '2', '4', '6', '8'][..])),
+        );
+
+        // unregistered names are treated as regexes
+        let re = ResourceDef::new("/num/{n:even}");
+        assert!(re.is_match("/num/even"));
+        assert_eq!(re.segment_matcher("n"), None);
+
+        let mut re = re;
+        re.set_id(7);
+        re.set_name("num");
+        let re = re.with_matchers(&matchers);
+        assert_eq!(re.id(), 7);
+        assert_eq!(re.name(), Some("num"));
+        assert_eq!(re.segment_matcher("n"), Some("even"));
+        assert!(re.is_match("/num/42"));
+        assert!(!re.is_match("/num/43"));
+        assert!(!re.is_match("/num/even"));
+
+        // joined resources resolve custom matchers
+        let scope = ResourceDef::prefix("/scope/{s:even}").with_matchers(&matchers);
+        let joined = scope.join(&ResourceDef::new("/num/{n:even}"));
+        assert!(joined.is_match("/scope/2/num/4"));
+        assert!(!joined.is_match("/scope/2/num/5"));
+        assert!(!joined.is_match("/scope/3/num/4"));
+        assert_eq!(
+            joined.segment_matchers().collect::<Vec<_>>(),
+            vec![("s", "even"), ("n", "even")]
+        );
+    }
+
     #[test]
     fn dynamic_prefix_proper_segmentation() {
         let resource = ResourceDef::prefix(r"/id/{id:\d{3}}");
@@ -1673,6 +1996,23 @@ mod tests {
         assert_eq!(s, "/user/item");
     }
 
+    #[test]
+    fn static_prefixes() {
+        assert_eq!(static_prefix(""), "");
+        assert_eq!(static_prefix("/"), "/");
+        assert_eq!(static_prefix("/user/"), "/user/");
+        assert_eq!(static_prefix("/user/{id}"), "/user");
+        assert_eq!(static_prefix("/user/{id}/stars"), "/user");
+        assert_eq!(static_prefix("/user-{id}"), "");
+        assert_eq!(static_prefix("/{id}"), "");
+        assert_eq!(static_prefix("/a/b/{tail}*"), "/a/b");
+        assert_eq!(static_prefix(r"/a/{id:\d{2}}"), "/a");
+
+        let resource = ResourceDef::new(["/user/{id}", "/profile", "/{id}/stars"]);
+        let prefixes = resource.static_prefix_iter().collect::<Vec<_>>();
+        assert_eq!(prefixes, ["/user", "/profile", ""]);
+    }
+
     #[test]
     fn prefix_trailing_slash() {
         // The prefix "/abc/" matches two segments: ["user", ""]
@@ -12,10 +12,16 @@ pub struct ResourceInfo {
 }
 
 /// Resource router.
+///
+/// Routes are indexed in a segment tree by the constant segments their patterns start with. Only
+/// routes whose constant prefix matches the start of a path are tried when recognizing it, which
+/// keeps lookups fast as the number of routes grows. Candidates are always checked in
+/// registration order so the first matching route wins, even when patterns overlap.
 // T is the resource itself
 // U is any other data needed for routing like method guards
 pub struct Router<T, U = ()> {
     routes: Vec<(ResourceDef, T, Option<U>)>,
+    tree: Node,
 }
 
 impl<T, U> Router<T, U> {
@@ -32,7 +38,9 @@ impl<T, U> Router<T, U> {
     {
         profile_method!(recognize);
 
-        for item in self.routes.iter() {
+        for &idx in self.tree.candidates(resource.resource_path().path()) {
+            let item = &self.routes[idx];
+
             if item.0.capture_match_info(resource.resource_path()) {
                 return Some((&item.1, ResourceId(item.0.id())));
             }
@@ -48,13 +56,16 @@ impl<T, U> Router<T, U> {
     {
         profile_method!(recognize_mut);
 
-        for item in self.routes.iter_mut() {
-            if item.0.capture_match_info(resource.resource_path()) {
-                return Some((&mut item.1, ResourceId(item.0.id())));
-            }
-        }
+        let routes = &self.routes;
+        let idx = self
+            .tree
+            .candidates(resource.resource_path().path())
+            .iter()
+            .copied()
+            .find(|&idx| routes[idx].0.capture_match_info(resource.resource_path()))?;
 
-        None
+        let item = &mut self.routes[idx];
+        Some((&mut item.1, ResourceId(item.0.id())))
     }
 
     pub fn recognize_fn<R, P, F>(&self, resource: &mut R, check: F) -> Option<(&T, ResourceId)>
@@ -65,7 +76,9 @@ impl<T, U> Router<T, U> {
     {
         profile_method!(recognize_checked);
 
-        for item in self.routes.iter() {
+        for &idx in self.tree.candidates(resource.resource_path().path()) {
+            let item = &self.routes[idx];
+
             if item.0.capture_match_info_fn(resource, &check, &item.2) {
                 return Some((&item.1, ResourceId(item.0.id())));
             }
@@ -74,26 +87,63 @@ impl<T, U> Router<T, U> {
         None
     }
 
-    pub fn recognize_mut_fn<R, P, F>(
-        &mut self,
+    /// Recognizes `resource` like [`recognize_fn`](Self::recognize_fn), calling `inspect` with
+    /// each candidate tried, in order, and whether it was accepted.
+    ///
+    /// A candidate is accepted if its pattern matches and `check` returns `true` for it.
+    pub fn recognize_fn_inspect<R, P, F, I>(
+        &self,
         resource: &mut R,
         check: F,
-    ) -> Option<(&mut T, ResourceId)>
+        mut inspect: I,
+    ) -> Option<(&T, ResourceId)>
     where
         F: Fn(&R, &Option<U>) -> bool,
+        I: FnMut(&ResourceDef, bool),
         R: Resource<P>,
         P: ResourcePath,
     {
-        profile_method!(recognize_mut_checked);
+        profile_method!(recognize_fn_inspect);
 
-        for item in self.routes.iter_mut() {
-            if item.0.capture_match_info_fn(resource, &check, &item.2) {
-                return Some((&mut item.1, ResourceId(item.0.id())));
+        for &idx in self.tree.candidates(resource.resource_path().path()) {
+            let item = &self.routes[idx];
+            let accepted = item.0.capture_match_info_fn(resource, &check, &item.2);
+            inspect(&item.0, accepted);
+
+            if accepted {
+                return Some((&item.1, ResourceId(item.0.id())));
             }
         }
 
         None
     }
+
+    pub fn recognize_mut_fn<R, P, F>(
+        &mut self,
+        resource: &mut R,
+        check: F,
+    ) -> Option<(&mut T, ResourceId)>
+    where
+        F: Fn(&R, &Option<U>) -> bool,
+        R: Resource<P>,
+        P: ResourcePath,
+    {
+        profile_method!(recognize_mut_checked);
+
+        let routes = &self.routes;
+        let idx = self
+            .tree
+            .candidates(resource.resource_path().path())
+            .iter()
+            .copied()
+            .find(|&idx| {
+                let item = &routes[idx];
+                item.0.capture_match_info_fn(resource, &check, &item.2)
+            })?;
+
+        let item = &mut self.routes[idx];
+        Some((&mut item.1, ResourceId(item.0.id())))
+    }
 }
 
 pub struct RouterBuilder<T, U = ()> {
@@ -132,11 +182,104 @@ impl<T, U> RouterBuilder<T, U> {
     }
 
     /// Finish configuration and create router instance.
+    ///
+    /// This compiles the registered resource definitions into the segment tree used for lookups.
     pub fn finish(self) -> Router<T, U> {
+        profile_method!(finish);
+
+        let mut tree = Node::default();
+
+        for (idx, (rdef, _, _)) in self.resources.iter().enumerate() {
+            for prefix in rdef.static_prefix_iter() {
+                tree.insert(prefix, idx);
+            }
+        }
+
+        tree.propagate(&[]);
+
         Router {
             routes: self.resources,
+            tree,
+        }
+    }
+}
+
+/// Segment tree node.
+///
+/// Each node represents a sequence of constant path segments. Routes are stored at the node
+/// matching the constant prefix of their pattern(s).
+#[derive(Debug, Default)]
+struct Node {
+    /// Child nodes keyed by the next path segment; kept sorted for binary search.
+    children: Vec<(String, Node)>,
+
+    /// Indices of routes that may match a path reaching this node, in registration order.
+    ///
+    /// After [`propagate`](Self::propagate) is called, this includes the routes of all
+    /// ancestor nodes.
+    routes: Vec<usize>,
+}
+
+impl Node {
+    /// Registers route index `idx` under the node for the constant prefix `prefix`.
+    fn insert(&mut self, prefix: &str, idx: usize) {
+        let mut node = self;
+
+        // empty prefix matches every path and so lives on the root node
+        if !prefix.is_empty() {
+            for segment in prefix.split('/') {
+                let pos = match node
+                    .children
+                    .binary_search_by(|(seg, _)| seg.as_str().cmp(segment))
+                {
+                    Ok(pos) => pos,
+                    Err(pos) => {
+                        node.children
+                            .insert(pos, (segment.to_owned(), Node::default()));
+                        pos
+                    }
+                };
+
+                node = &mut node.children[pos].1;
+            }
+        }
+
+        // multi-pattern resources may insert the same prefix more than once
+        if node.routes.last() != Some(&idx) {
+            node.routes.push(idx);
+        }
+    }
+
+    /// Merges route indices of ancestor nodes into every node so that lookups only need to
+    /// inspect the deepest node reached by a path.
+    fn propagate(&mut self, inherited: &[usize]) {
+        if !inherited.is_empty() {
+            self.routes.extend_from_slice(inherited);
+            self.routes.sort_unstable();
+            self.routes.dedup();
+        }
+
+        for (_, child) in self.children.iter_mut() {
+            child.propagate(&self.routes);
         }
     }
+
+    /// Returns indices of routes that may match `path`, in registration order.
+    fn candidates(&self, path: &str) -> &[usize] {
+        let mut node = self;
+
+        for segment in path.split('/') {
+            match node
+                .children
+                .binary_search_by(|(seg, _)| seg.as_str().cmp(segment))
+            {
+                Ok(pos) => node = &node.children[pos].1,
+                Err(_) => break,
+            }
+        }
+
+        &node.routes
+    }
 }
 
 #[cfg(test)]
@@ -278,4 +421,97 @@ mod tests {
         assert_eq!(*h, 11);
         assert_eq!(&path["val"], "ttt");
     }
+
+    #[test]
+    fn test_recognizer_registration_order() {
+        let mut router = Router::<usize>::build();
+        router.path("/{a}/{b}", 10);
+        router.path("/user/{id}", 11);
+        router.path("/user/profile", 12);
+        router.prefix("/user", 13);
+        router.path("/user/{id}/stars", 14);
+        let mut router = router.finish();
+
+        // earlier dynamic route at root shadows later, more specific, routes
+        let mut path = Path::new("/user/profile");
+        let (h, _) = router.recognize_mut(&mut path).unwrap();
+        assert_eq!(*h, 10);
+        assert_eq!(&path["a"], "user");
+        assert_eq!(&path["b"], "profile");
+
+        let mut path = Path::new("/user/123/stars");
+        let (h, _) = router.recognize_mut(&mut path).unwrap();
+        assert_eq!(*h, 13);
+
+        let mut path = Path::new("/user");
+        let (h, _) = router.recognize_mut(&mut path).unwrap();
+        assert_eq!(*h, 13);
+
+        let mut path = Path::new("/users/123/stars");
+        assert!(router.recognize_mut(&mut path).is_none());
+
+        let mut router = Router::<usize>::build();
+        router.path("/user/profile", 10);
+        router.path("/user/{id}", 11);
+        router.path("/{a}/{b}", 12);
+        let router = router.finish();
+
+        let mut path = Path::new("/user/profile");
+        assert_eq!(*router.recognize(&mut path).unwrap().0, 10);
+
+        let mut path = Path::new("/user/123");
+        assert_eq!(*router.recognize(&mut path).unwrap().0, 11);
+
+        let mut path = Path::new("/users/123");
+        assert_eq!(*router.recognize(&mut path).unwrap().0, 12);
+    }
+
+    #[test]
+    fn test_recognizer_multi_pattern_and_empty_prefix() {
+        let mut router = Router::<usize>::build();
+        router.path(["/a/{x}", "/b/{x}", "/a/c/{x}"], 10);
+        router.path("/a/c/d", 11);
+        router.prefix("", 12);
+        let router = router.finish();
+
+        let mut path = Path::new("/b/1");
+        assert_eq!(*router.recognize(&mut path).unwrap().0, 10);
+        assert_eq!(&path["x"], "1");
+
+        let mut path = Path::new("/a/c/d");
+        assert_eq!(*router.recognize(&mut path).unwrap().0, 10);
+        assert_eq!(&path["x"], "d");
+
+        let mut path = Path::new("/a/c/d/e");
+        assert_eq!(*router.recognize(&mut path).unwrap().0, 12);
+
+        let mut path = Path::new("");
+        assert_eq!(*router.recognize(&mut path).unwrap().0, 12);
+
+        // empty prefix still requires a segment boundary
+        let mut path = Path::new("relative/path");
+        assert!(router.recognize(&mut path).is_none());
+    }
+
+    #[test]
+    fn test_recognizer_fn_checks_candidates_in_order() {
+        let mut router = Router::<usize, bool>::build();
+        router.path("/{id}", 10).2 = Some(false);
+        router.path("/item", 11).2 = Some(true);
+        router.path("/{id}", 12).2 = Some(true);
+        let router = router.finish();
+
+        let mut path = Path::new("/item");
+        let (h, _) = router
+            .recognize_fn(&mut path, |_, allowed| allowed.unwrap())
+            .unwrap();
+        assert_eq!(*h, 11);
+
+        let mut path = Path::new("/other");
+        let (h, _) = router
+            .recognize_fn(&mut path, |_, allowed| allowed.unwrap())
+            .unwrap();
+        assert_eq!(*h, 12);
+        assert_eq!(&path["id"], "other");
+    }
 }
@@ -319,6 +319,11 @@ impl ToTokens for Route {
             }
         };
 
+        let (summary, description) = doc_summary(doc_attributes);
+        let summary = summary.map(|summary| quote! { __operation.summary(#summary); });
+        let description =
+            description.map(|description| quote! { __operation.description(#description); });
+
         let stream = quote! {
             #(#doc_attributes)*
             #[allow(non_camel_case_types, missing_docs)]
@@ -332,7 +337,15 @@ impl ToTokens for Route {
                         #method_guards
                         #(.guard(actix_web::guard::fn_guard(#guards)))*
                         #(.wrap(#wrappers))*
-                        .#resource_type(#name);
+                        .route(
+                            actix_web::Route::new()
+                                .#resource_type(#name)
+                                .operation(|__operation| {
+                                    __operation.operation_id(#resource_name);
+                                    #summary
+                                    #description
+                                })
+                        );
 
                     actix_web::dev::HttpServiceFactory::register(__resource, __config)
                 }
@@ -343,6 +356,38 @@ impl ToTokens for Route {
     }
 }
 
+/// Splits the text of doc comments into the first line, used as summary of the handler's
+/// operation, and the full text, used as description when it has more than one line.
+fn doc_summary(doc_attributes: &[syn::Attribute]) -> (Option<String>, Option<String>) {
+    let lines = doc_attributes
+        .iter()
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(syn::Meta::NameValue(syn::MetaNameValue {
+                lit: syn::Lit::Str(lit),
+                ..
+            })) => Some(lit.value()),
+            _ => None,
+        })
+        .flat_map(|doc| {
+            doc.split('\n')
+                .map(|line| line.strip_prefix(' ').unwrap_or(line).trim_end().to_owned())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let text = lines.join("\n");
+    let text = text.trim();
+
+    let summary = text.lines().next().map(ToOwned::to_owned);
+    let description = if text.lines().nth(1).is_some() {
+        Some(text.to_owned())
+    } else {
+        None
+    };
+
+    (summary, description)
+}
+
 pub(crate) fn with_method(
     method: Option<MethodType>,
     args: TokenStream,
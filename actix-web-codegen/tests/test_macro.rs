@@ -8,6 +8,7 @@ use actix_web::{
         header::{HeaderName, HeaderValue},
         StatusCode,
     },
+    openapi::OpenApi,
     web, App, Error, HttpResponse, Responder,
 };
 use actix_web_codegen::{connect, delete, get, head, options, patch, post, put, route, trace};
@@ -268,3 +269,46 @@ async fn test_wrap() {
     let body = String::from_utf8(body.to_vec()).unwrap();
     assert!(body.contains("wrong number of parameters"));
 }
+
+/// Fetch a documented item.
+///
+/// Longer description of
+/// the documented item.
+#[get("/documented/{id}")]
+async fn documented(_: web::Path<u32>) -> impl Responder {
+    HttpResponse::Ok()
+}
+
+#[actix_rt::test]
+async fn test_openapi_operation() {
+    let rmap = actix_web::test::init_resource_map(
+        App::new()
+            .service(documented)
+            .service(route_test)
+            .service(custom_resource_name_test),
+    )
+    .await;
+    let doc = OpenApi::new("Test", "1.0.0").document(&rmap);
+
+    let op = &doc["paths"]["/documented/{id}"]["get"];
+    assert_eq!(op["operationId"], "documented");
+    assert_eq!(op["summary"], "Fetch a documented item.");
+    assert_eq!(
+        op["description"],
+        "Fetch a documented item.\n\nLonger description of\nthe documented item."
+    );
+    assert_eq!(op["parameters"][0]["schema"]["type"], "integer");
+
+    assert_eq!(
+        doc["paths"]["/multi"]["get"]["operationId"],
+        "route_test_get"
+    );
+    assert_eq!(
+        doc["paths"]["/multi"]["post"]["operationId"],
+        "route_test_post"
+    );
+    assert!(doc["paths"]["/multi"]["get"].get("summary").is_none());
+
+    let op = &doc["paths"]["/custom_resource_name"]["get"];
+    assert_eq!(op["operationId"], "custom");
+}
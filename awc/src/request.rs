@@ -56,14 +56,20 @@ pub struct ClientRequest {
 
 impl ClientRequest {
     /// Create new client request builder.
+    ///
+    /// Headers propagated from the request being handled on this thread, such as its ID, are
+    /// added to the request.
     pub(crate) fn new<U>(method: Method, uri: U, config: ClientConfig) -> Self
     where
         Uri: TryFrom<U>,
         <Uri as TryFrom<U>>::Error: Into<HttpError>,
     {
+        let mut head = RequestHead::default();
+        head.headers = actix_http::client::propagated_headers();
+
         ClientRequest {
             config,
-            head: RequestHead::default(),
+            head,
             err: None,
             addr: None,
             #[cfg(feature = "cookies")]
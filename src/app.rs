@@ -15,7 +15,7 @@ use futures_util::future::FutureExt as _;
 use crate::app_service::{AppEntry, AppInit, AppRoutingFactory};
 use crate::config::ServiceConfig;
 use crate::data::{Data, DataFactory, FnDataFactory};
-use crate::dev::ResourceDef;
+use crate::dev::{ResourceDef, SegmentMatcher, SegmentMatchers};
 use crate::error::Error;
 use crate::resource::Resource;
 use crate::route::Route;
@@ -35,6 +35,8 @@ pub struct App<T, B> {
     factory_ref: Rc<RefCell<Option<AppRoutingFactory>>>,
     data_factories: Vec<FnDataFactory>,
     external: Vec<ResourceDef>,
+    segment_matchers: SegmentMatchers,
+    debug_routing: bool,
     extensions: Extensions,
     _phantom: PhantomData<B>,
 }
@@ -52,6 +54,8 @@ impl App<AppEntry, Body> {
             default: None,
             factory_ref,
             external: Vec::new(),
+            segment_matchers: SegmentMatchers::new(),
+            debug_routing: false,
             extensions: Extensions::new(),
             _phantom: PhantomData,
         }
@@ -306,6 +310,56 @@ where
         self
     }
 
+    /// Registers a custom segment matcher that resource patterns can reference by name.
+    ///
+    /// Dynamic segments written as `{segment:name}` only match values accepted by the matcher.
+    /// When a value is rejected, routing falls through to the next matching resource rather than
+    /// failing later during path extraction. Built-in matchers, such as `u64`, `uuid` and `slug`,
+    /// are always available; see [`SegmentMatcher`] for the full list.
+    ///
+    /// Matchers apply to all resources and scopes of the app, regardless of registration order.
+    ///
+    /// ```
+    /// use actix_web::{dev::SegmentMatcher, web, App, HttpResponse};
+    ///
+    /// let app = App::new()
+    ///     .segment_matcher("lang", SegmentMatcher::new("en|fr|de"))
+    ///     .route("/{lang:lang}/about", web::get().to(HttpResponse::Ok))
+    ///     .route("/user/{id:u64}", web::get().to(HttpResponse::Ok));
+    /// ```
+    pub fn segment_matcher(mut self, name: impl Into<String>, matcher: SegmentMatcher) -> Self {
+        self.segment_matchers.insert(name, matcher);
+        self
+    }
+
+    /// Enables routing diagnostics.
+    ///
+    /// When enabled, the app, its scopes and resources record the candidates they try for each
+    /// request: which patterns did not match, which guard rejected the request and which candidate
+    /// matched. The record is stored in request extensions as a [`RoutingTrace`] and is available
+    /// to handlers, middleware and custom default services. The built-in *404 Not Found* and
+    /// *405 Method Not Allowed* responses include it in their body.
+    ///
+    /// Diagnostics are meant for development; the explanations reveal the app's routing table.
+    ///
+    /// ```
+    /// use actix_web::{guard, web, App, HttpResponse};
+    ///
+    /// let app = App::new()
+    ///     .debug_routing(cfg!(debug_assertions))
+    ///     .service(
+    ///         web::scope("/api")
+    ///             .guard(guard::Header("x-api-version", "2"))
+    ///             .route("/users", web::get().to(HttpResponse::Ok)),
+    ///     );
+    /// ```
+    ///
+    /// [`RoutingTrace`]: crate::dev::RoutingTrace
+    pub fn debug_routing(mut self, enabled: bool) -> Self {
+        self.debug_routing = enabled;
+        self
+    }
+
     /// Register an external resource.
     ///
     /// External resources are useful for URL generation purposes only
@@ -399,6 +453,8 @@ where
             default: self.default,
             factory_ref: self.factory_ref,
             external: self.external,
+            segment_matchers: self.segment_matchers,
+            debug_routing: self.debug_routing,
             extensions: self.extensions,
             _phantom: PhantomData,
         }
@@ -460,6 +516,8 @@ where
             default: self.default,
             factory_ref: self.factory_ref,
             external: self.external,
+            segment_matchers: self.segment_matchers,
+            debug_routing: self.debug_routing,
             extensions: self.extensions,
             _phantom: PhantomData,
         }
@@ -484,6 +542,8 @@ where
             endpoint: self.endpoint,
             services: Rc::new(RefCell::new(self.services)),
             external: RefCell::new(self.external),
+            segment_matchers: self.segment_matchers,
+            debug_routing: self.debug_routing,
             default: self.default,
             factory_ref: self.factory_ref,
             extensions: RefCell::new(Some(self.extensions)),
@@ -690,6 +750,46 @@ mod tests {
         );
     }
 
+    #[actix_rt::test]
+    async fn test_segment_matchers() {
+        let srv = init_service(
+            App::new()
+                .segment_matcher("even", SegmentMatcher::new("[0-9]*[02468]"))
+                .route(
+                    "/user/{id:u8}",
+                    web::get().to(|id: web::Path<u8>| HttpResponse::Ok().body(id.to_string())),
+                )
+                .route("/user/{name}", web::get().to(HttpResponse::Accepted))
+                .service(
+                    web::scope("/num/{n:even}")
+                        .route("/{m:even}", web::get().to(HttpResponse::Ok)),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/user/255").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"255"));
+
+        // out of range values fall through to the next resource
+        let req = TestRequest::with_uri("/user/256").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::ACCEPTED);
+
+        let req = TestRequest::with_uri("/num/2/4").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("/num/2/5").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = TestRequest::with_uri("/num/3/4").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
     #[actix_rt::test]
     async fn test_external_resource() {
         let srv = init_service(
@@ -1,7 +1,7 @@
 use std::{cell::RefCell, mem, rc::Rc};
 
 use actix_http::{Extensions, Request};
-use actix_router::{Path, ResourceDef, Router, Url};
+use actix_router::{Path, ResourceDef, Router, SegmentMatchers, Url};
 use actix_service::{
     boxed::{self, BoxService, BoxServiceFactory},
     fn_service, Service, ServiceFactory,
@@ -14,7 +14,9 @@ use crate::{
     data::FnDataFactory,
     guard::Guard,
     request::{HttpRequest, HttpRequestPool},
+    request_span,
     rmap::ResourceMap,
+    routing_trace,
     service::{AppServiceFactory, ServiceRequest, ServiceResponse},
     Error, HttpResponse,
 };
@@ -42,6 +44,8 @@ where
     pub(crate) default: Option<Rc<HttpNewService>>,
     pub(crate) factory_ref: Rc<RefCell<Option<AppRoutingFactory>>>,
     pub(crate) external: RefCell<Vec<ResourceDef>>,
+    pub(crate) segment_matchers: SegmentMatchers,
+    pub(crate) debug_routing: bool,
 }
 
 impl<T, B> ServiceFactory<Request> for AppInit<T, B>
@@ -67,12 +71,14 @@ where
         // if no user defined default service exists.
         let default = self.default.clone().unwrap_or_else(|| {
             Rc::new(boxed::factory(fn_service(|req: ServiceRequest| async {
-                Ok(req.into_response(HttpResponse::NotFound()))
+                let res = routing_trace::respond(&req, HttpResponse::NotFound());
+                Ok(req.into_response(res))
             })))
         });
 
         // App config
-        let mut config = AppService::new(config, default.clone());
+        let mut config =
+            AppService::new(config, default.clone(), self.segment_matchers.clone());
 
         // register services
         mem::take(&mut *self.services.borrow_mut())
@@ -80,16 +86,18 @@ where
             .for_each(|mut srv| srv.register(&mut config));
 
         let mut rmap = ResourceMap::new(ResourceDef::prefix(""));
+        rmap.set_has_default(self.default.is_some());
 
         let (config, services) = config.into_services();
 
         // complete pipeline creation.
         *self.factory_ref.borrow_mut() = Some(AppRoutingFactory {
             default,
+            debug_routing: self.debug_routing,
             services: services
                 .into_iter()
-                .map(|(mut rdef, srv, guards, nested)| {
-                    rmap.add(&mut rdef, nested);
+                .map(|(mut rdef, srv, guards, nested, info)| {
+                    rmap.add_service(&mut rdef, nested, info);
                     (rdef, srv, RefCell::new(guards))
                 })
                 .collect::<Vec<_>>()
@@ -189,13 +197,23 @@ impl AppInitServiceState {
     }
 }
 
+impl<T, B> AppInitService<T, B>
+where
+    T: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    /// Returns the resource map of the application.
+    pub(crate) fn resource_map(&self) -> Rc<ResourceMap> {
+        Rc::clone(&self.app_state.rmap)
+    }
+}
+
 impl<T, B> Service<Request> for AppInitService<T, B>
 where
     T: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
 {
     type Response = ServiceResponse<B>;
     type Error = T::Error;
-    type Future = T::Future;
+    type Future = request_span::Instrumented<T::Future>;
 
     actix_service::forward_ready!(service);
 
@@ -216,7 +234,7 @@ where
                 self.app_data.clone(),
             )
         };
-        self.service.call(ServiceRequest::new(req, payload))
+        request_span::call(&self.service, ServiceRequest::new(req, payload))
     }
 }
 
@@ -232,6 +250,7 @@ where
 pub struct AppRoutingFactory {
     services: Rc<[(ResourceDef, HttpNewService, RefCell<Option<Guards>>)]>,
     default: Rc<HttpNewService>,
+    debug_routing: bool,
 }
 
 impl ServiceFactory<ServiceRequest> for AppRoutingFactory {
@@ -256,6 +275,7 @@ impl ServiceFactory<ServiceRequest> for AppRoutingFactory {
 
         // construct default service factory future
         let default_fut = self.default.new_service(());
+        let debug_routing = self.debug_routing;
 
         Box::pin(async move {
             let default = default_fut.await?;
@@ -272,7 +292,11 @@ impl ServiceFactory<ServiceRequest> for AppRoutingFactory {
                 })
                 .finish();
 
-            Ok(AppRouting { router, default })
+            Ok(AppRouting {
+                router,
+                default,
+                debug_routing,
+            })
         })
     }
 }
@@ -281,6 +305,7 @@ impl ServiceFactory<ServiceRequest> for AppRoutingFactory {
 pub struct AppRouting {
     router: Router<HttpService, Guards>,
     default: HttpService,
+    debug_routing: bool,
 }
 
 impl Service<ServiceRequest> for AppRouting {
@@ -291,18 +316,11 @@ impl Service<ServiceRequest> for AppRouting {
     actix_service::always_ready!();
 
     fn call(&self, mut req: ServiceRequest) -> Self::Future {
-        let res = self.router.recognize_fn(&mut req, |req, guards| {
-            if let Some(ref guards) = guards {
-                for f in guards {
-                    if !f.check(req.head()) {
-                        return false;
-                    }
-                }
-            }
-            true
-        });
+        if self.debug_routing {
+            routing_trace::start(&req);
+        }
 
-        if let Some((srv, _info)) = res {
+        if let Some(srv) = routing_trace::recognize(&self.router, &mut req) {
             srv.call(req)
         } else {
             self.default.call(req)
@@ -2,14 +2,14 @@ use std::net::SocketAddr;
 use std::rc::Rc;
 
 use actix_http::Extensions;
-use actix_router::ResourceDef;
+use actix_router::{ResourceDef, SegmentMatchers};
 use actix_service::{boxed, IntoServiceFactory, ServiceFactory};
 
 use crate::data::Data;
 use crate::error::Error;
-use crate::guard::Guard;
+use crate::guard::{self, Guard};
 use crate::resource::Resource;
-use crate::rmap::ResourceMap;
+use crate::rmap::{ResourceMap, ServiceInfo};
 use crate::route::Route;
 use crate::service::{
     AppServiceFactory, HttpServiceFactory, ServiceFactoryWrapper, ServiceRequest,
@@ -24,20 +24,27 @@ pub struct AppService {
     config: AppConfig,
     root: bool,
     default: Rc<HttpNewService>,
+    segment_matchers: SegmentMatchers,
     services: Vec<(
         ResourceDef,
         HttpNewService,
         Option<Guards>,
         Option<Rc<ResourceMap>>,
+        ServiceInfo,
     )>,
 }
 
 impl AppService {
     /// Crate server settings instance.
-    pub(crate) fn new(config: AppConfig, default: Rc<HttpNewService>) -> Self {
+    pub(crate) fn new(
+        config: AppConfig,
+        default: Rc<HttpNewService>,
+        segment_matchers: SegmentMatchers,
+    ) -> Self {
         AppService {
             config,
             default,
+            segment_matchers,
             root: true,
             services: Vec::new(),
         }
@@ -57,6 +64,7 @@ impl AppService {
             HttpNewService,
             Option<Guards>,
             Option<Rc<ResourceMap>>,
+            ServiceInfo,
         )>,
     ) {
         (self.config, self.services)
@@ -68,6 +76,7 @@ impl AppService {
         AppService {
             config: self.config.clone(),
             default: self.default.clone(),
+            segment_matchers: self.segment_matchers.clone(),
             services: Vec::new(),
             root: false,
         }
@@ -83,6 +92,15 @@ impl AppService {
         self.default.clone()
     }
 
+    /// Returns the custom segment matchers registered with
+    /// [`App::segment_matcher`](crate::App::segment_matcher).
+    ///
+    /// Services should resolve the matchers of their resource definitions with
+    /// [`ResourceDef::with_matchers`].
+    pub fn segment_matchers(&self) -> &SegmentMatchers {
+        &self.segment_matchers
+    }
+
     /// Register HTTP service.
     pub fn register_service<F, S>(
         &mut self,
@@ -100,8 +118,46 @@ impl AppService {
                 InitError = (),
             > + 'static,
     {
-        self.services
-            .push((rdef, boxed::factory(factory.into_factory()), guards, nested));
+        self.register_service_with_info(rdef, guards, factory, nested, ServiceInfo::default())
+    }
+
+    /// Register HTTP service, along with routing details recorded for it in the resource map.
+    ///
+    /// `info.methods` describes the methods accepted by the service's own routing, where `None`
+    /// means any method; the methods allowed by `guards` are taken into account separately.
+    pub(crate) fn register_service_with_info<F, S>(
+        &mut self,
+        rdef: ResourceDef,
+        guards: Option<Vec<Box<dyn Guard>>>,
+        factory: F,
+        nested: Option<Rc<ResourceMap>>,
+        mut info: ServiceInfo,
+    ) where
+        F: IntoServiceFactory<S, ServiceRequest>,
+        S: ServiceFactory<
+                ServiceRequest,
+                Response = ServiceResponse,
+                Error = Error,
+                Config = (),
+                InitError = (),
+            > + 'static,
+    {
+        let guard_methods = guards.as_deref().and_then(guard::all_methods);
+
+        info.methods = match (guard_methods, info.methods) {
+            (Some(guard_methods), Some(methods)) => {
+                Some(guard::intersect_methods(guard_methods, &methods))
+            }
+            (guard_methods, methods) => guard_methods.or(methods),
+        };
+
+        self.services.push((
+            rdef,
+            boxed::factory(factory.into_factory()),
+            guards,
+            nested,
+            info,
+        ));
     }
 }
 
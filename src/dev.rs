@@ -7,7 +7,8 @@ pub use crate::config::{AppConfig, AppService};
 #[doc(hidden)]
 pub use crate::handler::Handler;
 pub use crate::info::{ConnectionInfo, PeerAddr};
-pub use crate::rmap::ResourceMap;
+pub use crate::rmap::{ResourceEntry, ResourceMap};
+pub use crate::routing_trace::{RoutingOutcome, RoutingStep, RoutingTrace};
 pub use crate::service::{HttpServiceFactory, ServiceRequest, ServiceResponse, WebService};
 
 pub use crate::types::form::UrlEncoded;
@@ -19,7 +20,7 @@ pub use actix_http::body::{AnyBody, Body, BodySize, MessageBody, ResponseBody, S
 #[cfg(feature = "__compress")]
 pub use actix_http::encoding::Decoder as Decompress;
 pub use actix_http::{Extensions, Payload, PayloadStream, RequestHead, Response, ResponseHead};
-pub use actix_router::{Path, ResourceDef, ResourcePath, Url};
+pub use actix_router::{Path, ResourceDef, ResourcePath, SegmentMatcher, SegmentMatchers, Url};
 pub use actix_server::Server;
 pub use actix_service::{
     always_ready, fn_factory, fn_service, forward_ready, Service, ServiceFactory, Transform,
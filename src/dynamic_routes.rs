@@ -0,0 +1,655 @@
+use std::{
+    cell::RefCell,
+    fmt,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    task::{Context, Poll, Waker},
+};
+
+use actix_router::{Patterns, ResourceDef, Router, SegmentMatchers};
+use actix_service::{
+    boxed::{self, BoxService, BoxServiceFactory},
+    Service, ServiceFactory,
+};
+use futures_core::future::LocalBoxFuture;
+
+use crate::{
+    dev::ensure_leading_slash,
+    error::DynamicRouteError,
+    guard::Guard,
+    routing_trace,
+    service::{ServiceRequest, ServiceResponse},
+    Error, Route,
+};
+
+type Guards = Vec<Box<dyn Guard>>;
+type HttpService = BoxService<ServiceRequest, ServiceResponse, Error>;
+type HttpNewService = BoxServiceFactory<(), ServiceRequest, ServiceResponse, Error, ()>;
+type RouteFactory = dyn Fn() -> Route + Send + Sync;
+
+/// Route table that can be changed while the server is running.
+///
+/// Routes are registered by name, with a pattern and a function constructing the [`Route`] that
+/// handles matching requests. The table is mounted in a [`Scope`](crate::Scope) using
+/// [`Scope::dynamic`](crate::Scope::dynamic); requests not matched by the scope's own services
+/// are routed through the table before falling back to the scope's default service.
+///
+/// Handles are cheap to clone and share the same table, so routes registered or removed through
+/// any handle take effect in all workers. Create the table outside of the `HttpServer` app
+/// factory and clone it into each app. Changes are applied atomically: a request is routed using
+/// either the table before or after a change, never a mix of both. Each worker constructs the
+/// services of a new table the first time it routes a request after a change; requests arriving
+/// while they are constructed wait for them.
+///
+/// Dynamic routes are tried in registration order, re-registered routes keeping their position.
+/// They are not listed in the app's [`ResourceMap`](crate::dev::ResourceMap), so they can not be
+/// used to generate URLs.
+///
+/// ```
+/// use actix_web::{web, App, DynamicRoutes, HttpResponse, HttpServer};
+///
+/// async fn webhook(path: web::Path<String>) -> HttpResponse {
+///     HttpResponse::Ok().body(format!("event: {}", path.into_inner()))
+/// }
+///
+/// async fn add_hook(
+///     hooks: web::Data<DynamicRoutes>,
+///     name: web::Path<String>,
+/// ) -> actix_web::Result<HttpResponse> {
+///     let pattern = format!("/{}/{{event}}", name);
+///     hooks.insert(name.as_str(), &pattern, || web::post().to(webhook)).await?;
+///     Ok(HttpResponse::Created().finish())
+/// }
+///
+/// async fn remove_hook(
+///     hooks: web::Data<DynamicRoutes>,
+///     name: web::Path<String>,
+/// ) -> HttpResponse {
+///     hooks.remove(name.as_str());
+///     HttpResponse::NoContent().finish()
+/// }
+///
+/// # fn run() -> std::io::Result<()> {
+/// let hooks = DynamicRoutes::new();
+///
+/// let server = HttpServer::new(move || {
+///     App::new()
+///         .app_data(web::Data::new(hooks.clone()))
+///         .route("/admin/hooks/{name}", web::put().to(add_hook))
+///         .route("/admin/hooks/{name}", web::delete().to(remove_hook))
+///         .service(web::scope("/hooks").dynamic(hooks.clone()))
+/// });
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct DynamicRoutes {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    /// Incremented whenever `table` is swapped, so workers can check for changes without locking.
+    version: AtomicU64,
+    table: RwLock<Arc<[Entry]>>,
+}
+
+#[derive(Clone)]
+struct Entry {
+    name: String,
+    rdef: ResourceDef,
+    factory: Arc<RouteFactory>,
+}
+
+impl DynamicRoutes {
+    /// Constructs an empty route table.
+    pub fn new() -> Self {
+        DynamicRoutes {
+            shared: Arc::new(Shared {
+                version: AtomicU64::new(0),
+                table: RwLock::new(Arc::from(Vec::new())),
+            }),
+        }
+    }
+
+    /// Registers a route under `name`, replacing any route already registered with that name.
+    ///
+    /// `route` is called by each worker to construct the route handling requests that match
+    /// `pattern`. Patterns are relative to the scope the table is mounted in.
+    ///
+    /// The services of the route are constructed once before it is registered, and an error is
+    /// returned, leaving the table unchanged, if that fails. Workers failing to construct them
+    /// later on log an error and skip the route.
+    ///
+    /// # Panics
+    /// Panics if `pattern` is malformed.
+    pub async fn insert<F>(
+        &self,
+        name: impl Into<String>,
+        pattern: &str,
+        route: F,
+    ) -> Result<(), DynamicRouteError>
+    where
+        F: Fn() -> Route + Send + Sync + 'static,
+    {
+        let entry = Entry {
+            name: name.into(),
+            rdef: ResourceDef::new(ensure_leading_slash(Patterns::Single(pattern.to_owned()))),
+            factory: Arc::new(route),
+        };
+
+        if (entry.factory)().new_service(()).await.is_err() {
+            return Err(DynamicRouteError::new(entry.name));
+        }
+
+        self.update(
+            |entries| match entries.iter().position(|e| e.name == entry.name) {
+                Some(idx) => entries[idx] = entry,
+                None => entries.push(entry),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Removes the route registered under `name`.
+    ///
+    /// Returns `true` if a route was removed.
+    pub fn remove(&self, name: &str) -> bool {
+        let mut removed = false;
+
+        self.update(|entries| {
+            let len = entries.len();
+            entries.retain(|entry| entry.name != name);
+            removed = entries.len() != len;
+        });
+
+        removed
+    }
+
+    /// Returns `true` if a route is registered under `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.snapshot().1.iter().any(|entry| entry.name == name)
+    }
+
+    /// Returns the names and patterns of the registered routes, in routing order.
+    pub fn routes(&self) -> Vec<(String, String)> {
+        self.snapshot()
+            .1
+            .iter()
+            .map(|entry| {
+                let pattern = entry.rdef.pattern().unwrap_or_default();
+                (entry.name.clone(), pattern.to_owned())
+            })
+            .collect()
+    }
+
+    /// Returns the current version of the table.
+    fn version(&self) -> u64 {
+        self.shared.version.load(Ordering::Acquire)
+    }
+
+    /// Returns the current version and entries of the table.
+    fn snapshot(&self) -> (u64, Arc<[Entry]>) {
+        let table = self.shared.table.read().unwrap();
+        (self.version(), Arc::clone(&table))
+    }
+
+    /// Applies `f` to a copy of the entries and swaps in the result.
+    fn update(&self, f: impl FnOnce(&mut Vec<Entry>)) {
+        let mut table = self.shared.table.write().unwrap();
+
+        let mut entries = table.to_vec();
+        f(&mut entries);
+
+        *table = entries.into();
+        self.shared.version.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Constructs a factory for the service routing requests through the table, which falls back
+    /// to `default` for unmatched requests.
+    pub(crate) fn into_factory(
+        self,
+        default: Rc<HttpNewService>,
+        matchers: SegmentMatchers,
+    ) -> HttpNewService {
+        boxed::factory(DynamicRoutingFactory {
+            routes: self,
+            matchers,
+            default,
+        })
+    }
+}
+
+impl Default for DynamicRoutes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for DynamicRoutes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynamicRoutes")
+            .field("routes", &self.routes())
+            .finish()
+    }
+}
+
+struct DynamicRoutingFactory {
+    routes: DynamicRoutes,
+    matchers: SegmentMatchers,
+    default: Rc<HttpNewService>,
+}
+
+impl ServiceFactory<ServiceRequest> for DynamicRoutingFactory {
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Config = ();
+    type Service = DynamicRouting;
+    type InitError = ();
+    type Future = LocalBoxFuture<'static, Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        let default_fut = self.default.new_service(());
+        let routes = self.routes.clone();
+        let matchers = self.matchers.clone();
+
+        Box::pin(async move {
+            let default = default_fut.await?;
+            let table = LocalTable::build(routes.snapshot(), &matchers).await;
+
+            Ok(DynamicRouting {
+                routes,
+                matchers,
+                local: Rc::new(RefCell::new(Local {
+                    table: Rc::new(table),
+                    building: None,
+                })),
+                default: Rc::new(default),
+            })
+        })
+    }
+}
+
+/// Services of the dynamic routes of a table version, constructed by a worker.
+struct LocalTable {
+    version: u64,
+    router: Router<HttpService, Guards>,
+}
+
+impl LocalTable {
+    async fn build(
+        (version, entries): (u64, Arc<[Entry]>),
+        matchers: &SegmentMatchers,
+    ) -> Self {
+        let mut router = Router::build();
+
+        for entry in entries.iter() {
+            let mut route = (entry.factory)();
+            let guards = route.take_guards();
+
+            match route.new_service(()).await {
+                Ok(service) => {
+                    let rdef = entry.rdef.clone().with_matchers(matchers);
+                    router.rdef(rdef, boxed::service(service)).2 = Some(guards);
+                }
+                Err(_) => log::error!("Can not construct dynamic route: {}", entry.name),
+            }
+        }
+
+        LocalTable {
+            version,
+            router: router.finish(),
+        }
+    }
+
+    fn call(
+        &self,
+        mut req: ServiceRequest,
+        default: &HttpService,
+    ) -> LocalBoxFuture<'static, Result<ServiceResponse, Error>> {
+        if let Some(srv) = routing_trace::recognize(&self.router, &mut req) {
+            srv.call(req)
+        } else {
+            default.call(req)
+        }
+    }
+}
+
+/// Routes requests through the latest version of a dynamic route table.
+struct DynamicRouting {
+    routes: DynamicRoutes,
+    matchers: SegmentMatchers,
+    local: Rc<RefCell<Local>>,
+    default: Rc<HttpService>,
+}
+
+/// Services of the dynamic routes constructed by a worker.
+struct Local {
+    table: Rc<LocalTable>,
+    /// Newer table version whose services are being constructed.
+    building: Option<Building>,
+}
+
+/// A table version whose services are being constructed, and the wakers of the requests waiting
+/// for them.
+struct Building {
+    version: u64,
+    wakers: Vec<Waker>,
+}
+
+impl Service<ServiceRequest> for DynamicRouting {
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::always_ready!();
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let table = Rc::clone(&self.local.borrow().table);
+
+        if table.version == self.routes.version() {
+            return table.call(req, &self.default);
+        }
+
+        // table changed since services were last constructed
+        let routes = self.routes.clone();
+        let matchers = self.matchers.clone();
+        let local = Rc::clone(&self.local);
+        let default = Rc::clone(&self.default);
+
+        Box::pin(async move {
+            loop {
+                let version = routes.version();
+                let table = Rc::clone(&local.borrow().table);
+
+                if table.version >= version {
+                    return table.call(req, &default).await;
+                }
+
+                let building = local.borrow().building.as_ref().map(|b| b.version);
+
+                match building {
+                    // another request is constructing the services of this version or a newer one
+                    Some(building) if building >= version => {
+                        WaitBuild {
+                            local: Rc::clone(&local),
+                            version: building,
+                        }
+                        .await
+                    }
+
+                    _ => {
+                        let snapshot = routes.snapshot();
+                        let _builder = Builder::start(&local, snapshot.0);
+                        let table = LocalTable::build(snapshot, &matchers).await;
+
+                        let mut local = local.borrow_mut();
+                        if local.table.version < table.version {
+                            local.table = Rc::new(table);
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Marks a request as constructing the services of a table version, waking the requests waiting
+/// for them when dropped.
+struct Builder {
+    local: Rc<RefCell<Local>>,
+    version: u64,
+}
+
+impl Builder {
+    fn start(local: &Rc<RefCell<Local>>, version: u64) -> Self {
+        let mut state = local.borrow_mut();
+
+        // requests waiting for an older version are woken once this one is constructed
+        let wakers = state
+            .building
+            .take()
+            .map(|building| building.wakers)
+            .unwrap_or_default();
+
+        state.building = Some(Building { version, wakers });
+
+        Builder {
+            local: Rc::clone(local),
+            version,
+        }
+    }
+}
+
+impl Drop for Builder {
+    fn drop(&mut self) {
+        let mut local = self.local.borrow_mut();
+
+        let building = match local.building.take() {
+            Some(building) if building.version == self.version => building,
+            building => {
+                local.building = building;
+                return;
+            }
+        };
+
+        drop(local);
+
+        for waker in building.wakers {
+            waker.wake();
+        }
+    }
+}
+
+/// Resolves when the request constructing the services of a table version is done with them.
+struct WaitBuild {
+    local: Rc<RefCell<Local>>,
+    version: u64,
+}
+
+impl Future for WaitBuild {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut local = self.local.borrow_mut();
+
+        match local.building.as_mut() {
+            Some(building) if building.version == self.version => {
+                let wakers = &mut building.wakers;
+
+                if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    wakers.push(cx.waker().clone());
+                }
+
+                Poll::Pending
+            }
+            _ => Poll::Ready(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use actix_rt::time::sleep;
+
+    use super::*;
+    use crate::{
+        dev::{fn_factory, fn_service},
+        http::StatusCode,
+        test::{call_service, init_service, read_body, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    async fn event(path: web::Path<String>) -> HttpResponse {
+        HttpResponse::Ok().body(format!("event {}", path.into_inner()))
+    }
+
+    #[actix_rt::test]
+    async fn insert_and_remove() {
+        let hooks = DynamicRoutes::new();
+        hooks
+            .insert("early", "/early", || web::get().to(HttpResponse::Ok))
+            .await
+            .unwrap();
+
+        let srv = init_service(
+            App::new().service(
+                web::scope("/hooks")
+                    .route("/static", web::get().to(HttpResponse::Accepted))
+                    .dynamic(hooks.clone()),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/hooks/early").to_request();
+        assert_eq!(call_service(&srv, req).await.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("/hooks/acme/push").to_request();
+        assert_eq!(
+            call_service(&srv, req).await.status(),
+            StatusCode::NOT_FOUND
+        );
+
+        hooks
+            .insert("acme", "acme/{event}", || web::get().to(event))
+            .await
+            .unwrap();
+        assert!(hooks.contains("acme"));
+
+        let req = TestRequest::with_uri("/hooks/acme/push").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, "event push");
+
+        // static routes of the scope take precedence
+        hooks
+            .insert("static", "/static", || web::get().to(HttpResponse::Ok))
+            .await
+            .unwrap();
+        let req = TestRequest::with_uri("/hooks/static").to_request();
+        assert_eq!(call_service(&srv, req).await.status(), StatusCode::ACCEPTED);
+
+        // guards of dynamic routes are honored
+        let req = TestRequest::post().uri("/hooks/acme/push").to_request();
+        assert_eq!(
+            call_service(&srv, req).await.status(),
+            StatusCode::NOT_FOUND
+        );
+
+        // replacing a route keeps its position
+        hooks
+            .insert("early", "/early", || web::get().to(HttpResponse::Created))
+            .await
+            .unwrap();
+        let req = TestRequest::with_uri("/hooks/early").to_request();
+        assert_eq!(call_service(&srv, req).await.status(), StatusCode::CREATED);
+        assert_eq!(
+            hooks.routes(),
+            vec![
+                ("early".to_owned(), "/early".to_owned()),
+                ("acme".to_owned(), "/acme/{event}".to_owned()),
+                ("static".to_owned(), "/static".to_owned()),
+            ]
+        );
+
+        assert!(hooks.remove("acme"));
+        assert!(!hooks.remove("acme"));
+        assert!(!hooks.contains("acme"));
+
+        let req = TestRequest::with_uri("/hooks/acme/push").to_request();
+        assert_eq!(
+            call_service(&srv, req).await.status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[actix_rt::test]
+    async fn shared_between_apps() {
+        let hooks = DynamicRoutes::new();
+
+        let app = || {
+            App::new().service(
+                web::scope("/hooks")
+                    .dynamic(hooks.clone())
+                    .default_service(web::to(HttpResponse::Gone)),
+            )
+        };
+
+        let srv1 = init_service(app()).await;
+        let srv2 = init_service(app()).await;
+
+        hooks
+            .insert("acme", "/{event:u8}", || web::get().to(event))
+            .await
+            .unwrap();
+
+        for srv in [&srv1, &srv2].iter() {
+            let req = TestRequest::with_uri("/hooks/42").to_request();
+            let res = call_service(srv, req).await;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(read_body(res).await, "event 42");
+
+            let req = TestRequest::with_uri("/hooks/420").to_request();
+            assert_eq!(call_service(srv, req).await.status(), StatusCode::GONE);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn rejects_failing_routes() {
+        let hooks = DynamicRoutes::new();
+
+        let err = hooks
+            .insert("broken", "/broken", || {
+                web::get().service(fn_factory(|| async { Err::<HttpService, _>(()) }))
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.name(), "broken");
+        assert!(!hooks.contains("broken"));
+    }
+
+    #[actix_rt::test]
+    async fn constructs_table_once() {
+        let hooks = DynamicRoutes::new();
+        let srv =
+            init_service(App::new().service(web::scope("/hooks").dynamic(hooks.clone()))).await;
+
+        let constructed = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&constructed);
+
+        hooks
+            .insert("slow", "/slow", move || {
+                let counter = Arc::clone(&counter);
+
+                web::get().service(fn_factory(move || {
+                    counter.fetch_add(1, Ordering::SeqCst);
+
+                    async {
+                        sleep(Duration::from_millis(20)).await;
+                        Ok::<_, ()>(fn_service(|req: ServiceRequest| async {
+                            Ok::<_, Error>(req.into_response(HttpResponse::Ok()))
+                        }))
+                    }
+                }))
+            })
+            .await
+            .unwrap();
+        assert_eq!(constructed.load(Ordering::SeqCst), 1);
+
+        let (a, b) = futures_util::future::join(
+            call_service(&srv, TestRequest::with_uri("/hooks/slow").to_request()),
+            call_service(&srv, TestRequest::with_uri("/hooks/slow").to_request()),
+        )
+        .await;
+        assert_eq!(a.status(), StatusCode::OK);
+        assert_eq!(b.status(), StatusCode::OK);
+        assert_eq!(constructed.load(Ordering::SeqCst), 2);
+    }
+}
@@ -37,6 +37,21 @@ pub enum UrlGenerationError {
     #[display(fmt = "Not all path pattern covered")]
     NotEnoughElements,
 
+    /// Parameters for the listed dynamic segments were not provided
+    #[display(fmt = "Missing URL parameters: {}", "_0.join(\", \")")]
+    #[from(ignore)]
+    MissingParams(#[error(not(source))] Vec<String>),
+
+    /// The listed positional parameters were provided beyond the last dynamic segment
+    #[display(fmt = "Unexpected URL parameters: {}", "_0.join(\", \")")]
+    #[from(ignore)]
+    ExtraParams(#[error(not(source))] Vec<String>),
+
+    /// Parameters could not be serialized as segment values
+    #[display(fmt = "Invalid URL parameters: {}", _0)]
+    #[from(ignore)]
+    InvalidParams(#[error(not(source))] String),
+
     /// URL parse error
     #[display(fmt = "{}", _0)]
     ParseError(UrlParseError),
@@ -44,6 +59,28 @@ pub enum UrlGenerationError {
 
 impl ResponseError for UrlGenerationError {}
 
+/// Error returned when the services of a [`DynamicRoutes`](crate::DynamicRoutes) route can not
+/// be constructed.
+#[derive(Debug, PartialEq, Display, Error)]
+#[display(fmt = "Can not construct dynamic route: {}", name)]
+pub struct DynamicRouteError {
+    #[error(not(source))]
+    name: String,
+}
+
+impl DynamicRouteError {
+    pub(crate) fn new(name: String) -> Self {
+        DynamicRouteError { name }
+    }
+
+    /// Returns the name of the route.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl ResponseError for DynamicRouteError {}
+
 /// A set of errors that can occur during parsing urlencoded payloads
 #[derive(Debug, Display, Error, From)]
 #[non_exhaustive]
@@ -211,6 +248,28 @@ impl ResponseError for ReadlinesError {
     }
 }
 
+/// Errors which can occur when evaluating the preconditions of a request.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum PreconditionError {
+    /// A precondition evaluated to false.
+    #[display(fmt = "Precondition failed")]
+    Failed,
+
+    /// Preconditions are required for the request, but none were sent.
+    #[display(fmt = "Precondition required")]
+    Required,
+}
+
+impl ResponseError for PreconditionError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            PreconditionError::Failed => StatusCode::PRECONDITION_FAILED,
+            PreconditionError::Required => StatusCode::PRECONDITION_REQUIRED,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -255,4 +314,12 @@ mod tests {
         let resp = ReadlinesError::EncodingError.error_response();
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
+
+    #[test]
+    fn test_precondition_error() {
+        let resp = PreconditionError::Failed.error_response();
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+        let resp = PreconditionError::Required.error_response();
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_REQUIRED);
+    }
 }
@@ -11,7 +11,7 @@ use actix_http::http::{Method, Uri};
 use actix_utils::future::{ok, Ready};
 use futures_core::ready;
 
-use crate::{dev::Payload, Error, HttpRequest};
+use crate::{dev::Payload, openapi::Operation, Error, HttpRequest};
 
 /// A type that implements [`FromRequest`] is called an **extractor** and can extract data from
 /// the request. Some types that implement this trait are: [`Json`], [`Header`], and [`Path`].
@@ -74,6 +74,14 @@ pub trait FromRequest: Sized {
     fn extract(req: &HttpRequest) -> Self::Future {
         Self::from_request(req, &mut Payload::None)
     }
+
+    /// Describe the extracted parts of the request in an OpenAPI [`Operation`].
+    ///
+    /// Called when a handler using this extractor is registered. By default, nothing is described.
+    ///
+    /// See [`openapi`](crate::openapi) for details.
+    #[allow(unused_variables)]
+    fn schema(operation: &mut Operation) {}
 }
 
 /// Optionally extract a field from the request
@@ -137,6 +145,12 @@ where
             fut: T::from_request(req, payload),
         }
     }
+
+    fn schema(operation: &mut Operation) {
+        let mut inner = Operation::default();
+        T::schema(&mut inner);
+        operation.merge_optional(inner);
+    }
 }
 
 #[pin_project::pin_project]
@@ -224,6 +238,12 @@ where
             fut: T::from_request(req, payload),
         }
     }
+
+    fn schema(operation: &mut Operation) {
+        let mut inner = Operation::default();
+        T::schema(&mut inner);
+        operation.merge_optional(inner);
+    }
 }
 
 #[pin_project::pin_project]
@@ -337,6 +357,10 @@ macro_rules! tuple_from_req ({$fut_type:ident, $(($n:tt, $T:ident)),+} => {
                     futs: FutWrapper($($T::from_request(req, payload),)+),
                 }
             }
+
+            fn schema(operation: &mut Operation) {
+                $($T::schema(operation);)+
+            }
         }
 
         #[doc(hidden)]
@@ -31,6 +31,9 @@ use std::rc::Rc;
 
 use actix_http::http::{self, header, uri::Uri};
 use actix_http::RequestHead;
+use actix_router::{Path, ResourceDef, SegmentMatchers};
+
+use crate::service::ServiceRequest;
 
 /// Trait defines resource guards. Guards are used for route selection.
 ///
@@ -40,12 +43,78 @@ use actix_http::RequestHead;
 pub trait Guard {
     /// Check if request matches predicate
     fn check(&self, request: &RequestHead) -> bool;
+
+    /// Returns the HTTP methods this guard can match, if it restricts the request method.
+    ///
+    /// This is used to introspect registered routes and never affects request matching. The
+    /// default implementation returns `None`, meaning any method may pass the guard.
+    fn methods(&self) -> Option<Vec<http::Method>> {
+        None
+    }
+
+    /// Returns a short description of the guard, used in [routing diagnostics].
+    ///
+    /// The default implementation returns the guard's type name.
+    ///
+    /// [routing diagnostics]: crate::App::debug_routing
+    fn name(&self) -> String {
+        std::any::type_name::<Self>().to_owned()
+    }
 }
 
 impl Guard for Rc<dyn Guard> {
     fn check(&self, request: &RequestHead) -> bool {
         self.deref().check(request)
     }
+
+    fn methods(&self) -> Option<Vec<http::Method>> {
+        self.deref().methods()
+    }
+
+    fn name(&self) -> String {
+        self.deref().name()
+    }
+}
+
+/// Returns the methods that can pass all of `guards`, or `None` if none restrict the method.
+pub(crate) fn all_methods(guards: &[Box<dyn Guard>]) -> Option<Vec<http::Method>> {
+    guards
+        .iter()
+        .filter_map(|guard| guard.methods())
+        .fold(None, |acc, methods| {
+            Some(match acc {
+                None => methods,
+                Some(acc) => intersect_methods(acc, &methods),
+            })
+        })
+}
+
+/// Returns the methods that can pass any of the given method sets, where `None` stands for any
+/// method.
+pub(crate) fn any_methods<I>(sets: I) -> Option<Vec<http::Method>>
+where
+    I: IntoIterator<Item = Option<Vec<http::Method>>>,
+{
+    let mut union = Vec::new();
+
+    for methods in sets {
+        for method in methods? {
+            if !union.contains(&method) {
+                union.push(method);
+            }
+        }
+    }
+
+    Some(union)
+}
+
+/// Returns the methods in `a` that are also in `b`, keeping the order of `a`.
+pub(crate) fn intersect_methods(
+    mut a: Vec<http::Method>,
+    b: &[http::Method],
+) -> Vec<http::Method> {
+    a.retain(|method| b.contains(method));
+    a
 }
 
 /// Create guard object for supplied function.
@@ -128,6 +197,14 @@ impl Guard for AnyGuard {
         }
         false
     }
+
+    fn methods(&self) -> Option<Vec<http::Method>> {
+        any_methods(self.0.iter().map(|guard| guard.methods()))
+    }
+
+    fn name(&self) -> String {
+        format!("Any({})", guard_names(&self.0))
+    }
 }
 
 /// Return guard that matches if all of the supplied guards.
@@ -168,6 +245,23 @@ impl Guard for AllGuard {
         }
         true
     }
+
+    fn methods(&self) -> Option<Vec<http::Method>> {
+        all_methods(&self.0)
+    }
+
+    fn name(&self) -> String {
+        format!("All({})", guard_names(&self.0))
+    }
+}
+
+/// Returns the names of `guards`, separated by commas.
+fn guard_names(guards: &[Box<dyn Guard>]) -> String {
+    guards
+        .iter()
+        .map(|guard| guard.name())
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 /// Return guard that matches if supplied guard does not match.
@@ -182,6 +276,10 @@ impl Guard for NotGuard {
     fn check(&self, request: &RequestHead) -> bool {
         !self.0.check(request)
     }
+
+    fn name(&self) -> String {
+        format!("Not({})", self.0.name())
+    }
 }
 
 /// HTTP method guard.
@@ -192,6 +290,14 @@ impl Guard for MethodGuard {
     fn check(&self, request: &RequestHead) -> bool {
         request.method == self.0
     }
+
+    fn methods(&self) -> Option<Vec<http::Method>> {
+        Some(vec![self.0.clone()])
+    }
+
+    fn name(&self) -> String {
+        format!("Method({})", self.0)
+    }
 }
 
 /// Guard to match *GET* HTTP method.
@@ -263,6 +369,10 @@ impl Guard for HeaderGuard {
         }
         false
     }
+
+    fn name(&self) -> String {
+        format!("Header({}: {:?})", self.0, self.1)
+    }
 }
 
 /// Return predicate that matches if request contains specified Host name.
@@ -327,6 +437,236 @@ impl Guard for HostGuard {
 
         true
     }
+
+    fn name(&self) -> String {
+        match &self.1 {
+            Some(scheme) => format!("Host({}://{})", scheme, self.0),
+            None => format!("Host({})", self.0),
+        }
+    }
+}
+
+/// Return predicate that matches if the request accepts responses of the given media type.
+///
+/// The `Accept` header is parsed as a list of media ranges with quality values. The most
+/// specific media range that covers `mime` decides whether it is acceptable; the media type is
+/// rejected if that range has a quality of zero. Wildcards are honored on both sides, so a
+/// `text/*` guard matches requests that accept `text/html`. Requests without an `Accept`
+/// header, or with one that cannot be parsed, accept any media type.
+///
+/// Media type parameters are significant, which makes this guard suitable for versioning an API
+/// by media type on a single path. Register the routes for more specific media types first.
+///
+/// ```
+/// use actix_web::{guard, web, App, HttpResponse};
+///
+/// App::new().service(
+///     web::resource("/user")
+///         .route(
+///             web::get()
+///                 .guard(guard::Accept(
+///                     "application/vnd.api+json; version=2".parse().unwrap(),
+///                 ))
+///                 .to(|| HttpResponse::Ok().body("v2")),
+///         )
+///         .route(
+///             web::get()
+///                 .guard(guard::Accept("application/json".parse().unwrap()))
+///                 .to(|| HttpResponse::Ok().body("v1")),
+///         ),
+/// );
+/// ```
+pub fn Accept(mime: mime::Mime) -> AcceptGuard {
+    AcceptGuard(mime)
+}
+
+#[doc(hidden)]
+pub struct AcceptGuard(mime::Mime);
+
+impl Guard for AcceptGuard {
+    fn check(&self, req: &RequestHead) -> bool {
+        let ranges: Vec<header::QualityItem<mime::Mime>> =
+            match header::from_comma_delimited(req.headers.get_all(header::ACCEPT)) {
+                Ok(ranges) if !ranges.is_empty() => ranges,
+                _ => return true,
+            };
+
+        ranges
+            .iter()
+            .filter(|range| mime_matches(&range.item, &self.0))
+            .max_by(|a, b| {
+                mime_specificity(&a.item)
+                    .cmp(&mime_specificity(&b.item))
+                    .then(a.quality.cmp(&b.quality))
+            })
+            .map_or(false, |range| range.quality > header::q(0))
+    }
+
+    fn name(&self) -> String {
+        format!("Accept({})", self.0)
+    }
+}
+
+/// Return predicate that matches if the request body has the given media type.
+///
+/// The `Content-Type` header must be present and parse as a media type. The guard may use
+/// wildcards, such as `text/*`, and any parameters it specifies must be present in the header
+/// with equal values; parameters not specified by the guard are ignored.
+///
+/// ```
+/// use actix_web::{guard, web, App, HttpResponse};
+///
+/// App::new().service(
+///     web::resource("/upload")
+///         .guard(guard::ContentType(mime::APPLICATION_JSON))
+///         .to(|| HttpResponse::Ok()),
+/// );
+/// ```
+pub fn ContentType(mime: mime::Mime) -> ContentTypeGuard {
+    ContentTypeGuard(mime)
+}
+
+#[doc(hidden)]
+pub struct ContentTypeGuard(mime::Mime);
+
+impl Guard for ContentTypeGuard {
+    fn check(&self, req: &RequestHead) -> bool {
+        req.headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|val| val.to_str().ok())
+            .and_then(|val| val.parse::<mime::Mime>().ok())
+            .map_or(false, |content_type| mime_matches(&self.0, &content_type))
+    }
+
+    fn name(&self) -> String {
+        format!("ContentType({})", self.0)
+    }
+}
+
+/// Returns `true` if the media type `range` covers `mime`.
+///
+/// Wildcards in either media type match any type or subtype. Every parameter of `range`, other
+/// than the quality value, must be present in `mime` with an equal value.
+fn mime_matches(range: &mime::Mime, mime: &mime::Mime) -> bool {
+    let type_matches = range.type_() == mime::STAR
+        || mime.type_() == mime::STAR
+        || range.type_() == mime.type_();
+
+    let subtype_matches = range.subtype() == mime::STAR
+        || mime.subtype() == mime::STAR
+        || range.subtype() == mime.subtype();
+
+    type_matches
+        && subtype_matches
+        && range
+            .params()
+            .all(|(name, value)| name == "q" || mime.get_param(name) == Some(value))
+}
+
+/// Ranks media ranges by specificity, as used to pick the range that applies to a media type.
+fn mime_specificity(range: &mime::Mime) -> (bool, bool, usize) {
+    (
+        range.type_() != mime::STAR,
+        range.subtype() != mime::STAR,
+        range.params().filter(|(name, _)| *name != "q").count(),
+    )
+}
+
+/// Host pattern of a scope or resource, registered with [`Scope::host`](crate::Scope::host) or
+/// [`Resource::host`](crate::Resource::host).
+///
+/// Guards requests by matching the host name, without port and lowercased, against the pattern,
+/// whose static parts are lowercased as well. Dynamic segments without a custom regex or matcher match a single host label; they do not span
+/// dots.
+#[derive(Clone, Debug)]
+pub(crate) struct HostPattern {
+    pattern: String,
+    rdef: ResourceDef,
+}
+
+impl HostPattern {
+    pub(crate) fn new(pattern: &str, matchers: &SegmentMatchers) -> Self {
+        HostPattern {
+            pattern: pattern.to_owned(),
+            rdef: ResourceDef::new(normalize_host_pattern(pattern)).with_matchers(matchers),
+        }
+    }
+
+    /// Returns the pattern as registered.
+    pub(crate) fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns the resource definition used to match and generate host names.
+    pub(crate) fn rdef(&self) -> &ResourceDef {
+        &self.rdef
+    }
+
+    /// Adds the segments captured from the request's host name to its match info, ahead of the
+    /// path segments, as expected by [`url_for`](crate::HttpRequest::url_for).
+    pub(crate) fn capture(&self, req: &mut ServiceRequest) {
+        let host = match request_host(req.head()) {
+            Some(host) => host,
+            None => return,
+        };
+
+        let mut path = Path::new(host.as_str());
+        if self.rdef.capture_match_info(&mut path) {
+            for (idx, (name, value)) in path.iter().enumerate() {
+                req.match_info_mut()
+                    .insert_static(idx, name.to_owned(), value.to_owned());
+            }
+        }
+    }
+}
+
+/// Returns the lowercased host name of a request, without port.
+fn request_host(req: &RequestHead) -> Option<String> {
+    get_host_uri(req).and_then(|uri| uri.host().map(str::to_ascii_lowercase))
+}
+
+impl Guard for HostPattern {
+    fn check(&self, req: &RequestHead) -> bool {
+        request_host(req).map_or(false, |host| self.rdef.is_match(&host))
+    }
+
+    fn name(&self) -> String {
+        format!("Host({})", self.pattern)
+    }
+}
+
+/// Lowercases the static parts of a host pattern and restricts dynamic segments without a custom
+/// regex to a single host label.
+fn normalize_host_pattern(pattern: &str) -> String {
+    let mut out = String::with_capacity(pattern.len() + 8);
+    let mut depth = 0usize;
+    let mut custom = false;
+
+    for ch in pattern.chars() {
+        match ch {
+            '{' => {
+                depth += 1;
+                if depth == 1 {
+                    custom = false;
+                }
+            }
+            ':' if depth == 1 => custom = true,
+            '}' if depth == 1 && !custom => out.push_str(":[^.]+"),
+            _ => {}
+        }
+
+        if ch == '}' {
+            depth = depth.saturating_sub(1);
+        }
+
+        if depth == 0 {
+            out.push(ch.to_ascii_lowercase());
+        } else {
+            out.push(ch);
+        }
+    }
+
+    out
 }
 
 #[cfg(test)]
@@ -380,6 +720,57 @@ mod tests {
         assert!(!pred.check(req.head()));
     }
 
+    #[test]
+    fn test_host_pattern() {
+        let matchers = SegmentMatchers::new();
+
+        let req = TestRequest::default()
+            .insert_header((header::HOST, "acme.example.com:8080"))
+            .to_srv_request();
+
+        let pred = HostPattern::new("{tenant}.example.com", &matchers);
+        assert!(pred.check(req.head()));
+        assert_eq!(pred.rdef().pattern(), Some("{tenant:[^.]+}.example.com"));
+
+        let pred = HostPattern::new("{tenant:u32}.example.com", &matchers);
+        assert!(!pred.check(req.head()));
+
+        let pred = HostPattern::new("acme.{domain}", &matchers);
+        assert!(!pred.check(req.head()));
+
+        let pred = HostPattern::new(r"{sub:[a-z]{4}}.{domain}.com", &matchers);
+        assert!(pred.check(req.head()));
+
+        let mut req = req;
+        pred.capture(&mut req);
+        assert_eq!(req.match_info().get("sub"), Some("acme"));
+        assert_eq!(req.match_info().get("domain"), Some("example"));
+
+        let req = TestRequest::default()
+            .insert_header((header::HOST, "a.b.example.com"))
+            .to_http_request();
+        let pred = HostPattern::new("{tenant}.example.com", &matchers);
+        assert!(!pred.check(req.head()));
+
+        let req = TestRequest::default()
+            .insert_header((header::HOST, "api.example.com"))
+            .to_srv_request();
+        let pred = HostPattern::new("API.Example.com", &matchers);
+        assert!(pred.check(req.head()));
+        assert_eq!(pred.pattern(), "API.Example.com");
+
+        let pred = HostPattern::new("{Tenant:[A-Za-z]+}.EXAMPLE.com", &matchers);
+        assert_eq!(
+            pred.rdef().pattern(),
+            Some("{Tenant:[A-Za-z]+}.example.com")
+        );
+
+        let mut req = req;
+        assert!(pred.check(req.head()));
+        pred.capture(&mut req);
+        assert_eq!(req.match_info().get("Tenant"), Some("api"));
+    }
+
     #[test]
     fn test_host_scheme() {
         let req = TestRequest::default()
@@ -504,4 +895,175 @@ mod tests {
         assert!(Any(Get()).or(Trace()).check(r.head()));
         assert!(!Any(Get()).or(Get()).check(r.head()));
     }
+
+    #[test]
+    fn test_guard_methods() {
+        assert_eq!(Get().methods(), Some(vec![Method::GET]));
+        assert_eq!(Header("content-type", "text/plain").methods(), None);
+        assert_eq!(Not(Get()).methods(), None);
+
+        assert_eq!(
+            Any(Get()).or(Post()).or(Get()).methods(),
+            Some(vec![Method::GET, Method::POST])
+        );
+        assert_eq!(Any(Get()).or(Header("x", "y")).methods(), None);
+
+        assert_eq!(
+            All(Any(Get()).or(Post()))
+                .and(Header("x", "y"))
+                .and(Post())
+                .methods(),
+            Some(vec![Method::POST])
+        );
+        assert_eq!(All(Get()).and(Post()).methods(), Some(vec![]));
+        assert_eq!(All(Header("x", "y")).methods(), None);
+    }
+
+    #[test]
+    fn test_accept() {
+        let req = TestRequest::default().to_http_request();
+        assert!(Accept(mime::APPLICATION_JSON).check(req.head()));
+
+        let req = TestRequest::default()
+            .insert_header((header::ACCEPT, "text/html, application/json;q=0.5"))
+            .to_http_request();
+        assert!(Accept(mime::TEXT_HTML).check(req.head()));
+        assert!(Accept(mime::APPLICATION_JSON).check(req.head()));
+        assert!(Accept(mime::TEXT_STAR).check(req.head()));
+        assert!(!Accept(mime::TEXT_PLAIN).check(req.head()));
+        assert!(!Accept(mime::IMAGE_PNG).check(req.head()));
+
+        let req = TestRequest::default()
+            .insert_header((header::ACCEPT, "text/*, */*;q=0.1, text/csv;q=0"))
+            .to_http_request();
+        assert!(Accept(mime::TEXT_PLAIN).check(req.head()));
+        assert!(Accept(mime::IMAGE_PNG).check(req.head()));
+        assert!(!Accept(mime::TEXT_CSV).check(req.head()));
+
+        let req = TestRequest::default()
+            .insert_header((header::ACCEPT, "*/*;q=0"))
+            .to_http_request();
+        assert!(!Accept(mime::TEXT_PLAIN).check(req.head()));
+
+        // unparsable headers are ignored
+        let req = TestRequest::default()
+            .insert_header((header::ACCEPT, "text/html;q=2"))
+            .to_http_request();
+        assert!(Accept(mime::IMAGE_PNG).check(req.head()));
+    }
+
+    #[test]
+    fn test_accept_params() {
+        let v1: mime::Mime = "application/vnd.api+json; version=1".parse().unwrap();
+        let v2: mime::Mime = "application/vnd.api+json; version=2".parse().unwrap();
+
+        let req = TestRequest::default()
+            .insert_header((header::ACCEPT, "application/vnd.api+json; version=2"))
+            .to_http_request();
+        assert!(!Accept(v1.clone()).check(req.head()));
+        assert!(Accept(v2.clone()).check(req.head()));
+
+        let req = TestRequest::default()
+            .insert_header((header::ACCEPT, "application/vnd.api+json"))
+            .to_http_request();
+        assert!(Accept(v1.clone()).check(req.head()));
+        assert!(Accept(v2.clone()).check(req.head()));
+
+        // the most specific range decides
+        let req = TestRequest::default()
+            .insert_header((
+                header::ACCEPT,
+                "application/vnd.api+json, application/vnd.api+json;version=2;q=0",
+            ))
+            .to_http_request();
+        assert!(Accept(v1).check(req.head()));
+        assert!(!Accept(v2).check(req.head()));
+    }
+
+    #[test]
+    fn test_content_type() {
+        let req = TestRequest::default().to_http_request();
+        assert!(!ContentType(mime::APPLICATION_JSON).check(req.head()));
+
+        let req = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "text/plain; charset=utf-8"))
+            .to_http_request();
+        assert!(ContentType(mime::TEXT_PLAIN).check(req.head()));
+        assert!(ContentType(mime::TEXT_PLAIN_UTF_8).check(req.head()));
+        assert!(ContentType(mime::TEXT_STAR).check(req.head()));
+        assert!(ContentType(mime::STAR_STAR).check(req.head()));
+        assert!(!ContentType(mime::TEXT_HTML).check(req.head()));
+        assert!(!ContentType(mime::APPLICATION_JSON).check(req.head()));
+
+        let latin1: mime::Mime = "text/plain; charset=latin1".parse().unwrap();
+        assert!(!ContentType(latin1).check(req.head()));
+
+        let req = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "garbage"))
+            .to_http_request();
+        assert!(!ContentType(mime::STAR_STAR).check(req.head()));
+    }
+
+    #[actix_rt::test]
+    async fn test_accept_versioning() {
+        use crate::test::{call_service, init_service, read_body};
+        use crate::{web, App, HttpResponse};
+
+        let srv = init_service(
+            App::new().service(
+                web::resource("/user")
+                    .route(
+                        web::get()
+                            .guard(Accept(
+                                "application/vnd.api+json; version=2".parse().unwrap(),
+                            ))
+                            .to(|| HttpResponse::Ok().body("v2")),
+                    )
+                    .route(
+                        web::get()
+                            .guard(Accept(
+                                "application/vnd.api+json; version=1".parse().unwrap(),
+                            ))
+                            .to(|| HttpResponse::Ok().body("v1")),
+                    ),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/user")
+            .insert_header((header::ACCEPT, "application/vnd.api+json; version=1"))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, "v1");
+
+        let req = TestRequest::with_uri("/user")
+            .insert_header((header::ACCEPT, "application/vnd.api+json; version=2"))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, "v2");
+
+        let req = TestRequest::with_uri("/user")
+            .insert_header((header::ACCEPT, "text/html"))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        // no route matches so the resource's default service responds
+        assert_eq!(res.status(), http::StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[test]
+    fn test_guard_names() {
+        assert_eq!(Get().name(), "Method(GET)");
+        assert_eq!(
+            Any(Get()).or(Header("x", "y")).name(),
+            "Any(Method(GET), Header(x: \"y\"))"
+        );
+        assert_eq!(
+            Not(All(Post()).and(Host("a.com"))).name(),
+            "Not(All(Method(POST), Host(a.com)))"
+        );
+        assert_eq!(Accept(mime::TEXT_HTML).name(), "Accept(text/html)");
+        assert!(fn_guard(|_| true)
+            .name()
+            .starts_with("actix_web::guard::FnGuard<"));
+    }
 }
@@ -0,0 +1,264 @@
+use std::{
+    fmt::{self, Write},
+    str::FromStr,
+};
+
+use super::{from_one_raw_str, Header, IntoHeaderValue, Writer};
+
+use crate::http::header;
+
+/// `Content-Security-Policy` header, defined in
+/// [CSP Level 3](https://www.w3.org/TR/CSP3/#csp-header)
+///
+/// The `Content-Security-Policy` header field restricts the resources, such as scripts and
+/// styles, that a page may load, mitigating cross-site scripting.
+///
+/// # ABNF
+///
+/// ```text
+/// Content-Security-Policy = 1#serialized-policy
+/// serialized-policy       = directive *( ";" directive )
+/// directive               = directive-name [ 1*WSP directive-value ]
+/// ```
+///
+/// # Example values
+///
+/// * `default-src 'self'`
+/// * `default-src 'self'; img-src *; script-src 'self' 'nonce-2726c7f26c'`
+///
+/// # Examples
+/// ```
+/// use actix_web::{http::header::ContentSecurityPolicy, HttpResponse};
+///
+/// let mut builder = HttpResponse::Ok();
+/// builder.insert_header(
+///     ContentSecurityPolicy::new()
+///         .default_src(&["'self'"])
+///         .img_src(&["'self'", "https://images.example.com"])
+///         .frame_ancestors(&["'none'"]),
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContentSecurityPolicy(pub Vec<CspDirective>);
+
+crate::http::header::common_header_deref!(ContentSecurityPolicy => Vec<CspDirective>);
+
+/// Directive of a [`ContentSecurityPolicy`], such as `script-src 'self'`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CspDirective {
+    /// Name of the directive, such as `script-src`.
+    pub name: String,
+
+    /// Values of the directive, such as `'self'` or `https://cdn.example.com`.
+    pub sources: Vec<String>,
+}
+
+macro_rules! csp_directive {
+    ($($(#[$meta:meta])* $method:ident => $name:literal,)*) => {
+        $(
+            $(#[$meta])*
+            pub fn $method(self, sources: &[&str]) -> Self {
+                self.directive($name, sources)
+            }
+        )*
+    };
+}
+
+impl ContentSecurityPolicy {
+    /// Constructs an empty policy.
+    pub fn new() -> ContentSecurityPolicy {
+        ContentSecurityPolicy::default()
+    }
+
+    /// Sets the directive `name`, replacing any directive with the same name.
+    pub fn directive(mut self, name: &str, sources: &[&str]) -> Self {
+        let directive = CspDirective {
+            name: name.to_ascii_lowercase(),
+            sources: sources.iter().map(|source| (*source).to_owned()).collect(),
+        };
+
+        match self.0.iter_mut().find(|d| d.name == directive.name) {
+            Some(existing) => *existing = directive,
+            None => self.0.push(directive),
+        }
+
+        self
+    }
+
+    csp_directive! {
+        /// Sets the `default-src` directive, the fallback for other fetch directives.
+        default_src => "default-src",
+        /// Sets the `script-src` directive.
+        script_src => "script-src",
+        /// Sets the `style-src` directive.
+        style_src => "style-src",
+        /// Sets the `img-src` directive.
+        img_src => "img-src",
+        /// Sets the `connect-src` directive.
+        connect_src => "connect-src",
+        /// Sets the `font-src` directive.
+        font_src => "font-src",
+        /// Sets the `object-src` directive.
+        object_src => "object-src",
+        /// Sets the `base-uri` directive.
+        base_uri => "base-uri",
+        /// Sets the `form-action` directive.
+        form_action => "form-action",
+        /// Sets the `frame-ancestors` directive, restricting the pages that may embed this one.
+        frame_ancestors => "frame-ancestors",
+    }
+
+    /// Sets the `upgrade-insecure-requests` directive.
+    pub fn upgrade_insecure_requests(self) -> Self {
+        self.directive("upgrade-insecure-requests", &[])
+    }
+
+    /// Returns the directive `name`, if set.
+    pub fn get(&self, name: &str) -> Option<&CspDirective> {
+        self.0.iter().find(|d| d.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns a copy of the policy allowing scripts and styles with the given nonce.
+    ///
+    /// The `'nonce-…'` source is added to the `script-src` and `style-src` directives. Missing
+    /// directives are created from `default-src`, if set, so that they keep its sources.
+    pub fn with_nonce(&self, nonce: &str) -> ContentSecurityPolicy {
+        let mut csp = self.clone();
+        let source = format!("'nonce-{}'", nonce);
+
+        for name in &["script-src", "style-src"] {
+            if let Some(directive) = csp.0.iter_mut().find(|d| d.name == *name) {
+                directive.sources.push(source.clone());
+            } else if let Some(default) = self.get("default-src") {
+                let mut sources = default.sources.clone();
+                sources.push(source.clone());
+
+                csp.0.push(CspDirective {
+                    name: (*name).to_owned(),
+                    sources,
+                });
+            }
+        }
+
+        csp
+    }
+}
+
+impl fmt::Display for CspDirective {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+
+        for source in &self.sources {
+            write!(f, " {}", source)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for ContentSecurityPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, directive) in self.0.iter().enumerate() {
+            if idx > 0 {
+                f.write_str("; ")?;
+            }
+
+            write!(f, "{}", directive)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for ContentSecurityPolicy {
+    type Err = crate::error::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut csp = ContentSecurityPolicy::new();
+
+        for directive in s.split(';') {
+            let mut parts = directive.split_ascii_whitespace();
+
+            let name = match parts.next() {
+                Some(name) => name.to_ascii_lowercase(),
+                None => continue,
+            };
+
+            // as in browsers, only the first occurrence of a directive is used
+            if csp.get(&name).is_none() {
+                csp.0.push(CspDirective {
+                    name,
+                    sources: parts.map(str::to_owned).collect(),
+                });
+            }
+        }
+
+        if csp.is_empty() {
+            return Err(crate::error::ParseError::Header);
+        }
+
+        Ok(csp)
+    }
+}
+
+impl Header for ContentSecurityPolicy {
+    fn name() -> header::HeaderName {
+        header::CONTENT_SECURITY_POLICY
+    }
+
+    fn parse<T: crate::HttpMessage>(msg: &T) -> Result<Self, crate::error::ParseError> {
+        from_one_raw_str(msg.headers().get(&Self::name()))
+    }
+}
+
+impl IntoHeaderValue for ContentSecurityPolicy {
+    type Error = header::InvalidHeaderValue;
+
+    fn try_into_value(self) -> Result<header::HeaderValue, Self::Error> {
+        let mut writer = Writer::new();
+        let _ = write!(&mut writer, "{}", self);
+        header::HeaderValue::from_maybe_shared(writer.take())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_policies() {
+        let csp = ContentSecurityPolicy::new()
+            .default_src(&["'self'"])
+            .img_src(&["*"])
+            .default_src(&["'none'"])
+            .upgrade_insecure_requests();
+
+        assert_eq!(
+            csp.to_string(),
+            "default-src 'none'; img-src *; upgrade-insecure-requests"
+        );
+        assert_eq!(
+            csp.to_string().parse::<ContentSecurityPolicy>().unwrap(),
+            csp
+        );
+    }
+
+    #[test]
+    fn adds_nonces() {
+        let csp = ContentSecurityPolicy::new()
+            .default_src(&["'self'"])
+            .script_src(&["'strict-dynamic'"])
+            .with_nonce("abc");
+
+        assert_eq!(
+            csp.to_string(),
+            "default-src 'self'; script-src 'strict-dynamic' 'nonce-abc'; \
+             style-src 'self' 'nonce-abc'"
+        );
+
+        let csp = ContentSecurityPolicy::new()
+            .img_src(&["*"])
+            .with_nonce("abc");
+        assert_eq!(csp.to_string(), "img-src *");
+    }
+}
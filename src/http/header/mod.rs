@@ -18,6 +18,7 @@ pub use self::cache_control::{CacheControl, CacheDirective};
 pub use self::content_disposition::{ContentDisposition, DispositionParam, DispositionType};
 pub use self::content_language::ContentLanguage;
 pub use self::content_range::{ContentRange, ContentRangeSpec};
+pub use self::content_security_policy::{ContentSecurityPolicy, CspDirective};
 pub use self::content_type::ContentType;
 pub use self::date::Date;
 pub use self::encoding::Encoding;
@@ -30,6 +31,11 @@ pub use self::if_none_match::IfNoneMatch;
 pub use self::if_range::IfRange;
 pub use self::if_unmodified_since::IfUnmodifiedSince;
 pub use self::last_modified::LastModified;
+pub use self::permissions_policy::PermissionsPolicy;
+pub use self::referrer_policy::ReferrerPolicy;
+pub use self::strict_transport_security::StrictTransportSecurity;
+pub use self::x_content_type_options::XContentTypeOptions;
+pub use self::x_frame_options::XFrameOptions;
 //pub use self::range::{Range, ByteRangeSpec};
 pub(crate) use actix_http::http::header::{
     fmt_comma_delimited, from_comma_delimited, from_one_raw_str,
@@ -72,6 +78,7 @@ mod cache_control;
 mod content_disposition;
 mod content_language;
 mod content_range;
+mod content_security_policy;
 mod content_type;
 mod date;
 mod encoding;
@@ -84,6 +91,11 @@ mod if_none_match;
 mod if_range;
 mod if_unmodified_since;
 mod last_modified;
+mod permissions_policy;
+mod referrer_policy;
+mod strict_transport_security;
+mod x_content_type_options;
+mod x_frame_options;
 
 mod macros;
 #[cfg(test)]
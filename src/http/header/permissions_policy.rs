@@ -0,0 +1,184 @@
+use std::{
+    fmt::{self, Write},
+    str::FromStr,
+};
+
+use super::{from_one_raw_str, Header, HeaderName, IntoHeaderValue, Writer};
+
+use crate::http::header;
+
+/// `Permissions-Policy` header, defined in
+/// [Permissions Policy](https://www.w3.org/TR/permissions-policy-1/#permissions-policy-http-header-field)
+///
+/// The `Permissions-Policy` header field controls which browser features, such as the camera or
+/// geolocation, the page and the frames it embeds may use.
+///
+/// Each feature has an allowlist: `*` for all origins, `self` for the page's own origin, or
+/// origins such as `https://maps.example.com`. An empty allowlist disables the feature.
+///
+/// # Example values
+///
+/// * `camera=(), microphone=()`
+/// * `geolocation=(self "https://maps.example.com"), fullscreen=*`
+///
+/// # Examples
+/// ```
+/// use actix_web::{http::header::PermissionsPolicy, HttpResponse};
+///
+/// let mut builder = HttpResponse::Ok();
+/// builder.insert_header(
+///     PermissionsPolicy::new()
+///         .deny("camera")
+///         .deny("microphone")
+///         .allow("geolocation", &["self", "https://maps.example.com"]),
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PermissionsPolicy(pub Vec<(String, Vec<String>)>);
+
+crate::http::header::common_header_deref!(PermissionsPolicy => Vec<(String, Vec<String>)>);
+
+impl PermissionsPolicy {
+    /// Constructs an empty policy.
+    pub fn new() -> PermissionsPolicy {
+        PermissionsPolicy::default()
+    }
+
+    /// Sets the allowlist of `feature`, replacing any previous allowlist.
+    pub fn allow(mut self, feature: &str, allowlist: &[&str]) -> Self {
+        let allowlist = allowlist
+            .iter()
+            .map(|origin| (*origin).to_owned())
+            .collect();
+
+        match self.0.iter_mut().find(|(name, _)| name == feature) {
+            Some((_, existing)) => *existing = allowlist,
+            None => self.0.push((feature.to_owned(), allowlist)),
+        }
+
+        self
+    }
+
+    /// Allows `feature` for the page's own origin only.
+    pub fn allow_self(self, feature: &str) -> Self {
+        self.allow(feature, &["self"])
+    }
+
+    /// Disables `feature` for all origins.
+    pub fn deny(self, feature: &str) -> Self {
+        self.allow(feature, &[])
+    }
+
+    /// Returns the allowlist of `feature`, if set.
+    pub fn get(&self, feature: &str) -> Option<&[String]> {
+        self.0
+            .iter()
+            .find(|(name, _)| name == feature)
+            .map(|(_, allowlist)| allowlist.as_slice())
+    }
+}
+
+impl fmt::Display for PermissionsPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, (feature, allowlist)) in self.0.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+
+            write!(f, "{}=", feature)?;
+
+            if allowlist.len() == 1 && allowlist[0] == "*" {
+                f.write_str("*")?;
+                continue;
+            }
+
+            f.write_char('(')?;
+
+            for (idx, origin) in allowlist.iter().enumerate() {
+                if idx > 0 {
+                    f.write_char(' ')?;
+                }
+
+                match origin.as_str() {
+                    "self" | "src" | "*" => f.write_str(origin)?,
+                    origin => write!(f, "\"{}\"", origin)?,
+                }
+            }
+
+            f.write_char(')')?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for PermissionsPolicy {
+    type Err = crate::error::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut policy = PermissionsPolicy::new();
+
+        for entry in s
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let idx = entry.find('=').ok_or(crate::error::ParseError::Header)?;
+            let feature = entry[..idx].trim().to_owned();
+            let value = entry[idx + 1..].trim();
+
+            let allowlist = if value == "*" {
+                vec!["*".to_owned()]
+            } else if value.starts_with('(') && value.ends_with(')') {
+                value[1..value.len() - 1]
+                    .split_ascii_whitespace()
+                    .map(|origin| origin.trim_matches('"').to_owned())
+                    .collect()
+            } else {
+                return Err(crate::error::ParseError::Header);
+            };
+
+            policy.0.push((feature, allowlist));
+        }
+
+        Ok(policy)
+    }
+}
+
+impl Header for PermissionsPolicy {
+    fn name() -> HeaderName {
+        HeaderName::from_static("permissions-policy")
+    }
+
+    fn parse<T: crate::HttpMessage>(msg: &T) -> Result<Self, crate::error::ParseError> {
+        from_one_raw_str(msg.headers().get(&Self::name()))
+    }
+}
+
+impl IntoHeaderValue for PermissionsPolicy {
+    type Error = header::InvalidHeaderValue;
+
+    fn try_into_value(self) -> Result<header::HeaderValue, Self::Error> {
+        let mut writer = Writer::new();
+        let _ = write!(&mut writer, "{}", self);
+        header::HeaderValue::from_maybe_shared(writer.take())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let policy = PermissionsPolicy::new()
+            .deny("camera")
+            .allow("geolocation", &["self", "https://maps.example.com"])
+            .allow("fullscreen", &["*"]);
+
+        let value = r#"camera=(), geolocation=(self "https://maps.example.com"), fullscreen=*"#;
+        assert_eq!(policy.to_string(), value);
+        assert_eq!(value.parse::<PermissionsPolicy>().unwrap(), policy);
+        assert_eq!(policy.get("camera"), Some(&[][..]));
+    }
+}
@@ -0,0 +1,106 @@
+use std::{fmt, str::FromStr};
+
+use super::{from_one_raw_str, Header, HeaderValue, IntoHeaderValue, InvalidHeaderValue};
+
+use crate::http::header;
+
+/// `Referrer-Policy` header, defined in
+/// [Referrer Policy](https://www.w3.org/TR/referrer-policy/#referrer-policy-header)
+///
+/// The `Referrer-Policy` header field controls how much referrer information browsers send with
+/// requests made from the page.
+///
+/// # Example values
+///
+/// * `no-referrer`
+/// * `strict-origin-when-cross-origin`
+///
+/// # Examples
+/// ```
+/// use actix_web::{http::header::ReferrerPolicy, HttpResponse};
+///
+/// let mut builder = HttpResponse::Ok();
+/// builder.insert_header(ReferrerPolicy::StrictOriginWhenCrossOrigin);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferrerPolicy {
+    /// `no-referrer`
+    NoReferrer,
+    /// `no-referrer-when-downgrade`
+    NoReferrerWhenDowngrade,
+    /// `origin`
+    Origin,
+    /// `origin-when-cross-origin`
+    OriginWhenCrossOrigin,
+    /// `same-origin`
+    SameOrigin,
+    /// `strict-origin`
+    StrictOrigin,
+    /// `strict-origin-when-cross-origin`
+    StrictOriginWhenCrossOrigin,
+    /// `unsafe-url`
+    UnsafeUrl,
+}
+
+impl ReferrerPolicy {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ReferrerPolicy::NoReferrer => "no-referrer",
+            ReferrerPolicy::NoReferrerWhenDowngrade => "no-referrer-when-downgrade",
+            ReferrerPolicy::Origin => "origin",
+            ReferrerPolicy::OriginWhenCrossOrigin => "origin-when-cross-origin",
+            ReferrerPolicy::SameOrigin => "same-origin",
+            ReferrerPolicy::StrictOrigin => "strict-origin",
+            ReferrerPolicy::StrictOriginWhenCrossOrigin => "strict-origin-when-cross-origin",
+            ReferrerPolicy::UnsafeUrl => "unsafe-url",
+        }
+    }
+}
+
+impl fmt::Display for ReferrerPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ReferrerPolicy {
+    type Err = crate::error::ParseError;
+
+    /// Parses the last recognized policy of a comma-separated list, as browsers do.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .rev()
+            .find_map(|policy| match policy.trim().to_ascii_lowercase().as_str() {
+                "no-referrer" => Some(ReferrerPolicy::NoReferrer),
+                "no-referrer-when-downgrade" => Some(ReferrerPolicy::NoReferrerWhenDowngrade),
+                "origin" => Some(ReferrerPolicy::Origin),
+                "origin-when-cross-origin" => Some(ReferrerPolicy::OriginWhenCrossOrigin),
+                "same-origin" => Some(ReferrerPolicy::SameOrigin),
+                "strict-origin" => Some(ReferrerPolicy::StrictOrigin),
+                "strict-origin-when-cross-origin" => {
+                    Some(ReferrerPolicy::StrictOriginWhenCrossOrigin)
+                }
+                "unsafe-url" => Some(ReferrerPolicy::UnsafeUrl),
+                _ => None,
+            })
+            .ok_or(crate::error::ParseError::Header)
+    }
+}
+
+impl Header for ReferrerPolicy {
+    fn name() -> header::HeaderName {
+        header::REFERRER_POLICY
+    }
+
+    fn parse<T: crate::HttpMessage>(msg: &T) -> Result<Self, crate::error::ParseError> {
+        from_one_raw_str(msg.headers().get(&Self::name()))
+    }
+}
+
+impl IntoHeaderValue for ReferrerPolicy {
+    type Error = InvalidHeaderValue;
+
+    fn try_into_value(self) -> Result<HeaderValue, Self::Error> {
+        Ok(HeaderValue::from_static(self.as_str()))
+    }
+}
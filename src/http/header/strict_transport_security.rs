@@ -0,0 +1,191 @@
+use std::{
+    fmt::{self, Write},
+    str::FromStr,
+    time::Duration,
+};
+
+use super::{from_one_raw_str, Header, IntoHeaderValue, Writer};
+
+use crate::http::header;
+
+/// `Strict-Transport-Security` header, defined in
+/// [RFC6797](https://tools.ietf.org/html/rfc6797#section-6.1)
+///
+/// The `Strict-Transport-Security` header field tells browsers to only access the host over HTTPS
+/// for the given duration. It is ignored by browsers when received over plain HTTP.
+///
+/// # ABNF
+///
+/// ```text
+/// Strict-Transport-Security = directive *( ";" directive )
+/// directive                 = directive-name [ "=" directive-value ]
+/// ```
+///
+/// # Example values
+///
+/// * `max-age=31536000`
+/// * `max-age=63072000; includeSubDomains; preload`
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use actix_web::{http::header::StrictTransportSecurity, HttpResponse};
+///
+/// let mut builder = HttpResponse::Ok();
+/// builder.insert_header(
+///     StrictTransportSecurity::new(Duration::from_secs(63_072_000))
+///         .include_subdomains()
+///         .preload(),
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StrictTransportSecurity {
+    max_age: Duration,
+    include_subdomains: bool,
+    preload: bool,
+}
+
+impl StrictTransportSecurity {
+    /// Constructs a header telling browsers to only use HTTPS for `max_age`.
+    pub fn new(max_age: Duration) -> StrictTransportSecurity {
+        StrictTransportSecurity {
+            max_age,
+            include_subdomains: false,
+            preload: false,
+        }
+    }
+
+    /// Applies the policy to all subdomains of the host as well.
+    pub fn include_subdomains(mut self) -> Self {
+        self.include_subdomains = true;
+        self
+    }
+
+    /// Consents to the host being included in the HSTS preload lists of browsers.
+    ///
+    /// Preload lists require a `max_age` of at least a year and subdomains to be included.
+    pub fn preload(mut self) -> Self {
+        self.preload = true;
+        self
+    }
+
+    /// Returns the duration for which browsers must only use HTTPS.
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    /// Returns whether the policy applies to subdomains.
+    pub fn includes_subdomains(&self) -> bool {
+        self.include_subdomains
+    }
+
+    /// Returns whether the host consents to being preloaded.
+    pub fn is_preload(&self) -> bool {
+        self.preload
+    }
+}
+
+impl Default for StrictTransportSecurity {
+    /// A policy of one year, not including subdomains.
+    fn default() -> Self {
+        StrictTransportSecurity::new(Duration::from_secs(365 * 24 * 60 * 60))
+    }
+}
+
+impl fmt::Display for StrictTransportSecurity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "max-age={}", self.max_age.as_secs())?;
+
+        if self.include_subdomains {
+            f.write_str("; includeSubDomains")?;
+        }
+
+        if self.preload {
+            f.write_str("; preload")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for StrictTransportSecurity {
+    type Err = crate::error::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut max_age = None;
+        let mut hsts = StrictTransportSecurity::new(Duration::from_secs(0));
+
+        for directive in s.split(';').map(str::trim).filter(|d| !d.is_empty()) {
+            let (name, value) = match directive.find('=') {
+                Some(idx) => (
+                    &directive[..idx],
+                    Some(directive[idx + 1..].trim_matches('"')),
+                ),
+                None => (directive, None),
+            };
+
+            match (name.trim().to_ascii_lowercase().as_str(), value) {
+                ("max-age", Some(value)) => {
+                    let secs = value
+                        .parse()
+                        .map_err(|_| crate::error::ParseError::Header)?;
+                    max_age = Some(Duration::from_secs(secs));
+                }
+                ("includesubdomains", None) => hsts.include_subdomains = true,
+                ("preload", None) => hsts.preload = true,
+
+                // unknown directives are ignored
+                _ => {}
+            }
+        }
+
+        hsts.max_age = max_age.ok_or(crate::error::ParseError::Header)?;
+        Ok(hsts)
+    }
+}
+
+impl Header for StrictTransportSecurity {
+    fn name() -> header::HeaderName {
+        header::STRICT_TRANSPORT_SECURITY
+    }
+
+    fn parse<T: crate::HttpMessage>(msg: &T) -> Result<Self, crate::error::ParseError> {
+        from_one_raw_str(msg.headers().get(&Self::name()))
+    }
+}
+
+impl IntoHeaderValue for StrictTransportSecurity {
+    type Error = header::InvalidHeaderValue;
+
+    fn try_into_value(self) -> Result<header::HeaderValue, Self::Error> {
+        let mut writer = Writer::new();
+        let _ = write!(&mut writer, "{}", self);
+        header::HeaderValue::from_maybe_shared(writer.take())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let hsts = StrictTransportSecurity::new(Duration::from_secs(600))
+            .include_subdomains()
+            .preload();
+        assert_eq!(hsts.to_string(), "max-age=600; includeSubDomains; preload");
+        assert_eq!(
+            hsts.to_string().parse::<StrictTransportSecurity>().unwrap(),
+            hsts
+        );
+
+        let hsts = "max-age=\"60\"".parse::<StrictTransportSecurity>().unwrap();
+        assert_eq!(hsts.max_age(), Duration::from_secs(60));
+        assert!(!hsts.includes_subdomains());
+
+        assert!("includeSubDomains"
+            .parse::<StrictTransportSecurity>()
+            .is_err());
+    }
+}
@@ -0,0 +1,58 @@
+use std::{fmt, str::FromStr};
+
+use super::{from_one_raw_str, Header, HeaderValue, IntoHeaderValue, InvalidHeaderValue};
+
+use crate::http::header;
+
+/// `X-Content-Type-Options` header, defined in the
+/// [Fetch Standard](https://fetch.spec.whatwg.org/#x-content-type-options-header)
+///
+/// The only value, `nosniff`, tells browsers not to guess the type of responses, and to block
+/// scripts and styles whose `Content-Type` does not match.
+///
+/// # Examples
+/// ```
+/// use actix_web::{http::header::XContentTypeOptions, HttpResponse};
+///
+/// let mut builder = HttpResponse::Ok();
+/// builder.insert_header(XContentTypeOptions);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct XContentTypeOptions;
+
+impl fmt::Display for XContentTypeOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("nosniff")
+    }
+}
+
+impl FromStr for XContentTypeOptions {
+    type Err = crate::error::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split(',').next() {
+            Some(value) if value.trim().eq_ignore_ascii_case("nosniff") => {
+                Ok(XContentTypeOptions)
+            }
+            _ => Err(crate::error::ParseError::Header),
+        }
+    }
+}
+
+impl Header for XContentTypeOptions {
+    fn name() -> header::HeaderName {
+        header::X_CONTENT_TYPE_OPTIONS
+    }
+
+    fn parse<T: crate::HttpMessage>(msg: &T) -> Result<Self, crate::error::ParseError> {
+        from_one_raw_str(msg.headers().get(&Self::name()))
+    }
+}
+
+impl IntoHeaderValue for XContentTypeOptions {
+    type Error = InvalidHeaderValue;
+
+    fn try_into_value(self) -> Result<HeaderValue, Self::Error> {
+        Ok(HeaderValue::from_static("nosniff"))
+    }
+}
@@ -0,0 +1,76 @@
+use std::{fmt, str::FromStr};
+
+use super::{from_one_raw_str, Header, HeaderValue, IntoHeaderValue, InvalidHeaderValue};
+
+use crate::http::header;
+
+/// `X-Frame-Options` header, defined in [RFC7034](https://tools.ietf.org/html/rfc7034)
+///
+/// The `X-Frame-Options` header field tells browsers whether the page may be displayed in a
+/// frame, protecting against clickjacking. It is superseded by the `frame-ancestors` directive
+/// of [`ContentSecurityPolicy`](super::ContentSecurityPolicy), but still useful for older browsers.
+///
+/// # Examples
+/// ```
+/// use actix_web::{http::header::XFrameOptions, HttpResponse};
+///
+/// let mut builder = HttpResponse::Ok();
+/// builder.insert_header(XFrameOptions::Deny);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XFrameOptions {
+    /// `DENY`: the page cannot be displayed in a frame.
+    Deny,
+
+    /// `SAMEORIGIN`: the page can only be displayed in a frame on the same origin.
+    SameOrigin,
+}
+
+impl XFrameOptions {
+    fn as_str(&self) -> &'static str {
+        match self {
+            XFrameOptions::Deny => "DENY",
+            XFrameOptions::SameOrigin => "SAMEORIGIN",
+        }
+    }
+}
+
+impl fmt::Display for XFrameOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for XFrameOptions {
+    type Err = crate::error::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if s.eq_ignore_ascii_case("deny") {
+            Ok(XFrameOptions::Deny)
+        } else if s.eq_ignore_ascii_case("sameorigin") {
+            Ok(XFrameOptions::SameOrigin)
+        } else {
+            Err(crate::error::ParseError::Header)
+        }
+    }
+}
+
+impl Header for XFrameOptions {
+    fn name() -> header::HeaderName {
+        header::X_FRAME_OPTIONS
+    }
+
+    fn parse<T: crate::HttpMessage>(msg: &T) -> Result<Self, crate::error::ParseError> {
+        from_one_raw_str(msg.headers().get(&Self::name()))
+    }
+}
+
+impl IntoHeaderValue for XFrameOptions {
+    type Error = InvalidHeaderValue;
+
+    fn try_into_value(self) -> Result<HeaderValue, Self::Error> {
+        Ok(HeaderValue::from_static(self.as_str()))
+    }
+}
@@ -62,7 +62,9 @@
 //! * `compress-zstd` - zstd content encoding compression support (enabled by default)
 //! * `openssl` - HTTPS support via `openssl` crate, supports `HTTP/2`
 //! * `rustls` - HTTPS support via `rustls` crate, supports `HTTP/2`
-//! * `secure-cookies` - secure cookies support
+//! * `secure-cookies` - secure cookies support, and cookie-backed [sessions](session)
+//! * `tracing` - a [`tracing`](https://docs.rs/tracing) span for each request, carrying its
+//!   method, route pattern, peer address, status code and latency, and for each connection
 
 #![deny(rust_2018_idioms, nonstandard_style)]
 #![allow(clippy::needless_doctest_main, clippy::type_complexity)]
@@ -74,6 +76,7 @@ mod app_service;
 mod config;
 mod data;
 pub mod dev;
+mod dynamic_routes;
 pub mod error;
 mod extract;
 pub mod guard;
@@ -82,16 +85,21 @@ mod helpers;
 pub mod http;
 mod info;
 pub mod middleware;
+pub mod openapi;
 mod request;
 mod request_data;
+mod request_span;
 mod resource;
 mod responder;
 mod response;
 mod rmap;
 mod route;
+mod routing_trace;
 mod scope;
 mod server;
 mod service;
+#[cfg(feature = "secure-cookies")]
+pub mod session;
 pub mod test;
 pub(crate) mod types;
 pub mod web;
@@ -104,6 +112,7 @@ pub use actix_web_codegen::*;
 pub use cookie;
 
 pub use crate::app::App;
+pub use crate::dynamic_routes::DynamicRoutes;
 pub use crate::error::{Error, ResponseError, Result};
 pub use crate::extract::FromRequest;
 pub use crate::request::HttpRequest;
@@ -0,0 +1,350 @@
+//! For middleware documentation, see [`BodyLimit`].
+
+use std::{
+    cell::Cell,
+    error::Error as StdError,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use actix_http::error::PayloadError;
+use actix_utils::future::{ready, Ready};
+use bytes::Bytes;
+use futures_core::{future::LocalBoxFuture, Stream};
+
+use crate::{
+    body::{Body, MessageBody},
+    dev::{Payload, Service, Transform},
+    http::header,
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpMessage as _, HttpRequest,
+};
+
+/// Middleware for limiting the size of request bodies.
+///
+/// Requests whose `Content-Length` exceeds the limit are answered with *413 Payload Too Large*
+/// without calling the wrapped service. The payload of other requests is wrapped so that reading
+/// it fails with [`PayloadError::Overflow`], answered with *413 Payload Too Large* as well, once
+/// more bytes than the limit are received. This applies to all extractors, including
+/// [`web::Payload`](crate::web::Payload) and other extractors reading the raw payload stream, such
+/// as multipart forms.
+///
+/// The limit also becomes the default limit of the built-in body extractors. Registering a
+/// [`PayloadConfig`](crate::web::PayloadConfig), [`JsonConfig`](crate::web::JsonConfig) or
+/// [`FormConfig`](crate::web::FormConfig) for a scope or resource lowers it for the requests read
+/// by that extractor; configured limits above the limit of the middleware have no effect. Nested
+/// `BodyLimit` middleware can likewise only lower the limit.
+///
+/// # Examples
+/// ```
+/// use actix_web::{middleware::BodyLimit, web, App, HttpResponse};
+///
+/// let app = App::new()
+///     .wrap(BodyLimit::new(1_048_576))
+///     .route("/upload", web::post().to(|_: web::Payload| HttpResponse::Ok()))
+///     .service(
+///         web::resource("/small")
+///             .app_data(web::JsonConfig::default().limit(4096))
+///             .route(web::post().to(|_: web::Json<String>| HttpResponse::Ok())),
+///     );
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct BodyLimit {
+    limit: usize,
+}
+
+impl BodyLimit {
+    /// Constructs a `BodyLimit` middleware limiting request bodies to `limit` bytes.
+    pub fn new(limit: usize) -> BodyLimit {
+        BodyLimit { limit }
+    }
+}
+
+/// Current limit of a request's body, shared between the request extensions and its payload.
+#[derive(Clone)]
+struct Limit(Rc<Cell<usize>>);
+
+/// Returns the limit of a body extractor, and makes it the limit of the request payload.
+///
+/// The `configured` limit of an extractor config registered as app data applies if it is lower
+/// than the limit of the middleware. Without the middleware, it takes precedence over the
+/// `default` limit.
+pub(crate) fn extractor_limit(
+    req: &HttpRequest,
+    configured: Option<usize>,
+    default: usize,
+) -> usize {
+    let extensions = req.extensions();
+    let limit = extensions.get::<Limit>();
+
+    match (configured, limit) {
+        (Some(configured), Some(limit)) => {
+            let configured = configured.min(limit.0.get());
+            limit.0.set(configured);
+            configured
+        }
+        (Some(configured), None) => configured,
+        (None, Some(limit)) => limit.0.get(),
+        (None, None) => default,
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for BodyLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Transform = BodyLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(BodyLimitMiddleware {
+            service,
+            limit: self.limit,
+        }))
+    }
+}
+
+pub struct BodyLimitMiddleware<S> {
+    service: S,
+    limit: usize,
+}
+
+impl<S, B> Service<ServiceRequest> for BodyLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let existing = req.extensions().get::<Limit>().cloned();
+
+        // a nested middleware lowers the limit of the outer one
+        let limit = match existing {
+            Some(limit) => {
+                limit.0.set(limit.0.get().min(self.limit));
+                None
+            }
+            None => Some(Limit(Rc::new(Cell::new(self.limit)))),
+        };
+
+        let length = req
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|val| val.to_str().ok())
+            .and_then(|val| val.parse::<u64>().ok());
+
+        if matches!(length, Some(length) if length > self.limit as u64) {
+            let res = req.error_response(PayloadError::Overflow);
+            return Box::pin(async { Ok(res) });
+        }
+
+        if let Some(limit) = limit {
+            let payload = LimitedPayload {
+                payload: req.take_payload(),
+                limit: limit.clone(),
+                length,
+                read: 0,
+            };
+
+            req.set_payload(Payload::Stream(Box::pin(payload)));
+            req.extensions_mut().insert(limit);
+        }
+
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let res = fut.await?;
+            Ok(res.map_body(|_, body| Body::from_message(body)))
+        })
+    }
+}
+
+/// Payload stream failing once the limit is exceeded.
+struct LimitedPayload {
+    payload: Payload,
+    limit: Limit,
+    length: Option<u64>,
+    read: usize,
+}
+
+impl Stream for LimitedPayload {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let limit = this.limit.0.get();
+
+        if matches!(this.length, Some(length) if length > limit as u64) {
+            return Poll::Ready(Some(Err(PayloadError::Overflow)));
+        }
+
+        match Pin::new(&mut this.payload).poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                this.read += chunk.len();
+
+                if this.read > limit {
+                    Poll::Ready(Some(Err(PayloadError::Overflow)))
+                } else {
+                    Poll::Ready(Some(Ok(chunk)))
+                }
+            }
+            res => res,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use futures_util::StreamExt as _;
+
+    use super::*;
+    use crate::{
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    async fn read(mut payload: web::Payload) -> Result<HttpResponse, Error> {
+        let mut body = BytesMut::new();
+
+        while let Some(chunk) = payload.next().await {
+            body.extend_from_slice(&chunk?);
+        }
+
+        Ok(HttpResponse::Ok().body(body.freeze()))
+    }
+
+    #[actix_rt::test]
+    async fn limits_streaming_payloads() {
+        let srv = test::init_service(
+            App::new()
+                .wrap(BodyLimit::new(8))
+                .route("/", web::post().to(read)),
+        )
+        .await;
+
+        let req = TestRequest::post().set_payload("12345678").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::post().set_payload("123456789").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // chunked body without a Content-Length header
+        let (mut sender, payload) = actix_http::h1::Payload::create(false);
+        sender.feed_data(Bytes::from_static(b"12345"));
+        sender.feed_data(Bytes::from_static(b"6789"));
+        sender.feed_eof();
+        let (req, _) = TestRequest::post()
+            .to_request()
+            .replace_payload(payload.into());
+
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[actix_rt::test]
+    async fn rejects_large_content_length() {
+        let srv = test::init_service(
+            App::new()
+                .wrap(BodyLimit::new(8))
+                .route("/", web::post().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        // the handler does not read the body
+        let req = TestRequest::post()
+            .insert_header((header::CONTENT_LENGTH, 9))
+            .set_payload("123456789")
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let req = TestRequest::post()
+            .insert_header((header::CONTENT_LENGTH, 8))
+            .set_payload("12345678")
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // nested middleware only lower the limit
+        let srv = test::init_service(
+            App::new().wrap(BodyLimit::new(8)).service(
+                web::scope("/nested")
+                    .wrap(BodyLimit::new(4))
+                    .route("/", web::post().to(read)),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/nested/")
+            .insert_header((header::CONTENT_LENGTH, 5))
+            .set_payload("12345")
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[actix_rt::test]
+    async fn extractor_configs_lower_limit() {
+        let srv = test::init_service(
+            App::new()
+                .wrap(BodyLimit::new(8))
+                .service(
+                    web::resource("/small")
+                        .app_data(web::PayloadConfig::new(4))
+                        .route(web::post().to(|body: Bytes| HttpResponse::Ok().body(body))),
+                )
+                .service(
+                    web::resource("/large")
+                        .app_data(web::PayloadConfig::new(16))
+                        .route(web::post().to(|body: Bytes| HttpResponse::Ok().body(body))),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/small")
+            .set_payload("1234")
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::post()
+            .uri("/small")
+            .set_payload("12345")
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // chunked body without a Content-Length header
+        let (mut sender, payload) = actix_http::h1::Payload::create(false);
+        sender.feed_data(Bytes::from_static(b"12345"));
+        sender.feed_data(Bytes::from_static(b"6789"));
+        sender.feed_eof();
+        let (req, _) = TestRequest::post()
+            .uri("/large")
+            .to_request()
+            .replace_payload(payload.into());
+
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
@@ -0,0 +1,798 @@
+//! For middleware documentation, see [`Cache`].
+
+use std::{
+    convert::TryFrom,
+    error::Error as StdError,
+    fmt,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    str::FromStr,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use actix_http::body::{to_bytes, Body, BodySize, MessageBody};
+use actix_utils::future::{ready, Ready};
+use ahash::AHashMap;
+use bytes::Bytes;
+use futures_core::future::LocalBoxFuture;
+
+use crate::{
+    dev::{Service, Transform},
+    http::{
+        header::{self, CacheDirective, HeaderMap, HeaderName, HeaderValue},
+        Method, StatusCode,
+    },
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpRequest, HttpResponse,
+};
+
+/// Default maximum number of cached responses.
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// Default maximum size of cached response bodies.
+const DEFAULT_MAX_ENTRY_SIZE: usize = 1_048_576; // 1 MiB
+
+/// Middleware for caching responses in memory.
+///
+/// Responses to `GET` and `HEAD` requests are stored when their `Cache-Control` header gives them
+/// a freshness lifetime with the `s-maxage` or `max-age` directive, and served from the cache
+/// until they become stale, with an `Age` header. Responses are not stored if they:
+/// - have the `no-store`, `no-cache` or `private` directive;
+/// - have a `Set-Cookie` header or a `Vary: *` header;
+/// - have a status code that is not cacheable by default, such as *500 Internal Server Error*;
+/// - have a streaming body, or a body larger than the [maximum entry
+///   size](Self::max_entry_size).
+///
+/// Responses are keyed by request method, host and URI, and by the request headers listed in their
+/// `Vary` header. Requests with an `Authorization` header or a `no-store` directive bypass the
+/// cache, and requests with a `no-cache` or `max-age=0` directive are handled by the app, but
+/// can update the cache.
+///
+/// Concurrent requests for the same method, host and URI that miss the cache are coalesced: one of them
+/// is handled by the app while the others wait to be served its response from the cache. If the
+/// response could not be stored, the waiting requests are handled by the app without waiting
+/// again. Coalescing therefore only helps with cacheable responses; for responses that are never
+/// stored, such as `private` ones, it delays the waiting requests by one round trip to the app.
+///
+/// The cache holds a bounded number of responses, set by [`capacity`](Self::capacity), evicting
+/// the least recently used ones. Clones of a `Cache` share the same storage, so create the
+/// middleware outside of the `HttpServer` app factory and clone it into each app to share
+/// cached responses between workers.
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     http::header::{CacheControl, CacheDirective},
+///     middleware::Cache,
+///     web, App, HttpResponse, HttpServer,
+/// };
+///
+/// # fn run() -> std::io::Result<()> {
+/// let cache = Cache::new().capacity(256);
+///
+/// let server = HttpServer::new(move || {
+///     App::new().wrap(cache.clone()).route(
+///         "/",
+///         web::get().to(|| {
+///             HttpResponse::Ok()
+///                 .insert_header(CacheControl(vec![CacheDirective::MaxAge(60)]))
+///                 .body("expensive")
+///         }),
+///     )
+/// });
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Cache {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    capacity: usize,
+    max_entry_size: usize,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    entries: AHashMap<Key, Vec<Entry>>,
+    len: usize,
+    clock: u64,
+
+    /// Requests being handled, by key.
+    pending: AHashMap<Key, Pending>,
+
+    /// ID of the last request registered in `pending`.
+    last_pending_id: u64,
+}
+
+/// A request being handled, and the wakers of the requests waiting for its response.
+struct Pending {
+    id: u64,
+    wakers: Vec<Waker>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Key {
+    method: Method,
+    /// Lowercased authority of the request, since the URI of HTTP/1.1 requests is only a path.
+    host: Option<String>,
+    uri: String,
+}
+
+impl Key {
+    fn new(req: &ServiceRequest) -> Key {
+        let host = req
+            .headers()
+            .get(header::HOST)
+            .and_then(|val| val.to_str().ok())
+            .or_else(|| req.uri().authority().map(|authority| authority.as_str()))
+            .map(str::to_ascii_lowercase);
+
+        Key {
+            method: req.method().clone(),
+            host,
+            uri: req.uri().to_string(),
+        }
+    }
+}
+
+/// A stored response.
+struct Entry {
+    /// Values of the request headers listed in the response's `Vary` header.
+    vary: Vec<(HeaderName, Option<HeaderValue>)>,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    stored: Instant,
+    ttl: Duration,
+    last_used: u64,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Cache {
+            inner: Arc::new(Inner {
+                capacity: DEFAULT_CAPACITY,
+                max_entry_size: DEFAULT_MAX_ENTRY_SIZE,
+                state: Mutex::new(State::default()),
+            }),
+        }
+    }
+}
+
+impl Cache {
+    /// Constructs a `Cache` middleware with the default capacity.
+    pub fn new() -> Cache {
+        Cache::default()
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Arc::get_mut(&mut self.inner).expect("Multiple `Inner` copies exist.")
+    }
+
+    /// Sets the maximum number of cached responses.
+    ///
+    /// Default is 1024.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware was cloned.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.inner_mut().capacity = capacity;
+        self
+    }
+
+    /// Sets the maximum size of cached response bodies.
+    ///
+    /// Default is 1 MiB.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware was cloned.
+    pub fn max_entry_size(mut self, size: usize) -> Self {
+        self.inner_mut().max_entry_size = size;
+        self
+    }
+
+    /// Returns the number of cached responses, including stale ones not yet evicted.
+    pub fn len(&self) -> usize {
+        self.inner.state().len
+    }
+
+    /// Returns `true` if no responses are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all cached responses.
+    pub fn clear(&self) {
+        let mut state = self.inner.state();
+        state.entries.clear();
+        state.len = 0;
+    }
+}
+
+impl fmt::Debug for Cache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cache")
+            .field("capacity", &self.inner.capacity)
+            .field("max_entry_size", &self.inner.max_entry_size)
+            .finish()
+    }
+}
+
+/// Outcome of looking up a request in the cache.
+enum Lookup {
+    Hit(HttpResponse),
+    Lead(Leader),
+    Wait(Wait),
+}
+
+impl Inner {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Looks up a fresh response to `req`, or else registers the request as handling `key`,
+    /// unless another request already is.
+    fn lookup(self: &Arc<Self>, key: &Key, req: &HttpRequest) -> Lookup {
+        let mut state = self.state();
+
+        if let Some(res) = state.get(key, req) {
+            return Lookup::Hit(res);
+        }
+
+        if let Some(pending) = state.pending.get(key) {
+            return Lookup::Wait(Wait {
+                inner: Arc::clone(self),
+                key: key.clone(),
+                id: pending.id,
+            });
+        }
+
+        state.last_pending_id += 1;
+        let id = state.last_pending_id;
+        state.pending.insert(
+            key.clone(),
+            Pending {
+                id,
+                wakers: Vec::new(),
+            },
+        );
+
+        Lookup::Lead(Leader {
+            inner: Arc::clone(self),
+            key: key.clone(),
+        })
+    }
+
+    fn get(&self, key: &Key, req: &HttpRequest) -> Option<HttpResponse> {
+        self.state().get(key, req)
+    }
+
+    /// Stores the response to `req`, if cacheable, and returns it.
+    async fn store(
+        &self,
+        key: Key,
+        req: &HttpRequest,
+        res: HttpResponse,
+    ) -> Result<HttpResponse, Error> {
+        let (ttl, vary) = match storage_policy(req, &res) {
+            Some(policy) => policy,
+            None => return Ok(res),
+        };
+
+        match res.body().size() {
+            BodySize::None | BodySize::Empty => {}
+            BodySize::Sized(size) if size <= self.max_entry_size as u64 => {}
+            _ => return Ok(res),
+        }
+
+        let (res, body) = res.into_parts();
+        let body = to_bytes(body).await?;
+
+        let entry = Entry {
+            vary,
+            status: res.status(),
+            headers: res.headers().clone(),
+            body: body.clone(),
+            stored: Instant::now(),
+            ttl,
+            last_used: 0,
+        };
+
+        self.state().insert(self.capacity, key, entry);
+
+        Ok(res.set_body(Body::Bytes(body)))
+    }
+}
+
+impl State {
+    fn get(&mut self, key: &Key, req: &HttpRequest) -> Option<HttpResponse> {
+        self.clock += 1;
+        let clock = self.clock;
+
+        let entry = self
+            .entries
+            .get_mut(key)?
+            .iter_mut()
+            .find(|entry| entry.matches(req))?;
+
+        let age = entry.stored.elapsed();
+
+        if age >= entry.ttl {
+            return None;
+        }
+
+        entry.last_used = clock;
+
+        let mut res = HttpResponse::with_body(entry.status, Body::Bytes(entry.body.clone()));
+
+        for (name, value) in entry.headers.iter() {
+            res.headers_mut().append(name.clone(), value.clone());
+        }
+
+        res.headers_mut()
+            .insert(header::AGE, HeaderValue::from(age.as_secs()));
+
+        Some(res)
+    }
+
+    fn insert(&mut self, capacity: usize, key: Key, mut entry: Entry) {
+        self.clock += 1;
+        entry.last_used = self.clock;
+
+        let entries = self.entries.entry(key).or_insert_with(Vec::new);
+
+        match entries.iter().position(|other| other.vary == entry.vary) {
+            Some(idx) => entries[idx] = entry,
+            None => {
+                entries.push(entry);
+                self.len += 1;
+            }
+        }
+
+        while self.len > capacity {
+            self.evict();
+        }
+    }
+
+    /// Removes stale responses, or the least recently used response if none are stale.
+    fn evict(&mut self) {
+        let before = self.len;
+
+        for entries in self.entries.values_mut() {
+            entries.retain(|entry| entry.stored.elapsed() < entry.ttl);
+        }
+
+        self.entries.retain(|_, entries| !entries.is_empty());
+        self.len = self.entries.values().map(Vec::len).sum();
+
+        if self.len < before {
+            return;
+        }
+
+        let lru = self
+            .entries
+            .iter()
+            .flat_map(|(key, entries)| {
+                entries
+                    .iter()
+                    .enumerate()
+                    .map(move |(idx, entry)| (entry.last_used, key, idx))
+            })
+            .min_by_key(|(last_used, _, _)| *last_used)
+            .map(|(_, key, idx)| (key.clone(), idx));
+
+        if let Some((key, idx)) = lru {
+            let entries = self.entries.get_mut(&key).unwrap();
+            entries.remove(idx);
+
+            if entries.is_empty() {
+                self.entries.remove(&key);
+            }
+
+            self.len -= 1;
+        }
+    }
+}
+
+impl Entry {
+    /// Returns whether the request headers listed in the `Vary` header match those of `req`.
+    fn matches(&self, req: &HttpRequest) -> bool {
+        self.vary
+            .iter()
+            .all(|(name, value)| req.headers().get(name) == value.as_ref())
+    }
+}
+
+/// Marks a request as handling its key, waking the requests waiting for it when dropped.
+struct Leader {
+    inner: Arc<Inner>,
+    key: Key,
+}
+
+impl Drop for Leader {
+    fn drop(&mut self) {
+        let pending = self.inner.state().pending.remove(&self.key);
+
+        for waker in pending.into_iter().flat_map(|pending| pending.wakers) {
+            waker.wake();
+        }
+    }
+}
+
+/// Resolves when the request handling a key is done.
+///
+/// Only waits for the request that was handling the key when the `Wait` was created, and not for
+/// any request that takes over the key after it.
+struct Wait {
+    inner: Arc<Inner>,
+    key: Key,
+    id: u64,
+}
+
+impl Future for Wait {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.inner.state();
+
+        match state.pending.get_mut(&self.key) {
+            Some(pending) if pending.id == self.id => {
+                let wakers = &mut pending.wakers;
+
+                if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    wakers.push(cx.waker().clone());
+                }
+
+                Poll::Pending
+            }
+            _ => Poll::Ready(()),
+        }
+    }
+}
+
+/// Returns the directives of the `Cache-Control` headers in `headers`.
+fn cache_directives(headers: &HeaderMap) -> Vec<CacheDirective> {
+    headers
+        .get_all(header::CACHE_CONTROL)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .filter_map(|directive| CacheDirective::from_str(directive.trim()).ok())
+        .collect()
+}
+
+/// Returns whether the response to `req` may be looked up in the cache.
+fn is_cacheable_request(req: &ServiceRequest) -> bool {
+    if !matches!(*req.method(), Method::GET | Method::HEAD)
+        || req.headers().contains_key(header::AUTHORIZATION)
+    {
+        return false;
+    }
+
+    !cache_directives(req.headers())
+        .iter()
+        .any(|directive| matches!(directive, CacheDirective::NoStore))
+}
+
+/// Returns whether `req` asks for a response validated by the app.
+fn requires_validation(req: &ServiceRequest) -> bool {
+    cache_directives(req.headers()).iter().any(|directive| {
+        matches!(
+            directive,
+            CacheDirective::NoCache | CacheDirective::MaxAge(0)
+        )
+    })
+}
+
+/// Returns the freshness lifetime and the `Vary` request header values of `res`, if it can be
+/// stored.
+fn storage_policy(
+    req: &HttpRequest,
+    res: &HttpResponse,
+) -> Option<(Duration, Vec<(HeaderName, Option<HeaderValue>)>)> {
+    // status codes cacheable by default (RFC 9110 §15.1)
+    if !matches!(
+        res.status().as_u16(),
+        200 | 203 | 204 | 300 | 301 | 308 | 404 | 405 | 410 | 414 | 501
+    ) || res.headers().contains_key(header::SET_COOKIE)
+    {
+        return None;
+    }
+
+    let mut max_age = None;
+    let mut s_max_age = None;
+
+    for directive in cache_directives(res.headers()) {
+        match directive {
+            CacheDirective::NoStore | CacheDirective::NoCache | CacheDirective::Private => {
+                return None
+            }
+            CacheDirective::MaxAge(secs) => max_age = Some(secs),
+            CacheDirective::SMaxAge(secs) => s_max_age = Some(secs),
+            _ => {}
+        }
+    }
+
+    // the shared cache lifetime overrides max-age
+    let ttl = match s_max_age.or(max_age) {
+        Some(secs) if secs > 0 => Duration::from_secs(u64::from(secs)),
+        _ => return None,
+    };
+
+    let mut vary = Vec::new();
+
+    for val in res.headers().get_all(header::VARY) {
+        for name in val.to_str().ok()?.split(',') {
+            let name = name.trim();
+
+            if name == "*" {
+                return None;
+            }
+
+            let name = HeaderName::try_from(name).ok()?;
+            let value = req.headers().get(&name).cloned();
+            vary.push((name, value));
+        }
+    }
+
+    Some((ttl, vary))
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Cache
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Transform = CacheMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CacheMiddleware {
+            service: Rc::new(service),
+            inner: Arc::clone(&self.inner),
+        }))
+    }
+}
+
+pub struct CacheMiddleware<S> {
+    service: Rc<S>,
+    inner: Arc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for CacheMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let inner = Arc::clone(&self.inner);
+
+        Box::pin(async move {
+            if !is_cacheable_request(&req) {
+                let res = service.call(req).await?;
+                return Ok(res.map_body(|_, body| Body::from_message(body)));
+            }
+
+            let key = Key::new(&req);
+
+            let _leader = if requires_validation(&req) {
+                None
+            } else {
+                match inner.lookup(&key, &*req.parts_mut().0) {
+                    Lookup::Hit(res) => return Ok(req.into_response(res)),
+                    Lookup::Lead(leader) => Some(leader),
+                    Lookup::Wait(wait) => {
+                        // wait once; if nothing was stored, handle the request directly
+                        wait.await;
+
+                        if let Some(res) = inner.get(&key, &*req.parts_mut().0) {
+                            return Ok(req.into_response(res));
+                        }
+
+                        None
+                    }
+                }
+            };
+
+            let res = service.call(req).await?;
+
+            let req = res.request().clone();
+            let res = HttpResponse::from(res).map_body(|_, body| Body::from_message(body));
+            let res = inner.store(key, &req, res).await?;
+
+            Ok(ServiceResponse::new(req, res))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::{
+        http::header::CacheControl,
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    async fn init(
+        cache: Cache,
+        calls: Arc<AtomicUsize>,
+    ) -> impl Service<actix_http::Request, Response = ServiceResponse, Error = Error> {
+        let handler = move |directive: web::Path<String>| {
+            let calls = Arc::clone(&calls);
+
+            async move {
+                let count = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                actix_rt::time::sleep(Duration::from_millis(10)).await;
+
+                let directive = match directive.as_str() {
+                    "private" => CacheDirective::Private,
+                    "no-store" => CacheDirective::NoStore,
+                    _ => CacheDirective::MaxAge(60),
+                };
+
+                HttpResponse::Ok()
+                    .insert_header(CacheControl(vec![directive]))
+                    .insert_header((header::VARY, "accept-language"))
+                    .body(count.to_string())
+            }
+        };
+
+        test::init_service(
+            App::new()
+                .wrap(cache)
+                .route("/{directive}", web::get().to(handler)),
+        )
+        .await
+    }
+
+    #[actix_rt::test]
+    async fn serves_cached_responses() {
+        let cache = Cache::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let srv = init(cache.clone(), Arc::clone(&calls)).await;
+
+        let req = TestRequest::with_uri("/public").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert!(!res.headers().contains_key(header::AGE));
+        assert_eq!(test::read_body(res).await, "1");
+
+        let req = TestRequest::with_uri("/public").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.headers().get(header::AGE).unwrap(), "0");
+        assert_eq!(test::read_body(res).await, "1");
+
+        // variants are selected by the headers listed in Vary
+        let req = TestRequest::with_uri("/public")
+            .insert_header((header::ACCEPT_LANGUAGE, "fr"))
+            .to_request();
+        assert_eq!(
+            test::read_body(test::call_service(&srv, req).await).await,
+            "2"
+        );
+
+        let req = TestRequest::with_uri("/public")
+            .insert_header((header::CACHE_CONTROL, "no-cache"))
+            .to_request();
+        assert_eq!(
+            test::read_body(test::call_service(&srv, req).await).await,
+            "3"
+        );
+
+        // responses are not shared between hosts
+        let req = TestRequest::with_uri("/public")
+            .insert_header((header::HOST, "tenant.example.com"))
+            .to_request();
+        assert_eq!(
+            test::read_body(test::call_service(&srv, req).await).await,
+            "4"
+        );
+
+        let req = TestRequest::with_uri("/public")
+            .insert_header((header::HOST, "TENANT.example.com"))
+            .to_request();
+        assert_eq!(
+            test::read_body(test::call_service(&srv, req).await).await,
+            "4"
+        );
+
+        for uri in &["/private", "/no-store", "/private", "/no-store"] {
+            let req = TestRequest::with_uri(uri).to_request();
+            test::call_service(&srv, req).await;
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 8);
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[actix_rt::test]
+    async fn evicts_least_recently_used() {
+        let cache = Cache::new().capacity(2);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let srv = init(cache.clone(), Arc::clone(&calls)).await;
+
+        for uri in &["/a", "/b", "/a", "/c", "/a", "/b"] {
+            let req = TestRequest::with_uri(uri).to_request();
+            test::call_service(&srv, req).await;
+        }
+
+        // "/b" was evicted by "/c", and "/c" by "/b"
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[actix_rt::test]
+    async fn coalesces_concurrent_misses() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let srv = init(Cache::new(), Arc::clone(&calls)).await;
+
+        let (a, b) = futures_util::future::join(
+            test::call_service(&srv, TestRequest::with_uri("/public").to_request()),
+            test::call_service(&srv, TestRequest::with_uri("/public").to_request()),
+        )
+        .await;
+
+        assert_eq!(test::read_body(a).await, "1");
+        assert_eq!(test::read_body(b).await, "1");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let (a, b) = futures_util::future::join(
+            test::call_service(&srv, TestRequest::with_uri("/private").to_request()),
+            test::call_service(&srv, TestRequest::with_uri("/private").to_request()),
+        )
+        .await;
+
+        assert_eq!(test::read_body(a).await, "2");
+        assert_eq!(test::read_body(b).await, "3");
+    }
+
+    #[actix_rt::test]
+    async fn waits_for_one_leader_only() {
+        let cache = Cache::new();
+        let req = TestRequest::with_uri("/private").to_srv_request();
+        let key = Key::new(&req);
+        let (req, _) = req.into_parts();
+
+        let leader = match cache.inner.lookup(&key, &req) {
+            Lookup::Lead(leader) => leader,
+            _ => panic!("expected first request to lead"),
+        };
+        let mut wait = match cache.inner.lookup(&key, &req) {
+            Lookup::Wait(wait) => wait,
+            _ => panic!("expected second request to wait"),
+        };
+
+        let mut cx = Context::from_waker(futures_util::task::noop_waker_ref());
+        assert!(Pin::new(&mut wait).poll(&mut cx).is_pending());
+
+        // nothing was stored, and another request takes over the key before the waiter is polled
+        drop(leader);
+        let _next = match cache.inner.lookup(&key, &req) {
+            Lookup::Lead(leader) => leader,
+            _ => panic!("expected third request to lead"),
+        };
+
+        assert!(Pin::new(&mut wait).poll(&mut cx).is_ready());
+    }
+}
@@ -0,0 +1,334 @@
+//! For middleware documentation, see [`ConditionalGet`].
+
+use std::{
+    error::Error as StdError,
+    fmt::Write as _,
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use actix_http::body::{to_bytes, Body, BodySize, MessageBody};
+use actix_utils::future::{ready, Ready};
+use futures_core::future::LocalBoxFuture;
+use sha1::{Digest as _, Sha1};
+
+use crate::{
+    dev::{Service, Transform},
+    http::{
+        header::{
+            self, EntityTag, HeaderMap, HttpDate, IfModifiedSince, IfNoneMatch,
+            IntoHeaderValue as _,
+        },
+        Method, StatusCode,
+    },
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpMessage as _, HttpRequest, HttpResponse,
+};
+
+/// Default limit of the size of bodies buffered to compute ETags.
+const DEFAULT_LIMIT: usize = 262_144; // 256 KiB
+
+/// Middleware for answering conditional `GET` and `HEAD` requests with *304 Not Modified*.
+///
+/// Successful responses without an `ETag` header are given a strong ETag computed from a hash of
+/// their body. Only bodies of known size up to the [limit](Self::limit) are buffered and hashed;
+/// streaming and larger bodies are passed through unchanged. ETags set by handlers are kept.
+///
+/// When the request's `If-None-Match` header matches the ETag, or, in the absence of
+/// `If-None-Match`, when the response's `Last-Modified` time is not after the request's
+/// `If-Modified-Since` time, the response is replaced with a *304 Not Modified* response. It has no
+/// body and no content headers, but keeps the validators and caching headers of the original.
+///
+/// # Examples
+/// ```
+/// use actix_web::{middleware, web, App, HttpResponse};
+///
+/// let app = App::new()
+///     .wrap(middleware::ConditionalGet::new().limit(65_536))
+///     .route(
+///         "/",
+///         web::get().to(|| HttpResponse::Ok().json(vec!["a", "b", "c"])),
+///     );
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConditionalGet {
+    limit: usize,
+}
+
+impl Default for ConditionalGet {
+    fn default() -> Self {
+        ConditionalGet {
+            limit: DEFAULT_LIMIT,
+        }
+    }
+}
+
+impl ConditionalGet {
+    /// Constructs a `ConditionalGet` middleware with the default limit.
+    pub fn new() -> ConditionalGet {
+        ConditionalGet::default()
+    }
+
+    /// Sets the maximum size of bodies that are buffered to compute ETags.
+    ///
+    /// Default is 256 KiB.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+/// Returns whether the conditional headers of `req` are satisfied by a representation with the
+/// given validators, meaning it has not been modified.
+fn is_not_modified(
+    req: &HttpRequest,
+    etag: Option<&EntityTag>,
+    last_modified: Option<HttpDate>,
+) -> bool {
+    // If-Modified-Since is ignored when If-None-Match is present (RFC 9110 §13.1.3)
+    match req.get_header::<IfNoneMatch>() {
+        Some(IfNoneMatch::Any) => true,
+
+        Some(IfNoneMatch::Items(ref items)) => match etag {
+            Some(etag) => items.iter().any(|item| item.weak_eq(etag)),
+            None => false,
+        },
+
+        None => match (last_modified, req.get_header::<IfModifiedSince>()) {
+            (Some(modified), Some(IfModifiedSince(since))) => {
+                match (unix_secs(modified), unix_secs(since)) {
+                    (Some(modified), Some(since)) => modified <= since,
+                    _ => false,
+                }
+            }
+            _ => false,
+        },
+    }
+}
+
+/// HTTP dates have a resolution of one second, so times are compared in whole seconds.
+pub(crate) fn unix_secs(date: HttpDate) -> Option<u64> {
+    SystemTime::from(date)
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|dur| dur.as_secs())
+}
+
+fn parse_header<T: std::str::FromStr>(
+    headers: &HeaderMap,
+    name: header::HeaderName,
+) -> Option<T> {
+    headers
+        .get(name)
+        .and_then(|val| val.to_str().ok())
+        .and_then(|val| val.parse().ok())
+}
+
+/// Computes a strong ETag from the SHA-1 hash of `body`.
+fn hash_etag(body: &[u8]) -> EntityTag {
+    let mut tag = String::with_capacity(40);
+
+    for byte in Sha1::digest(body).iter() {
+        write!(tag, "{:02x}", byte).unwrap();
+    }
+
+    EntityTag::strong(tag)
+}
+
+/// Turns `res` into a *304 Not Modified* response, keeping its validators and caching headers.
+fn not_modified(res: HttpResponse) -> HttpResponse {
+    let mut res = res.set_body(Body::None);
+    *res.status_mut() = StatusCode::NOT_MODIFIED;
+
+    let headers = res.headers_mut();
+    headers.remove(header::CONTENT_TYPE);
+    headers.remove(header::CONTENT_LENGTH);
+    headers.remove(header::CONTENT_ENCODING);
+    headers.remove(header::CONTENT_LANGUAGE);
+    headers.remove(header::CONTENT_RANGE);
+    headers.remove(header::TRANSFER_ENCODING);
+
+    res
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ConditionalGet
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Transform = ConditionalGetMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ConditionalGetMiddleware {
+            service: Rc::new(service),
+            limit: self.limit,
+        }))
+    }
+}
+
+pub struct ConditionalGetMiddleware<S> {
+    service: Rc<S>,
+    limit: usize,
+}
+
+impl<S, B> Service<ServiceRequest> for ConditionalGetMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let limit = self.limit;
+
+        Box::pin(async move {
+            let is_candidate = matches!(*req.method(), Method::GET | Method::HEAD);
+            let res = service.call(req).await?;
+
+            if !is_candidate || res.status() != StatusCode::OK {
+                return Ok(res.map_body(|_, body| Body::from_message(body)));
+            }
+
+            let req = res.request().clone();
+            let res = HttpResponse::from(res).map_body(|_, body| Body::from_message(body));
+
+            let mut etag = parse_header::<EntityTag>(res.headers(), header::ETAG);
+
+            let mut res = match res.body().size() {
+                BodySize::Sized(size) if etag.is_none() && size <= limit as u64 => {
+                    let (res, body) = res.into_parts();
+                    let body = to_bytes(body).await?;
+
+                    let tag = hash_etag(&body);
+                    let mut res = res.set_body(Body::Bytes(body));
+                    res.headers_mut()
+                        .insert(header::ETAG, header::ETag(tag.clone()).try_into_value()?);
+
+                    etag = Some(tag);
+                    res
+                }
+                _ => res,
+            };
+
+            let last_modified = parse_header::<HttpDate>(res.headers(), header::LAST_MODIFIED);
+
+            if is_not_modified(&req, etag.as_ref(), last_modified) {
+                res = not_modified(res);
+            }
+
+            Ok(ServiceResponse::new(req, res))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        http::header::LastModified,
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    async fn init(
+    ) -> impl Service<actix_http::Request, Response = ServiceResponse, Error = Error> {
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+
+        test::init_service(
+            App::new()
+                .wrap(ConditionalGet::new().limit(16))
+                .route(
+                    "/",
+                    web::get().to(|| {
+                        HttpResponse::Ok()
+                            .content_type("application/json")
+                            .body(r#"{"a":1}"#)
+                    }),
+                )
+                .route(
+                    "/large",
+                    web::get().to(|| HttpResponse::Ok().body("a".repeat(17))),
+                )
+                .route(
+                    "/modified",
+                    web::get().to(move || {
+                        HttpResponse::Ok()
+                            .insert_header(LastModified(modified.into()))
+                            .body("a".repeat(17))
+                    }),
+                ),
+        )
+        .await
+    }
+
+    #[actix_rt::test]
+    async fn answers_matching_etags() {
+        let srv = init().await;
+
+        let req = TestRequest::default().to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let etag = res.headers().get(header::ETAG).unwrap().clone();
+        assert_eq!(etag, "\"9f89c740ceb46d7418c924a78ac57941d5e96520\"");
+        assert_eq!(test::read_body(res).await, r#"{"a":1}"#);
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, etag.clone()))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers().get(header::ETAG).unwrap(), &etag);
+        assert!(!res.headers().contains_key(header::CONTENT_TYPE));
+        assert!(test::read_body(res).await.is_empty());
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, "\"other\""))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::default()
+            .uri("/large")
+            .insert_header((header::IF_NONE_MATCH, "*"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert!(!res.headers().contains_key(header::ETAG));
+    }
+
+    #[actix_rt::test]
+    async fn answers_unmodified_responses() {
+        let srv = init().await;
+
+        let req = TestRequest::default()
+            .uri("/modified")
+            .insert_header((header::IF_MODIFIED_SINCE, "Mon, 12 Jan 1970 13:46:40 GMT"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert!(res.headers().contains_key(header::LAST_MODIFIED));
+
+        let req = TestRequest::default()
+            .uri("/modified")
+            .insert_header((header::IF_MODIFIED_SINCE, "Mon, 12 Jan 1970 13:46:39 GMT"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(test::read_body(res).await, "a".repeat(17));
+    }
+}
@@ -0,0 +1,771 @@
+//! For middleware documentation, see [`Cors`].
+
+use std::{convert::TryFrom, error::Error as StdError, fmt, rc::Rc};
+
+use actix_http::RequestHead;
+use actix_utils::future::{ready, Ready};
+use derive_more::{Display, Error};
+use futures_core::future::LocalBoxFuture;
+
+use crate::{
+    body::{Body, MessageBody},
+    dev::{Service, Transform},
+    http::{
+        header::{self, HeaderMap, HeaderName, HeaderValue},
+        Error as HttpError, Method, StatusCode,
+    },
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpResponse, ResponseError,
+};
+
+type OriginFn = dyn Fn(&HeaderValue, &RequestHead) -> bool;
+
+/// Middleware for Cross-Origin Resource Sharing (CORS).
+///
+/// Requests with an `Origin` header from an allowed origin are answered with the
+/// `Access-Control-Allow-*` headers that let browsers expose the response to the calling page.
+/// Origins can be allowed exactly, with a wildcard for subdomains, such as
+/// `https://*.example.com`, or with a predicate. Requests from other origins are rejected with a
+/// *400 Bad Request* response explaining the reason.
+///
+/// Preflight requests, `OPTIONS` requests with an `Access-Control-Request-Method` header, are
+/// answered by the middleware itself and never reach the wrapped service. The requested method
+/// and headers must be allowed.
+///
+/// Responses that depend on the request origin include a `Vary: Origin` header, so caches keep
+/// separate copies for each origin.
+///
+/// By default, no origins are allowed; the methods `GET`, `HEAD`, `POST`, `PUT`, `PATCH`,
+/// `DELETE` and `OPTIONS` are allowed; no request headers beyond the CORS-safelisted ones are
+/// allowed; credentials are not supported and no response headers are exposed.
+///
+/// # Examples
+/// ```
+/// use actix_web::{http::header, middleware::Cors, web, App, HttpResponse};
+///
+/// let cors = Cors::new()
+///     .allowed_origin("https://www.rust-lang.org")
+///     .allowed_origin("https://*.rust-lang.org")
+///     .allowed_origin_fn(|origin, _req_head| origin.as_bytes().ends_with(b".localhost"))
+///     .allowed_header(header::CONTENT_TYPE)
+///     .expose_headers(vec!["x-request-id"])
+///     .supports_credentials()
+///     .max_age(3600);
+///
+/// let app = App::new()
+///     .wrap(cors)
+///     .route("/index.html", web::get().to(HttpResponse::Ok));
+/// ```
+#[derive(Clone)]
+pub struct Cors {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    any_origin: bool,
+    origins: Vec<HeaderValue>,
+    origin_patterns: Vec<(String, String)>,
+    origin_fns: Vec<Box<OriginFn>>,
+    methods: Vec<Method>,
+    any_header: bool,
+    headers: Vec<HeaderName>,
+    expose_headers: Vec<HeaderName>,
+    max_age: Option<u32>,
+    supports_credentials: bool,
+}
+
+impl Default for Cors {
+    fn default() -> Self {
+        Cors {
+            inner: Rc::new(Inner {
+                any_origin: false,
+                origins: Vec::new(),
+                origin_patterns: Vec::new(),
+                origin_fns: Vec::new(),
+                methods: vec![
+                    Method::GET,
+                    Method::HEAD,
+                    Method::POST,
+                    Method::PUT,
+                    Method::PATCH,
+                    Method::DELETE,
+                    Method::OPTIONS,
+                ],
+                any_header: false,
+                headers: Vec::new(),
+                expose_headers: Vec::new(),
+                max_age: None,
+                supports_credentials: false,
+            }),
+        }
+    }
+}
+
+impl Cors {
+    /// Constructs a `Cors` middleware that allows no origins.
+    pub fn new() -> Cors {
+        Cors::default()
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("Multiple `Inner` copies exist.")
+    }
+
+    /// Adds an origin that is allowed to make requests.
+    ///
+    /// Origins are compared case-insensitively and must include the scheme, such as
+    /// `https://www.rust-lang.org`. A single `*` in place of the leading host labels allows any
+    /// subdomain, such as `https://*.rust-lang.org`. The origin `*` allows any origin.
+    ///
+    /// # Panics
+    /// Panics if the origin is not a valid header value or contains more than one `*`.
+    pub fn allowed_origin(mut self, origin: &str) -> Self {
+        let inner = self.inner_mut();
+
+        if origin == "*" {
+            inner.any_origin = true;
+        } else if let Some(idx) = origin.find('*') {
+            assert!(
+                !origin[idx + 1..].contains('*'),
+                "origin pattern `{}` contains more than one wildcard",
+                origin
+            );
+
+            let prefix = origin[..idx].to_ascii_lowercase();
+            let suffix = origin[idx + 1..].to_ascii_lowercase();
+            inner.origin_patterns.push((prefix, suffix));
+        } else {
+            let origin = HeaderValue::from_str(origin).expect("Can not create origin value");
+            inner.origins.push(origin);
+        }
+
+        self
+    }
+
+    /// Allows any origin to make requests.
+    ///
+    /// Responses allow any origin with `Access-Control-Allow-Origin: *`, unless credentials are
+    /// [supported](Self::supports_credentials), in which case the request origin is echoed.
+    pub fn allow_any_origin(self) -> Self {
+        self.allowed_origin("*")
+    }
+
+    /// Adds a predicate for origins that are allowed to make requests.
+    ///
+    /// Origins that are not allowed by [`allowed_origin`](Self::allowed_origin) are allowed if any
+    /// predicate returns `true`.
+    pub fn allowed_origin_fn<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&HeaderValue, &RequestHead) -> bool + 'static,
+    {
+        self.inner_mut().origin_fns.push(Box::new(f));
+        self
+    }
+
+    /// Sets the methods that preflighted requests are allowed to use, replacing the defaults.
+    pub fn allowed_methods<U>(mut self, methods: U) -> Self
+    where
+        U: IntoIterator<Item = Method>,
+    {
+        self.inner_mut().methods = methods.into_iter().collect();
+        self
+    }
+
+    /// Adds a request header that preflighted requests are allowed to send.
+    ///
+    /// # Panics
+    /// Panics if the header name is invalid.
+    pub fn allowed_header<H>(mut self, header: H) -> Self
+    where
+        HeaderName: TryFrom<H>,
+        <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+    {
+        let header = header_name(header);
+        self.inner_mut().headers.push(header);
+        self
+    }
+
+    /// Adds request headers that preflighted requests are allowed to send.
+    ///
+    /// # Panics
+    /// Panics if any header name is invalid.
+    pub fn allowed_headers<U, H>(self, headers: U) -> Self
+    where
+        U: IntoIterator<Item = H>,
+        HeaderName: TryFrom<H>,
+        <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+    {
+        headers
+            .into_iter()
+            .fold(self, |cors, header| cors.allowed_header(header))
+    }
+
+    /// Allows preflighted requests to send any request header.
+    pub fn allow_any_header(mut self) -> Self {
+        self.inner_mut().any_header = true;
+        self
+    }
+
+    /// Adds response headers that browsers are allowed to expose to the calling page.
+    ///
+    /// # Panics
+    /// Panics if any header name is invalid.
+    pub fn expose_headers<U, H>(mut self, headers: U) -> Self
+    where
+        U: IntoIterator<Item = H>,
+        HeaderName: TryFrom<H>,
+        <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+    {
+        let headers = headers.into_iter().map(header_name);
+        self.inner_mut().expose_headers.extend(headers);
+        self
+    }
+
+    /// Sets the number of seconds browsers may cache the results of preflight requests.
+    pub fn max_age(mut self, seconds: u32) -> Self {
+        self.inner_mut().max_age = Some(seconds);
+        self
+    }
+
+    /// Allows requests to include credentials, such as cookies and authorization headers.
+    pub fn supports_credentials(mut self) -> Self {
+        self.inner_mut().supports_credentials = true;
+        self
+    }
+}
+
+fn header_name<H>(header: H) -> HeaderName
+where
+    HeaderName: TryFrom<H>,
+    <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+{
+    match HeaderName::try_from(header) {
+        Ok(header) => header,
+        Err(_) => panic!("Can not create header name"),
+    }
+}
+
+impl Inner {
+    /// Returns `true` if `Access-Control-Allow-Origin` is always `*`, so responses do not vary
+    /// by origin.
+    fn is_origin_wildcard(&self) -> bool {
+        self.any_origin && !self.supports_credentials
+    }
+
+    fn is_origin_allowed(&self, origin: &HeaderValue, req: &RequestHead) -> bool {
+        if self.any_origin {
+            return true;
+        }
+
+        let value = origin.as_bytes();
+
+        self.origins
+            .iter()
+            .any(|allowed| allowed.as_bytes().eq_ignore_ascii_case(value))
+            || self.origin_patterns.iter().any(|(prefix, suffix)| {
+                origin_matches_pattern(&value.to_ascii_lowercase(), prefix, suffix)
+            })
+            || self.origin_fns.iter().any(|f| f(origin, req))
+    }
+
+    /// Checks a preflight request and returns the response to it.
+    fn preflight(
+        &self,
+        req: &RequestHead,
+        origin: &HeaderValue,
+    ) -> Result<HttpResponse, CorsError> {
+        let method = req
+            .headers
+            .get(header::ACCESS_CONTROL_REQUEST_METHOD)
+            .and_then(|val| Method::from_bytes(val.as_bytes()).ok())
+            .ok_or(CorsError::BadRequestMethod)?;
+
+        if !self.methods.contains(&method) {
+            return Err(CorsError::MethodNotAllowed);
+        }
+
+        let requested_headers = req.headers.get(header::ACCESS_CONTROL_REQUEST_HEADERS);
+
+        if let Some(requested) = requested_headers {
+            let requested = requested
+                .to_str()
+                .map_err(|_| CorsError::BadRequestHeaders)?;
+
+            for name in requested
+                .split(',')
+                .map(str::trim)
+                .filter(|h| !h.is_empty())
+            {
+                let name =
+                    HeaderName::try_from(name).map_err(|_| CorsError::BadRequestHeaders)?;
+
+                if !self.any_header && !self.headers.contains(&name) {
+                    return Err(CorsError::HeadersNotAllowed);
+                }
+            }
+        }
+
+        let mut res = HttpResponse::Ok();
+        res.insert_header((header::ACCESS_CONTROL_ALLOW_METHODS, join(&self.methods)));
+
+        if self.any_header {
+            if let Some(requested) = requested_headers {
+                res.insert_header((header::ACCESS_CONTROL_ALLOW_HEADERS, requested.clone()));
+            }
+        } else if !self.headers.is_empty() {
+            res.insert_header((header::ACCESS_CONTROL_ALLOW_HEADERS, join(&self.headers)));
+        }
+
+        if let Some(max_age) = self.max_age {
+            res.insert_header((header::ACCESS_CONTROL_MAX_AGE, max_age));
+        }
+
+        let mut res = res.finish();
+        self.set_origin_headers(res.headers_mut(), origin);
+
+        // the allowed methods and headers are the same for all origins
+        append_vary(res.headers_mut(), "Access-Control-Request-Method");
+        append_vary(res.headers_mut(), "Access-Control-Request-Headers");
+
+        Ok(res)
+    }
+
+    /// Sets the headers allowing `origin` to read a response.
+    fn set_origin_headers(&self, headers: &mut HeaderMap, origin: &HeaderValue) {
+        if self.is_origin_wildcard() {
+            headers.insert(
+                header::ACCESS_CONTROL_ALLOW_ORIGIN,
+                HeaderValue::from_static("*"),
+            );
+        } else {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+            append_vary(headers, "Origin");
+        }
+
+        if self.supports_credentials {
+            headers.insert(
+                header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                HeaderValue::from_static("true"),
+            );
+        }
+    }
+}
+
+/// Returns `true` if the lowercase `origin` starts with `prefix` and ends with `suffix`, with at
+/// least one host label in between.
+fn origin_matches_pattern(origin: &[u8], prefix: &str, suffix: &str) -> bool {
+    origin.len() > prefix.len() + suffix.len()
+        && origin.starts_with(prefix.as_bytes())
+        && origin.ends_with(suffix.as_bytes())
+        && origin[prefix.len()..origin.len() - suffix.len()]
+            .iter()
+            .all(|&b| b.is_ascii_alphanumeric() || b == b'-' || b == b'.')
+}
+
+/// Joins items into a comma-separated header value.
+fn join<T: AsRef<str>>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(AsRef::as_ref)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Adds `name` to the `Vary` header unless it, or `*`, is already listed.
+fn append_vary(headers: &mut HeaderMap, name: &'static str) {
+    let listed = headers
+        .get_all(header::VARY)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .map(str::trim)
+        .any(|val| val == "*" || val.eq_ignore_ascii_case(name));
+
+    if !listed {
+        headers.append(header::VARY, HeaderValue::from_static(name));
+    }
+}
+
+/// Errors for requests rejected by the [`Cors`] middleware.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum CorsError {
+    /// The request origin is not allowed.
+    #[display(fmt = "Origin is not allowed to make this request.")]
+    OriginNotAllowed,
+
+    /// The preflight request's `Access-Control-Request-Method` header is missing or invalid.
+    #[display(
+        fmt = "Preflight request has a missing or invalid Access-Control-Request-Method header."
+    )]
+    BadRequestMethod,
+
+    /// The preflight request's `Access-Control-Request-Headers` header is invalid.
+    #[display(fmt = "Preflight request has an invalid Access-Control-Request-Headers header.")]
+    BadRequestHeaders,
+
+    /// The method requested by the preflight request is not allowed.
+    #[display(fmt = "Requested method is not allowed.")]
+    MethodNotAllowed,
+
+    /// Some headers requested by the preflight request are not allowed.
+    #[display(fmt = "Requested headers are not allowed.")]
+    HeadersNotAllowed,
+}
+
+impl ResponseError for CorsError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+impl fmt::Debug for Cors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cors")
+            .field("any_origin", &self.inner.any_origin)
+            .field("origins", &self.inner.origins)
+            .field("origin_fns", &self.inner.origin_fns.len())
+            .field("methods", &self.inner.methods)
+            .field("any_header", &self.inner.any_header)
+            .field("headers", &self.inner.headers)
+            .field("expose_headers", &self.inner.expose_headers)
+            .field("max_age", &self.inner.max_age)
+            .field("supports_credentials", &self.inner.supports_credentials)
+            .finish()
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Cors
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Transform = CorsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CorsMiddleware {
+            service,
+            inner: Rc::clone(&self.inner),
+        }))
+    }
+}
+
+pub struct CorsMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for CorsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let inner = Rc::clone(&self.inner);
+        let origin = req.headers().get(header::ORIGIN).cloned();
+
+        if let Some(ref origin) = origin {
+            if !inner.is_origin_allowed(origin, req.head()) {
+                let res = req.error_response(CorsError::OriginNotAllowed);
+                return Box::pin(ready(Ok(res)));
+            }
+
+            let is_preflight = req.method() == Method::OPTIONS
+                && req
+                    .headers()
+                    .contains_key(header::ACCESS_CONTROL_REQUEST_METHOD);
+
+            if is_preflight {
+                let res = match inner.preflight(req.head(), origin) {
+                    Ok(res) => req.into_response(res),
+                    Err(err) => req.error_response(err),
+                };
+
+                return Box::pin(ready(Ok(res)));
+            }
+        }
+
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let mut res = fut.await?;
+
+            match origin {
+                Some(ref origin) => {
+                    let headers = res.headers_mut();
+                    inner.set_origin_headers(headers, origin);
+
+                    if !inner.expose_headers.is_empty() {
+                        let exposed = join(&inner.expose_headers);
+                        headers.insert(
+                            header::ACCESS_CONTROL_EXPOSE_HEADERS,
+                            HeaderValue::try_from(exposed).unwrap(),
+                        );
+                    }
+                }
+
+                // response would differ for cross-origin requests
+                None if !inner.is_origin_wildcard() => {
+                    append_vary(res.headers_mut(), "Origin");
+                }
+
+                None => {}
+            }
+
+            Ok(res.map_body(|_, body| Body::from_message(body)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::header::{self, HeaderValue},
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    async fn init(
+        cors: Cors,
+    ) -> impl Service<actix_http::Request, Response = ServiceResponse, Error = Error> {
+        test::init_service(
+            App::new()
+                .wrap(cors)
+                .route(
+                    "/",
+                    web::get().to(|| {
+                        HttpResponse::Ok()
+                            .insert_header(("vary", "accept"))
+                            .finish()
+                    }),
+                )
+                .route("/", web::put().to(HttpResponse::Created)),
+        )
+        .await
+    }
+
+    fn cors() -> Cors {
+        Cors::new()
+            .allowed_origin("https://www.rust-lang.org")
+            .allowed_origin("https://*.crates.io")
+            .allowed_origin_fn(|origin, _| origin == "http://localhost:8080")
+            .allowed_methods(vec![Method::GET, Method::PUT])
+            .allowed_header(header::CONTENT_TYPE)
+            .expose_headers(vec!["x-request-id"])
+            .max_age(600)
+    }
+
+    #[actix_rt::test]
+    async fn actual_request() {
+        let srv = init(cors()).await;
+
+        for origin in &[
+            "https://www.rust-lang.org",
+            "https://docs.crates.io",
+            "http://localhost:8080",
+        ] {
+            let req = TestRequest::default()
+                .insert_header((header::ORIGIN, *origin))
+                .to_request();
+            let res = test::call_service(&srv, req).await;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(
+                res.headers()
+                    .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                    .unwrap(),
+                *origin
+            );
+            assert_eq!(
+                res.headers()
+                    .get(header::ACCESS_CONTROL_EXPOSE_HEADERS)
+                    .unwrap(),
+                "x-request-id"
+            );
+            assert!(!res
+                .headers()
+                .contains_key(header::ACCESS_CONTROL_ALLOW_CREDENTIALS));
+
+            let vary = res.headers().get_all(header::VARY).collect::<Vec<_>>();
+            assert_eq!(
+                vary,
+                vec![
+                    HeaderValue::from_static("accept"),
+                    HeaderValue::from_static("Origin")
+                ]
+            );
+        }
+
+        // not a cross-origin request
+        let req = TestRequest::default().to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+        assert_eq!(res.headers().get_all(header::VARY).count(), 2);
+    }
+
+    #[actix_rt::test]
+    async fn disallowed_origin() {
+        let srv = init(cors()).await;
+
+        for origin in &[
+            "https://evil.org",
+            "https://crates.io",
+            "https://a.crates.io.evil.org",
+            "https://a/b.crates.io",
+        ] {
+            let req = TestRequest::default()
+                .insert_header((header::ORIGIN, *origin))
+                .to_request();
+            let res = test::call_service(&srv, req).await;
+            assert_eq!(res.status(), StatusCode::BAD_REQUEST, "{}", origin);
+            assert!(!res
+                .headers()
+                .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+            assert_eq!(
+                test::read_body(res).await,
+                "Origin is not allowed to make this request."
+            );
+        }
+    }
+
+    #[actix_rt::test]
+    async fn preflight() {
+        let srv = init(cors()).await;
+
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .insert_header((header::ORIGIN, "https://www.rust-lang.org"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "PUT"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_HEADERS, "Content-Type"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let headers = res.headers();
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "https://www.rust-lang.org"
+        );
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_METHODS).unwrap(),
+            "GET, PUT"
+        );
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_HEADERS).unwrap(),
+            "content-type"
+        );
+        assert_eq!(headers.get(header::ACCESS_CONTROL_MAX_AGE).unwrap(), "600");
+        assert_eq!(headers.get_all(header::VARY).count(), 3);
+
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .insert_header((header::ORIGIN, "https://www.rust-lang.org"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "DELETE"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            test::read_body(res).await,
+            "Requested method is not allowed."
+        );
+
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .insert_header((header::ORIGIN, "https://www.rust-lang.org"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "GET"))
+            .insert_header((
+                header::ACCESS_CONTROL_REQUEST_HEADERS,
+                "content-type, x-custom",
+            ))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            test::read_body(res).await,
+            "Requested headers are not allowed."
+        );
+
+        // plain OPTIONS requests are passed through to the app, which has no OPTIONS route
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .insert_header((header::ORIGIN, "https://www.rust-lang.org"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "https://www.rust-lang.org"
+        );
+        assert!(!res
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_METHODS));
+    }
+
+    #[actix_rt::test]
+    async fn any_origin() {
+        let srv = init(Cors::new().allow_any_origin().allow_any_header()).await;
+
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .insert_header((header::ORIGIN, "https://evil.org"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "GET"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_HEADERS, "x-custom"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "*"
+        );
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_HEADERS)
+                .unwrap(),
+            "x-custom"
+        );
+
+        let req = TestRequest::default().to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.headers().get_all(header::VARY).count(), 1);
+
+        // credentials require the origin to be echoed
+        let srv = init(Cors::new().allow_any_origin().supports_credentials()).await;
+
+        let req = TestRequest::default()
+            .insert_header((header::ORIGIN, "https://evil.org"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "https://evil.org"
+        );
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS)
+                .unwrap(),
+            "true"
+        );
+    }
+}
@@ -0,0 +1,562 @@
+//! For middleware documentation, see [`Csrf`].
+
+use std::{convert::TryFrom, error::Error as StdError, fmt, rc::Rc};
+
+use actix_router::ResourceDef;
+use actix_utils::future::{ready, Ready};
+use bytes::BytesMut;
+use derive_more::{Display, Error};
+use futures_core::future::LocalBoxFuture;
+use futures_util::StreamExt as _;
+use url::{form_urlencoded, Url};
+
+use crate::{
+    body::{Body, MessageBody},
+    cookie::{Cookie, SameSite},
+    dev::{Payload, Service, Transform},
+    error::PayloadError,
+    http::{
+        header::{self, HeaderName, HeaderValue},
+        Error as HttpError, Method, StatusCode,
+    },
+    service::{ServiceRequest, ServiceResponse},
+    web, Error, HttpMessage as _, ResponseError,
+};
+
+/// Length of generated tokens, in hex digits.
+const TOKEN_LEN: usize = 64;
+
+/// Middleware for protecting against cross-site request forgery (CSRF).
+///
+/// Each client is given a random token in a cookie, by default named `csrf-token`. Requests with
+/// unsafe methods, anything but `GET`, `HEAD`, `OPTIONS` and `TRACE`, must submit the same token
+/// in the `X-CSRF-Token` header or, for URL-encoded forms, in the `csrf_token` field. Handlers get
+/// the token to render into pages with the [`web::CsrfToken`] extractor.
+///
+/// Unsafe requests must also come from the same origin: if present, the `Origin` header, or
+/// failing that the `Referer` header, must match the host of the request as given by
+/// [`ConnectionInfo::host`](crate::dev::ConnectionInfo::host), or an origin trusted with
+/// [`trusted_origin`](Self::trusted_origin).
+///
+/// Requests failing these checks are rejected with a *403 Forbidden* response explaining the
+/// reason, and never reach the wrapped service.
+///
+/// Form fields are read by buffering the request body, up to 64 KiB by default, which is then
+/// given back to the request so that extractors such as [`web::Form`] work as usual. Tokens in
+/// multipart forms are not read; such forms must submit the token in the header.
+///
+/// Scopes and resources that must accept cross-site requests, such as webhooks, can be exempted
+/// with [`exempt`](Self::exempt).
+///
+/// # Examples
+/// ```
+/// use actix_web::{middleware::Csrf, web, App, HttpResponse};
+///
+/// let app = App::new()
+///     .wrap(
+///         Csrf::new()
+///             .trusted_origin("https://admin.example.com")
+///             .exempt("/webhooks"),
+///     )
+///     .route("/form", web::post().to(HttpResponse::Ok))
+///     .route("/webhooks/{provider}", web::post().to(HttpResponse::Ok));
+/// ```
+#[derive(Clone)]
+pub struct Csrf {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    cookie_name: String,
+    cookie_secure: bool,
+    header: HeaderName,
+    field: String,
+    form_limit: usize,
+    trusted_origins: Vec<String>,
+    exempt: Vec<ResourceDef>,
+}
+
+impl Default for Csrf {
+    fn default() -> Self {
+        Csrf {
+            inner: Rc::new(Inner {
+                cookie_name: "csrf-token".to_owned(),
+                cookie_secure: true,
+                header: HeaderName::from_static("x-csrf-token"),
+                field: "csrf_token".to_owned(),
+                form_limit: 65_536,
+                trusted_origins: Vec::new(),
+                exempt: Vec::new(),
+            }),
+        }
+    }
+}
+
+impl Csrf {
+    /// Constructs a `Csrf` middleware with the default cookie, header and field names.
+    pub fn new() -> Csrf {
+        Csrf::default()
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("Multiple `Inner` copies exist.")
+    }
+
+    /// Sets the name of the token cookie. Default is `csrf-token`.
+    pub fn cookie_name(mut self, name: impl Into<String>) -> Self {
+        self.inner_mut().cookie_name = name.into();
+        self
+    }
+
+    /// Sets whether the token cookie is only sent over HTTPS. Default is `true`.
+    pub fn cookie_secure(mut self, secure: bool) -> Self {
+        self.inner_mut().cookie_secure = secure;
+        self
+    }
+
+    /// Sets the name of the header carrying submitted tokens. Default is `X-CSRF-Token`.
+    ///
+    /// # Panics
+    /// Panics if the header name is invalid.
+    pub fn header<H>(mut self, header: H) -> Self
+    where
+        HeaderName: TryFrom<H>,
+        <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+    {
+        self.inner_mut().header = match HeaderName::try_from(header) {
+            Ok(header) => header,
+            Err(_) => panic!("Can not create header name"),
+        };
+        self
+    }
+
+    /// Sets the name of the form field carrying submitted tokens. Default is `csrf_token`.
+    pub fn field(mut self, field: impl Into<String>) -> Self {
+        self.inner_mut().field = field.into();
+        self
+    }
+
+    /// Sets the maximum size of URL-encoded form bodies searched for a token. Default is 64 KiB.
+    ///
+    /// Larger forms must submit the token in the header.
+    pub fn form_limit(mut self, limit: usize) -> Self {
+        self.inner_mut().form_limit = limit;
+        self
+    }
+
+    /// Trusts requests from `origin`, such as `https://admin.example.com`, in addition to
+    /// requests from the host of the request.
+    ///
+    /// # Panics
+    /// Panics if the origin is not a valid URL.
+    pub fn trusted_origin(mut self, origin: &str) -> Self {
+        let origin = match Url::parse(origin) {
+            Ok(url) => url.origin().ascii_serialization(),
+            Err(_) => panic!("Invalid origin: {}", origin),
+        };
+
+        self.inner_mut().trusted_origins.push(origin);
+        self
+    }
+
+    /// Exempts requests to the scope or resource at `pattern` from CSRF checks.
+    ///
+    /// Patterns are matched as scope prefixes, so `/webhooks` exempts `/webhooks` and all paths
+    /// under it, and may have dynamic segments such as `/{tenant}/webhooks`. Exempted requests
+    /// still get a token.
+    pub fn exempt(mut self, pattern: &str) -> Self {
+        self.inner_mut()
+            .exempt
+            .push(ResourceDef::root_prefix(pattern));
+        self
+    }
+}
+
+impl fmt::Debug for Csrf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Csrf")
+            .field("cookie_name", &self.inner.cookie_name)
+            .field("header", &self.inner.header)
+            .field("field", &self.inner.field)
+            .field("form_limit", &self.inner.form_limit)
+            .field("trusted_origins", &self.inner.trusted_origins)
+            .field(
+                "exempt",
+                &self
+                    .inner
+                    .exempt
+                    .iter()
+                    .map(ResourceDef::pattern)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+/// Errors for requests rejected by the [`Csrf`] middleware.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum CsrfError {
+    /// The request comes from another origin.
+    #[display(fmt = "Cross-origin request denied.")]
+    CrossOrigin,
+
+    /// The request does not include a CSRF token.
+    #[display(fmt = "Missing CSRF token.")]
+    MissingToken,
+
+    /// The submitted CSRF token does not match the token cookie.
+    #[display(fmt = "Invalid CSRF token.")]
+    InvalidToken,
+}
+
+impl ResponseError for CsrfError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::FORBIDDEN
+    }
+}
+
+impl Inner {
+    /// Returns whether `req` comes from the same origin or from a trusted origin.
+    ///
+    /// Requests without `Origin` and `Referer` headers are allowed.
+    fn is_same_origin(&self, req: &ServiceRequest) -> bool {
+        let source = req
+            .headers()
+            .get(header::ORIGIN)
+            .or_else(|| req.headers().get(header::REFERER));
+
+        let source = match source {
+            Some(source) => source,
+            None => return true,
+        };
+
+        let url = match source.to_str().ok().and_then(|val| Url::parse(val).ok()) {
+            Some(url) => url,
+            None => return false,
+        };
+
+        let host = match url.host_str() {
+            Some(host) => host,
+            None => return false,
+        };
+
+        let authority = match url.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_owned(),
+        };
+
+        authority.eq_ignore_ascii_case(req.connection_info().host())
+            || self
+                .trusted_origins
+                .contains(&url.origin().ascii_serialization())
+    }
+
+    fn is_exempt(&self, req: &ServiceRequest) -> bool {
+        self.exempt.iter().any(|rdef| rdef.is_match(req.path()))
+    }
+
+    /// Returns the token submitted in the form body of `req`, buffering the body and giving it
+    /// back to the request.
+    async fn form_token(&self, req: &mut ServiceRequest) -> Result<Option<String>, Error> {
+        let is_form = req
+            .mime_type()
+            .ok()
+            .flatten()
+            .map_or(false, |mime| mime == mime::APPLICATION_WWW_FORM_URLENCODED);
+
+        let too_large = req
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|len| len.to_str().ok())
+            .and_then(|len| len.parse::<usize>().ok())
+            .map_or(false, |len| len > self.form_limit);
+
+        if !is_form || too_large {
+            return Ok(None);
+        }
+
+        let mut payload = req.take_payload();
+        let mut body = BytesMut::new();
+
+        while let Some(chunk) = payload.next().await {
+            body.extend_from_slice(&chunk?);
+
+            if body.len() > self.form_limit {
+                return Err(PayloadError::Overflow.into());
+            }
+        }
+
+        let body = body.freeze();
+
+        let token = form_urlencoded::parse(&body)
+            .find(|(name, _)| *name == self.field)
+            .map(|(_, token)| token.into_owned());
+
+        let (_, mut h1_payload) = actix_http::h1::Payload::create(true);
+        h1_payload.unread_data(body);
+        req.set_payload(Payload::from(h1_payload));
+
+        Ok(token)
+    }
+
+    /// Returns the token cookie storing `token`.
+    fn cookie(&self, token: &str) -> Cookie<'static> {
+        Cookie::build(self.cookie_name.clone(), token.to_owned())
+            .path("/")
+            .secure(self.cookie_secure)
+            .same_site(SameSite::Strict)
+            .finish()
+    }
+}
+
+/// Returns whether requests with `method` must be checked.
+fn is_unsafe(method: &Method) -> bool {
+    !matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    )
+}
+
+fn is_valid_token(token: &str) -> bool {
+    token.len() == TOKEN_LEN && token.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Generates a random token.
+fn generate_token() -> String {
+    rand::random::<[u8; TOKEN_LEN / 2]>()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Compares tokens in constant time, so that timing does not reveal the expected token.
+fn tokens_match(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Csrf
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Transform = CsrfMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CsrfMiddleware {
+            service: Rc::new(service),
+            inner: Rc::clone(&self.inner),
+        }))
+    }
+}
+
+pub struct CsrfMiddleware<S> {
+    service: Rc<S>,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for CsrfMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let inner = Rc::clone(&self.inner);
+
+        Box::pin(async move {
+            let cookie_token = req
+                .cookie(&inner.cookie_name)
+                .map(|cookie| cookie.value().to_owned())
+                .filter(|token| is_valid_token(token));
+
+            if is_unsafe(req.method()) && !inner.is_exempt(&req) {
+                if !inner.is_same_origin(&req) {
+                    return Ok(req.error_response(CsrfError::CrossOrigin));
+                }
+
+                let submitted = match req
+                    .headers()
+                    .get(&inner.header)
+                    .and_then(|val| val.to_str().ok())
+                {
+                    Some(token) => Some(token.to_owned()),
+                    None => inner.form_token(&mut req).await?,
+                };
+
+                let err = match (&submitted, &cookie_token) {
+                    (None, _) => Some(CsrfError::MissingToken),
+                    (Some(submitted), Some(expected)) if tokens_match(submitted, expected) => {
+                        None
+                    }
+                    (Some(_), _) => Some(CsrfError::InvalidToken),
+                };
+
+                if let Some(err) = err {
+                    return Ok(req.error_response(err));
+                }
+            }
+
+            let (token, is_new) = match cookie_token {
+                Some(token) => (token, false),
+                None => (generate_token(), true),
+            };
+
+            req.extensions_mut().insert(web::CsrfToken::new(
+                token.clone(),
+                inner.header.clone(),
+                inner.field.clone(),
+            ));
+
+            let mut res = service.call(req).await?;
+
+            if is_new {
+                let cookie = inner.cookie(&token);
+                let cookie = HeaderValue::from_str(&cookie.to_string())?;
+                res.headers_mut().append(header::SET_COOKIE, cookie);
+            }
+
+            Ok(res.map_body(|_, body| Body::from_message(body)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test::{self, TestRequest},
+        App, HttpResponse,
+    };
+
+    async fn init(
+    ) -> impl Service<actix_http::Request, Response = ServiceResponse, Error = Error> {
+        test::init_service(
+            App::new()
+                .wrap(Csrf::new().exempt("/hooks"))
+                .route(
+                    "/form",
+                    web::get().to(|token: web::CsrfToken| {
+                        HttpResponse::Ok().body(token.into_inner())
+                    }),
+                )
+                .route(
+                    "/form",
+                    web::post().to(|form: web::Form<Vec<(String, String)>>| {
+                        HttpResponse::Ok().body(form.0[1].1.clone())
+                    }),
+                )
+                .route("/api", web::post().to(HttpResponse::Ok))
+                .route("/hooks/{id}", web::post().to(HttpResponse::Ok)),
+        )
+        .await
+    }
+
+    async fn token(
+        srv: &impl Service<actix_http::Request, Response = ServiceResponse, Error = Error>,
+    ) -> Cookie<'static> {
+        let req = TestRequest::with_uri("/form").to_request();
+        let res = test::call_service(srv, req).await;
+        let header = res.headers().get(header::SET_COOKIE).unwrap();
+        let cookie = Cookie::parse(header.to_str().unwrap().to_owned()).unwrap();
+        assert_eq!(test::read_body(res).await, cookie.value());
+        cookie
+    }
+
+    #[actix_rt::test]
+    async fn validates_tokens() {
+        let srv = init().await;
+        let cookie = token(&srv).await;
+        assert!(is_valid_token(cookie.value()));
+        assert_eq!(cookie.same_site(), Some(SameSite::Strict));
+
+        let req = TestRequest::post()
+            .uri("/api")
+            .cookie(cookie.clone())
+            .insert_header(("x-csrf-token", cookie.value()))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.headers().get(header::SET_COOKIE).is_none());
+
+        let req = TestRequest::post()
+            .uri("/api")
+            .cookie(cookie.clone())
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let req = TestRequest::post()
+            .uri("/api")
+            .cookie(cookie)
+            .insert_header(("x-csrf-token", generate_token()))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[actix_rt::test]
+    async fn reads_form_field() {
+        let srv = init().await;
+        let cookie = token(&srv).await;
+
+        let req = TestRequest::post()
+            .uri("/form")
+            .cookie(cookie.clone())
+            .insert_header((header::CONTENT_TYPE, "application/x-www-form-urlencoded"))
+            .set_payload(format!("csrf_token={}&name=ferris", cookie.value()))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(test::read_body(res).await, "ferris");
+    }
+
+    #[actix_rt::test]
+    async fn checks_origin() {
+        let srv = init().await;
+        let cookie = token(&srv).await;
+
+        let req = TestRequest::post()
+            .uri("/api")
+            .cookie(cookie.clone())
+            .insert_header(("x-csrf-token", cookie.value()))
+            .insert_header((header::ORIGIN, "https://evil.example"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let req = TestRequest::post()
+            .uri("/api")
+            .cookie(cookie.clone())
+            .insert_header(("x-csrf-token", cookie.value()))
+            .insert_header((header::REFERER, "http://localhost:8080/form"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::post()
+            .uri("/hooks/1")
+            .insert_header((header::ORIGIN, "https://evil.example"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,632 @@
+//! For middleware documentation, see [`Decompress`].
+
+use std::{
+    error::Error as StdError,
+    future::Future,
+    io::{self, Write as _},
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use actix_http::{
+    body::{Body, MessageBody},
+    error::{BlockingError, PayloadError},
+};
+use actix_rt::task::{spawn_blocking, JoinHandle};
+use actix_utils::future::{ready, Ready};
+use bytes::{Bytes, BytesMut};
+use futures_core::{future::LocalBoxFuture, ready, Stream};
+
+#[cfg(feature = "compress-brotli")]
+use brotli2::write::BrotliDecoder;
+
+#[cfg(feature = "compress-gzip")]
+use flate2::write::{GzDecoder, ZlibDecoder};
+
+#[cfg(feature = "compress-zstd")]
+use zstd::stream::write::Decoder as ZstdDecoder;
+
+use crate::{
+    dev::{Payload, Service, Transform},
+    http::header::{self, ContentEncoding},
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpMessage as _, HttpResponse,
+};
+
+/// Default maximum size of decompressed bodies.
+const DEFAULT_MAX_SIZE: u64 = 8_388_608; // 8 MiB
+
+/// Default maximum ratio of decompressed to compressed size.
+const DEFAULT_MAX_RATIO: u64 = 100;
+
+/// Number of decompressed bytes allowed before the compression ratio is enforced, since small
+/// bodies can legitimately compress at high ratios.
+const RATIO_GRACE: u64 = 65_536; // 64 KiB
+
+/// Size of compressed chunks above which they are decoded on the blocking thread pool.
+const MAX_CHUNK_SIZE_DECODE_IN_PLACE: usize = 2049;
+
+/// Middleware for decompressing request payloads.
+///
+/// Payloads of requests with a `Content-Encoding` of `gzip`, `deflate`, `br` or `zstd`, depending
+/// on the enabled `compress-*` features, are decoded as they are read, so that all extractors,
+/// including [`web::Payload`](crate::web::Payload), receive the decompressed body. The
+/// `Content-Encoding` and `Content-Length` headers are removed from such requests, so extractors
+/// do not decode them again. Requests with any other content coding, or several of them, are
+/// answered with *415 Unsupported Media Type* listing the supported codings in an
+/// `Accept-Encoding` header.
+///
+/// To protect against decompression bombs, reading the payload fails with
+/// [`PayloadError::Overflow`], answered with *413 Payload Too Large*, once the decompressed body
+/// exceeds the [maximum size](Self::max_size), or once it is over 64 KiB and exceeds the
+/// [maximum ratio](Self::max_ratio) to the compressed bytes read so far. Decoding stops as soon as
+/// a limit is reached, so no more than the allowed number of bytes is ever held in memory, even
+/// for a single large compressed chunk.
+///
+/// When wrapped by a [`BodyLimit`](super::BodyLimit) middleware, the body limit applies to the
+/// compressed payload; when wrapping one, it applies to the decompressed payload.
+///
+/// # Examples
+/// ```
+/// use actix_web::{middleware, web, App, HttpResponse};
+///
+/// let app = App::new()
+///     .wrap(middleware::Decompress::new().max_size(1_048_576).max_ratio(20))
+///     .route("/", web::post().to(|body: String| HttpResponse::Ok().body(body)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Decompress {
+    max_size: u64,
+    max_ratio: u64,
+}
+
+impl Default for Decompress {
+    fn default() -> Self {
+        Decompress {
+            max_size: DEFAULT_MAX_SIZE,
+            max_ratio: DEFAULT_MAX_RATIO,
+        }
+    }
+}
+
+impl Decompress {
+    /// Constructs a `Decompress` middleware with the default limits.
+    pub fn new() -> Decompress {
+        Decompress::default()
+    }
+
+    /// Sets the maximum size of decompressed bodies, in bytes.
+    ///
+    /// Default is 8 MiB.
+    pub fn max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Sets the maximum ratio of the size of decompressed bodies to the size of their compressed
+    /// form.
+    ///
+    /// Default is 100.
+    pub fn max_ratio(mut self, max_ratio: u64) -> Self {
+        self.max_ratio = max_ratio;
+        self
+    }
+}
+
+/// Content codings that can be decoded, as listed in `Accept-Encoding` headers.
+const SUPPORTED: &[(ContentEncoding, bool)] = &[
+    (ContentEncoding::Gzip, cfg!(feature = "compress-gzip")),
+    (ContentEncoding::Deflate, cfg!(feature = "compress-gzip")),
+    (ContentEncoding::Br, cfg!(feature = "compress-brotli")),
+    (ContentEncoding::Zstd, cfg!(feature = "compress-zstd")),
+];
+
+/// Parses the content coding of a request, returning `None` when it cannot be decoded.
+fn parse_encoding(val: &header::HeaderValue) -> Option<ContentEncoding> {
+    let val = val.to_str().ok()?.trim();
+
+    if val.eq_ignore_ascii_case("identity") {
+        return Some(ContentEncoding::Identity);
+    }
+
+    let encoding = val.parse::<ContentEncoding>().ok()?;
+
+    SUPPORTED
+        .iter()
+        .any(|&(supported, enabled)| enabled && supported == encoding)
+        .then(|| encoding)
+}
+
+fn accept_encoding() -> String {
+    SUPPORTED
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(encoding, _)| encoding.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Decompress
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Transform = DecompressMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(DecompressMiddleware {
+            service: Rc::new(service),
+            max_size: self.max_size,
+            max_ratio: self.max_ratio,
+        }))
+    }
+}
+
+pub struct DecompressMiddleware<S> {
+    service: Rc<S>,
+    max_size: u64,
+    max_ratio: u64,
+}
+
+impl<S, B> Service<ServiceRequest> for DecompressMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        let encoding = match req.headers().get(header::CONTENT_ENCODING) {
+            Some(val) => parse_encoding(val),
+            None => Some(ContentEncoding::Identity),
+        };
+
+        let encoding = match encoding {
+            Some(encoding) => encoding,
+            None => {
+                let res = HttpResponse::UnsupportedMediaType()
+                    .insert_header((header::ACCEPT_ENCODING, accept_encoding()))
+                    .finish();
+
+                return Box::pin(async move { Ok(req.into_response(res)) });
+            }
+        };
+
+        if encoding != ContentEncoding::Identity {
+            let headers = req.headers_mut();
+            headers.remove(header::CONTENT_ENCODING);
+            headers.remove(header::CONTENT_LENGTH);
+
+            let payload = DecompressedPayload {
+                payload: req.take_payload(),
+                decoder: ContentDecoder::new(encoding),
+                fut: None,
+                compressed: 0,
+                decompressed: 0,
+                max_size: self.max_size,
+                max_ratio: self.max_ratio,
+            };
+
+            req.set_payload(Payload::Stream(Box::pin(payload)));
+        }
+
+        Box::pin(async move {
+            let res = service.call(req).await?;
+            Ok(res.map_body(|_, body| Body::from_message(body)))
+        })
+    }
+}
+
+/// Result of decoding a chunk, along with the decoder to continue with.
+type Decoded = (Result<Bytes, PayloadError>, ContentDecoder);
+
+/// Payload stream decoding the compressed payload and failing once it exceeds the limits.
+struct DecompressedPayload {
+    payload: Payload,
+    decoder: Option<ContentDecoder>,
+    fut: Option<JoinHandle<Decoded>>,
+    compressed: u64,
+    decompressed: u64,
+    max_size: u64,
+    max_ratio: u64,
+}
+
+impl DecompressedPayload {
+    /// Returns the number of bytes that may still be decompressed from the compressed bytes read
+    /// so far.
+    fn remaining(&self) -> u64 {
+        let max_decompressed = self
+            .compressed
+            .saturating_mul(self.max_ratio)
+            .max(RATIO_GRACE);
+
+        self.max_size
+            .min(max_decompressed)
+            .saturating_sub(self.decompressed)
+    }
+
+    /// Accounts for the output of the decoder, returning `None` if there was none.
+    fn output(
+        &mut self,
+        res: Result<Bytes, PayloadError>,
+    ) -> Option<Result<Bytes, PayloadError>> {
+        match res {
+            Ok(chunk) if chunk.is_empty() => None,
+            Ok(chunk) => {
+                self.decompressed += chunk.len() as u64;
+                Some(Ok(chunk))
+            }
+            Err(err) => {
+                self.decoder = None;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl Stream for DecompressedPayload {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(ref mut fut) = this.fut {
+                let res = ready!(Pin::new(fut).poll(cx));
+                this.fut = None;
+
+                let (res, decoder) = res.map_err(|_| BlockingError)?;
+                this.decoder = Some(decoder);
+
+                if let Some(item) = this.output(res) {
+                    return Poll::Ready(Some(item));
+                }
+            }
+
+            let mut decoder = match this.decoder.take() {
+                Some(decoder) => decoder,
+                None => return Poll::Ready(None),
+            };
+
+            match Pin::new(&mut this.payload).poll_next(cx) {
+                Poll::Pending => {
+                    this.decoder = Some(decoder);
+                    return Poll::Pending;
+                }
+
+                Poll::Ready(Some(Ok(chunk))) => {
+                    this.compressed += chunk.len() as u64;
+                    let limit = this.remaining();
+
+                    if chunk.len() < MAX_CHUNK_SIZE_DECODE_IN_PLACE {
+                        let res = decoder.decode(Some(&chunk), limit);
+                        this.decoder = Some(decoder);
+
+                        if let Some(item) = this.output(res) {
+                            return Poll::Ready(Some(item));
+                        }
+                    } else {
+                        this.fut = Some(spawn_blocking(move || {
+                            let res = decoder.decode(Some(&chunk), limit);
+                            (res, decoder)
+                        }));
+                    }
+                }
+
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+
+                Poll::Ready(None) => {
+                    let res = decoder.decode(None, this.remaining());
+                    return Poll::Ready(this.output(res));
+                }
+            }
+        }
+    }
+}
+
+/// Writer collecting decoded bytes, failing once more than `limit` bytes are written.
+struct LimitedWriter {
+    buf: BytesMut,
+    limit: u64,
+    overflowed: bool,
+}
+
+impl LimitedWriter {
+    fn new() -> LimitedWriter {
+        LimitedWriter {
+            buf: BytesMut::with_capacity(8192),
+            limit: 0,
+            overflowed: false,
+        }
+    }
+}
+
+impl io::Write for LimitedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() as u64 > self.limit {
+            self.overflowed = true;
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "decompressed payload is too large",
+            ));
+        }
+
+        self.limit -= buf.len() as u64;
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+enum ContentDecoder {
+    #[cfg(feature = "compress-gzip")]
+    Deflate(Box<ZlibDecoder<LimitedWriter>>),
+    #[cfg(feature = "compress-gzip")]
+    Gzip(Box<GzDecoder<LimitedWriter>>),
+    #[cfg(feature = "compress-brotli")]
+    Br(Box<BrotliDecoder<LimitedWriter>>),
+    // 'static lifetime since decoders are moved to the blocking thread pool
+    #[cfg(feature = "compress-zstd")]
+    Zstd(Box<ZstdDecoder<'static, LimitedWriter>>),
+}
+
+impl ContentDecoder {
+    fn new(encoding: ContentEncoding) -> Option<ContentDecoder> {
+        match encoding {
+            #[cfg(feature = "compress-gzip")]
+            ContentEncoding::Deflate => Some(ContentDecoder::Deflate(Box::new(
+                ZlibDecoder::new(LimitedWriter::new()),
+            ))),
+            #[cfg(feature = "compress-gzip")]
+            ContentEncoding::Gzip => Some(ContentDecoder::Gzip(Box::new(GzDecoder::new(
+                LimitedWriter::new(),
+            )))),
+            #[cfg(feature = "compress-brotli")]
+            ContentEncoding::Br => Some(ContentDecoder::Br(Box::new(BrotliDecoder::new(
+                LimitedWriter::new(),
+            )))),
+            #[cfg(feature = "compress-zstd")]
+            ContentEncoding::Zstd => Some(ContentDecoder::Zstd(Box::new(
+                ZstdDecoder::new(LimitedWriter::new()).expect("Failed to create zstd decoder."),
+            ))),
+            _ => None,
+        }
+    }
+
+    fn writer(&mut self) -> &mut LimitedWriter {
+        match self {
+            #[cfg(feature = "compress-gzip")]
+            ContentDecoder::Deflate(decoder) => decoder.get_mut(),
+            #[cfg(feature = "compress-gzip")]
+            ContentDecoder::Gzip(decoder) => decoder.get_mut(),
+            #[cfg(feature = "compress-brotli")]
+            ContentDecoder::Br(decoder) => decoder.get_mut(),
+            #[cfg(feature = "compress-zstd")]
+            ContentDecoder::Zstd(decoder) => decoder.get_mut(),
+        }
+    }
+
+    fn write_all(&mut self, data: &[u8]) -> io::Result<()> {
+        match self {
+            #[cfg(feature = "compress-gzip")]
+            ContentDecoder::Deflate(decoder) => decoder.write_all(data)?,
+            #[cfg(feature = "compress-gzip")]
+            ContentDecoder::Gzip(decoder) => decoder.write_all(data)?,
+            #[cfg(feature = "compress-brotli")]
+            ContentDecoder::Br(decoder) => decoder.write_all(data)?,
+            #[cfg(feature = "compress-zstd")]
+            ContentDecoder::Zstd(decoder) => decoder.write_all(data)?,
+        }
+
+        self.flush()
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            #[cfg(feature = "compress-gzip")]
+            ContentDecoder::Deflate(decoder) => decoder.flush(),
+            #[cfg(feature = "compress-gzip")]
+            ContentDecoder::Gzip(decoder) => decoder.flush(),
+            #[cfg(feature = "compress-brotli")]
+            ContentDecoder::Br(decoder) => decoder.flush(),
+            #[cfg(feature = "compress-zstd")]
+            ContentDecoder::Zstd(decoder) => decoder.flush(),
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        match self {
+            #[cfg(feature = "compress-gzip")]
+            ContentDecoder::Deflate(decoder) => decoder.try_finish(),
+            #[cfg(feature = "compress-gzip")]
+            ContentDecoder::Gzip(decoder) => decoder.try_finish(),
+            #[cfg(feature = "compress-brotli")]
+            ContentDecoder::Br(decoder) => decoder.flush(),
+            #[cfg(feature = "compress-zstd")]
+            ContentDecoder::Zstd(decoder) => decoder.flush(),
+        }
+    }
+
+    /// Decodes `data`, or the end of the payload if `None`, failing with
+    /// [`PayloadError::Overflow`] once the output would exceed `limit` bytes.
+    fn decode(&mut self, data: Option<&[u8]>, limit: u64) -> Result<Bytes, PayloadError> {
+        self.writer().limit = limit;
+
+        let res = match data {
+            Some(data) => self.write_all(data),
+            None => self.finish(),
+        };
+
+        let writer = self.writer();
+
+        match res {
+            Ok(()) => Ok(writer.buf.split().freeze()),
+            Err(_) if writer.overflowed => Err(PayloadError::Overflow),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "compress-gzip"))]
+mod tests {
+    use std::io::Write as _;
+
+    use flate2::{write::GzEncoder, Compression};
+    use futures_util::StreamExt as _;
+
+    use super::*;
+    use crate::{
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    fn gzip(body: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(body).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    async fn init(
+    ) -> impl Service<actix_http::Request, Response = ServiceResponse, Error = Error> {
+        test::init_service(
+            App::new()
+                .wrap(Decompress::new().max_size(200_000).max_ratio(10))
+                .route(
+                    "/",
+                    web::post().to(|body: Bytes| HttpResponse::Ok().body(body)),
+                )
+                .service(
+                    web::resource("/large")
+                        .app_data(web::PayloadConfig::new(1_048_576))
+                        .route(web::post().to(|body: Bytes| HttpResponse::Ok().body(body))),
+                ),
+        )
+        .await
+    }
+
+    #[actix_rt::test]
+    async fn decompresses_payloads() {
+        let srv = init().await;
+
+        let req = TestRequest::post()
+            .insert_header((header::CONTENT_ENCODING, "gzip"))
+            .set_payload(gzip(b"hello world"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(test::read_body(res).await, "hello world");
+
+        let req = TestRequest::post()
+            .insert_header((header::CONTENT_ENCODING, "identity"))
+            .set_payload("hello world")
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(test::read_body(res).await, "hello world");
+
+        let req = TestRequest::post()
+            .insert_header((header::CONTENT_ENCODING, "compress"))
+            .set_payload("hello world")
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let accepted = res.headers().get(header::ACCEPT_ENCODING).unwrap();
+        assert!(accepted.to_str().unwrap().contains("gzip"));
+    }
+
+    #[actix_rt::test]
+    async fn rejects_decompression_bombs() {
+        let srv = init().await;
+
+        // compressible, but under the grace size of the ratio check
+        let req = TestRequest::post()
+            .uri("/large")
+            .insert_header((header::CONTENT_ENCODING, "gzip"))
+            .set_payload(gzip(&[0; 60_000]))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // over the maximum ratio
+        let req = TestRequest::post()
+            .uri("/large")
+            .insert_header((header::CONTENT_ENCODING, "gzip"))
+            .set_payload(gzip(&[0; 150_000]))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // over the maximum size
+        let mut state = 0x2545_f491_u32;
+        let body = (0..250_000)
+            .map(|_| {
+                // xorshift, so that the body does not compress
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect::<Vec<_>>();
+        let req = TestRequest::post()
+            .uri("/large")
+            .insert_header((header::CONTENT_ENCODING, "gzip"))
+            .set_payload(gzip(&body))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[actix_rt::test]
+    async fn bounds_large_chunks() {
+        // 50 MiB of zeros, compressed to a single chunk of about 50 KiB
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        for _ in 0..800 {
+            encoder.write_all(&[0; 65_536]).unwrap();
+        }
+        let compressed = Bytes::from(encoder.finish().unwrap());
+        let max_decompressed = compressed.len() as u64 * 10;
+
+        let mut payload = DecompressedPayload {
+            payload: Payload::Stream(Box::pin(futures_util::stream::once(async move {
+                Ok(compressed)
+            }))),
+            decoder: ContentDecoder::new(ContentEncoding::Gzip),
+            fut: None,
+            compressed: 0,
+            decompressed: 0,
+            max_size: DEFAULT_MAX_SIZE,
+            max_ratio: 10,
+        };
+
+        let mut decompressed = 0;
+        let err = loop {
+            match payload.next().await {
+                Some(Ok(chunk)) => decompressed += chunk.len() as u64,
+                Some(Err(err)) => break err,
+                None => panic!("expected decompression bomb to be rejected"),
+            }
+        };
+
+        assert!(matches!(err, PayloadError::Overflow));
+        assert!(decompressed <= max_decompressed);
+        assert!(payload.next().await.is_none());
+    }
+}
@@ -1,6 +1,7 @@
 //! For middleware documentation, see [`Logger`].
 
 use std::{
+    cell::Cell,
     collections::HashSet,
     convert::TryFrom,
     env,
@@ -15,16 +16,19 @@ use std::{
 use actix_service::{Service, Transform};
 use actix_utils::future::{ok, Ready};
 use bytes::Bytes;
-use futures_core::ready;
+use futures_core::{ready, Stream};
 use log::{debug, warn};
 use regex::{Regex, RegexSet};
 use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 
 use crate::{
-    dev::{BodySize, MessageBody},
+    dev::{BodySize, MessageBody, Payload},
+    error::PayloadError,
     http::{HeaderName, StatusCode},
+    middleware::TimedOut,
     service::{ServiceRequest, ServiceResponse},
-    Error, HttpResponse, Result,
+    web::RequestId,
+    Error, HttpMessage as _, HttpResponse, Result,
 };
 
 /// Middleware for logging request and response summaries to the terminal.
@@ -66,12 +70,33 @@ use crate::{
 /// `%T` | Time taken to serve the request, in seconds to 6 decimal places
 /// `%D` | Time taken to serve the request, in milliseconds
 /// `%U` | Request URL
+/// `%{request_id}` | Request ID assigned by the [`RequestId`](super::RequestId) middleware
+/// `%{match_pattern}` | [Resource pattern](crate::HttpRequest::match_pattern) that matched the request
+/// `%{request_size}` | Size of the request body read by the app, in bytes
+/// `%X` | `timeout` if the request was cancelled by the [`Timeout`](super::Timeout) middleware, otherwise `-`
 /// `%{r}a` | "Real IP" remote address **\***
 /// `%{FOO}i` |  `request.headers["FOO"]`
 /// `%{FOO}o` | `response.headers["FOO"]`
 /// `%{FOO}e` | `env_var["FOO"]`
 /// `%{FOO}xi` | [Custom request replacement](Logger::custom_request_replace) labelled "FOO"
 ///
+/// # JSON Output
+/// A logger constructed with [`Logger::json`] logs each request as a JSON object instead, with
+/// fields added by [`field`](Logger::field). Each field is rendered from a format string like the
+/// ones above. Fields consisting of a single numeric unit, such as `%s` or `%T`, are logged as
+/// numbers, and fields consisting of a single unit with no value, such as a missing header, are
+/// logged as `null`.
+///
+/// ```
+/// use actix_web::{middleware::Logger, App};
+///
+/// let app = App::new().wrap(
+///     Logger::json()
+///         .field("forwarded_for", "%{X-Forwarded-For}i")
+///         .field("content_type", "%{Content-Type}o"),
+/// );
+/// ```
+///
 /// # Security
 /// **\*** "Real IP" remote address is calculated using
 /// [`ConnectionInfo::realip_remote_addr()`](crate::dev::ConnectionInfo::realip_remote_addr())
@@ -84,6 +109,7 @@ pub struct Logger(Rc<Inner>);
 #[derive(Debug, Clone)]
 struct Inner {
     format: Format,
+    json: bool,
     exclude: HashSet<String>,
     exclude_regex: RegexSet,
 }
@@ -93,11 +119,90 @@ impl Logger {
     pub fn new(format: &str) -> Logger {
         Logger(Rc::new(Inner {
             format: Format::new(format),
+            json: false,
             exclude: HashSet::new(),
             exclude_regex: RegexSet::empty(),
         }))
     }
 
+    /// Create `Logger` middleware that logs JSON objects with the default fields.
+    ///
+    /// The default fields are:
+    ///
+    /// Field | Format
+    /// ----- | ------
+    /// `remote_addr` | `%a`
+    /// `request` | `%r`
+    /// `match_pattern` | `%{match_pattern}`
+    /// `status` | `%s`
+    /// `request_size` | `%{request_size}`
+    /// `response_size` | `%b`
+    /// `referer` | `%{Referer}i`
+    /// `user_agent` | `%{User-Agent}i`
+    /// `duration` | `%T`
+    ///
+    /// See the [JSON output](Logger#json-output) section for details.
+    pub fn json() -> Logger {
+        let logger = Logger(Rc::new(Inner {
+            format: Format(Vec::new()),
+            json: true,
+            exclude: HashSet::new(),
+            exclude_regex: RegexSet::empty(),
+        }));
+
+        DEFAULT_JSON_FIELDS
+            .iter()
+            .fold(logger, |logger, (key, format)| logger.field(key, format))
+    }
+
+    /// Add a field to the objects logged by a [JSON](Logger::json) logger, replacing any
+    /// existing field with the same key.
+    ///
+    /// The field is rendered from `format`, using the same syntax as [`Logger::new`].
+    pub fn field(mut self, key: &str, format: &str) -> Self {
+        let inner = Rc::get_mut(&mut self.0).expect("Multiple `Inner` copies exist.");
+
+        if !inner.json {
+            // non-printed field diagnostic
+            debug!(
+                "Attempted to add field \"{}\" to a Logger that does not log JSON",
+                key
+            );
+            return self;
+        }
+
+        let units = &mut inner.format.0;
+
+        // remove existing field and its units
+        if let Some(start) = units.iter().position(
+            |ft| matches!(ft, FormatText::Field { key: field_key, .. } if key == field_key),
+        ) {
+            let end = units[start + 1..]
+                .iter()
+                .position(|ft| matches!(ft, FormatText::Field { .. }))
+                .map_or(units.len(), |len| start + 1 + len);
+            units.drain(start..end);
+        }
+
+        let Format(field_units) = Format::new(format);
+        let numeric = matches!(
+            field_units.as_slice(),
+            [FormatText::ResponseStatus]
+                | [FormatText::ResponseSize]
+                | [FormatText::RequestSize(_)]
+                | [FormatText::Time]
+                | [FormatText::TimeMillis]
+        );
+
+        units.push(FormatText::Field {
+            key: key.to_owned(),
+            numeric,
+        });
+        units.extend(field_units);
+
+        self
+    }
+
     /// Ignore and do not log access info for specified path.
     pub fn exclude<T: Into<String>>(mut self, path: T) -> Self {
         Rc::get_mut(&mut self.0)
@@ -167,6 +272,7 @@ impl Default for Logger {
     fn default() -> Logger {
         Logger(Rc::new(Inner {
             format: Format::default(),
+            json: false,
             exclude: HashSet::new(),
             exclude_regex: RegexSet::empty(),
         }))
@@ -219,13 +325,14 @@ where
 
     actix_service::forward_ready!(service);
 
-    fn call(&self, req: ServiceRequest) -> Self::Future {
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
         if self.inner.exclude.contains(req.path())
             || self.inner.exclude_regex.is_match(req.path())
         {
             LoggerResponse {
                 fut: self.service.call(req),
                 format: None,
+                json: false,
                 time: OffsetDateTime::now_utc(),
                 _phantom: PhantomData,
             }
@@ -236,9 +343,32 @@ where
             for unit in &mut format.0 {
                 unit.render_request(now, &req);
             }
+
+            let counts_request_size = format
+                .0
+                .iter()
+                .any(|unit| matches!(unit, FormatText::RequestSize(_)));
+
+            if counts_request_size {
+                let counter = Rc::new(Cell::new(0));
+
+                let payload = req.take_payload();
+                req.set_payload(Payload::Stream(Box::pin(CountedPayload {
+                    payload,
+                    counter: Rc::clone(&counter),
+                })));
+
+                for unit in &mut format.0 {
+                    if let FormatText::RequestSize(ref mut size) = unit {
+                        *size = Some(Rc::clone(&counter));
+                    }
+                }
+            }
+
             LoggerResponse {
                 fut: self.service.call(req),
                 format: Some(format),
+                json: self.inner.json,
                 time: now,
                 _phantom: PhantomData,
             }
@@ -256,6 +386,7 @@ where
     fut: S::Future,
     time: OffsetDateTime,
     format: Option<Format>,
+    json: bool,
     _phantom: PhantomData<B>,
 }
 
@@ -282,17 +413,19 @@ where
 
         if let Some(ref mut format) = this.format {
             for unit in &mut format.0 {
-                unit.render_response(res.response());
+                unit.render_service_response(&res);
             }
         }
 
         let time = *this.time;
         let format = this.format.take();
+        let json = *this.json;
 
         Poll::Ready(Ok(res.map_body(move |_, body| StreamLog {
             body,
             time,
             format,
+            json,
             size: 0,
         })))
     }
@@ -305,6 +438,7 @@ pub struct StreamLog<B> {
     #[pin]
     body: B,
     format: Option<Format>,
+    json: bool,
     size: usize,
     time: OffsetDateTime,
 }
@@ -313,6 +447,11 @@ pub struct StreamLog<B> {
 impl<B> PinnedDrop for StreamLog<B> {
     fn drop(self: Pin<&mut Self>) {
         if let Some(ref format) = self.format {
+            if self.json {
+                log::info!("{}", format.render_json(self.size, self.time));
+                return;
+            }
+
             let render = |fmt: &mut fmt::Formatter<'_>| {
                 for unit in &format.0 {
                     unit.render(fmt, self.size, self.time)?;
@@ -352,6 +491,39 @@ where
     }
 }
 
+/// Request payload counting the bytes read from it.
+struct CountedPayload {
+    payload: Payload,
+    counter: Rc<Cell<u64>>,
+}
+
+impl Stream for CountedPayload {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let item = ready!(Pin::new(&mut self.payload).poll_next(cx));
+
+        if let Some(Ok(ref chunk)) = item {
+            self.counter.set(self.counter.get() + chunk.len() as u64);
+        }
+
+        Poll::Ready(item)
+    }
+}
+
+/// Fields logged by [`Logger::json`] by default.
+const DEFAULT_JSON_FIELDS: &[(&str, &str)] = &[
+    ("remote_addr", "%a"),
+    ("request", "%r"),
+    ("match_pattern", "%{match_pattern}"),
+    ("status", "%s"),
+    ("request_size", "%{request_size}"),
+    ("response_size", "%b"),
+    ("referer", "%{Referer}i"),
+    ("user_agent", "%{User-Agent}i"),
+    ("duration", "%T"),
+];
+
 /// A formatting style for the `Logger` consisting of multiple concatenated `FormatText` items.
 #[derive(Debug, Clone)]
 struct Format(Vec<FormatText>);
@@ -369,7 +541,7 @@ impl Format {
     /// Returns `None` if the format string syntax is incorrect.
     pub fn new(s: &str) -> Format {
         log::trace!("Access log format: {}", s);
-        let fmt = Regex::new(r"%(\{([A-Za-z0-9\-_]+)\}([aioe]|xi)|[%atPrUsbTD]?)").unwrap();
+        let fmt = Regex::new(r"%(\{([A-Za-z0-9\-_]+)\}([aioe]|xi)?|[%atPrUsbTDX]?)").unwrap();
 
         let mut idx = 0;
         let mut results = Vec::new();
@@ -382,7 +554,27 @@ impl Format {
             idx = m.end();
 
             if let Some(key) = cap.get(2) {
-                results.push(match cap.get(3).unwrap().as_str() {
+                let kind = match cap.get(3) {
+                    Some(kind) => kind.as_str(),
+                    None if key.as_str() == "request_id" => {
+                        results.push(FormatText::RequestId);
+                        continue;
+                    }
+                    None if key.as_str() == "match_pattern" => {
+                        results.push(FormatText::MatchPattern);
+                        continue;
+                    }
+                    None if key.as_str() == "request_size" => {
+                        results.push(FormatText::RequestSize(None));
+                        continue;
+                    }
+                    None => {
+                        results.push(FormatText::Str(m.as_str().to_owned()));
+                        continue;
+                    }
+                };
+
+                results.push(match kind {
                     "a" => {
                         if key.as_str() == "r" {
                             FormatText::RealIpRemoteAddr
@@ -412,6 +604,7 @@ impl Format {
                     "U" => FormatText::UrlPath,
                     "T" => FormatText::Time,
                     "D" => FormatText::TimeMillis,
+                    "X" => FormatText::TimedOut,
                     _ => FormatText::Str(m.as_str().to_owned()),
                 });
             }
@@ -422,6 +615,45 @@ impl Format {
 
         Format(results)
     }
+
+    /// Renders the fields of a JSON logger as a JSON object.
+    fn render_json(&self, size: usize, entry_time: OffsetDateTime) -> serde_json::Value {
+        let mut fields = serde_json::Map::new();
+        let mut units = self.0.iter().peekable();
+
+        while let Some(unit) = units.next() {
+            let (key, numeric) = match unit {
+                FormatText::Field { key, numeric } => (key, *numeric),
+                _ => continue,
+            };
+
+            let mut value = String::new();
+            let mut count = 0;
+
+            while let Some(unit) =
+                units.next_if(|unit| !matches!(unit, FormatText::Field { .. }))
+            {
+                let render = |fmt: &mut fmt::Formatter<'_>| unit.render(fmt, size, entry_time);
+                value.push_str(&FormatDisplay(&render).to_string());
+                count += 1;
+            }
+
+            let value = if count == 1 && value == "-" {
+                serde_json::Value::Null
+            } else if numeric {
+                match value.parse::<serde_json::Number>() {
+                    Ok(num) => serde_json::Value::Number(num),
+                    Err(_) => serde_json::Value::String(value),
+                }
+            } else {
+                serde_json::Value::String(value)
+            };
+
+            fields.insert(key.clone(), value);
+        }
+
+        serde_json::Value::Object(fields)
+    }
 }
 
 /// A string of text to be logged.
@@ -441,6 +673,11 @@ enum FormatText {
     RemoteAddr,
     RealIpRemoteAddr,
     UrlPath,
+    TimedOut,
+    RequestId,
+    MatchPattern,
+    RequestSize(Option<Rc<Cell<u64>>>),
+    Field { key: String, numeric: bool },
     RequestHeader(HeaderName),
     ResponseHeader(HeaderName),
     EnvironHeader(String),
@@ -475,6 +712,8 @@ impl FormatText {
             FormatText::Str(ref string) => fmt.write_str(string),
             FormatText::Percent => "%".fmt(fmt),
             FormatText::ResponseSize => size.fmt(fmt),
+            FormatText::RequestSize(Some(ref counter)) => counter.get().fmt(fmt),
+            FormatText::RequestSize(None) => "-".fmt(fmt),
             FormatText::Time => {
                 let rt = OffsetDateTime::now_utc() - entry_time;
                 let rt = rt.as_seconds_f64();
@@ -517,6 +756,29 @@ impl FormatText {
         }
     }
 
+    fn render_service_response<B>(&mut self, res: &ServiceResponse<B>) {
+        match self {
+            FormatText::TimedOut => {
+                let timed_out = res.request().extensions().contains::<TimedOut>();
+                *self = FormatText::Str(if timed_out { "timeout" } else { "-" }.to_owned())
+            }
+            FormatText::RequestId => {
+                let extensions = res.request().extensions();
+                *self = match extensions.get::<RequestId>() {
+                    Some(request_id) => FormatText::Str(request_id.to_string()),
+                    None => FormatText::Str("-".to_owned()),
+                }
+            }
+            FormatText::MatchPattern => {
+                *self = match res.request().match_pattern() {
+                    Some(pattern) => FormatText::Str(pattern),
+                    None => FormatText::Str("-".to_owned()),
+                }
+            }
+            _ => self.render_response(res.response()),
+        }
+    }
+
     fn render_request(&mut self, now: OffsetDateTime, req: &ServiceRequest) {
         match self {
             FormatText::RequestLine => {
@@ -803,6 +1065,161 @@ mod tests {
         assert!(s.contains("192.0.2.60"));
     }
 
+    #[actix_rt::test]
+    async fn test_timed_out() {
+        let mut format = Format::new("%s %X");
+
+        let req = TestRequest::default().to_http_request();
+        req.extensions_mut().insert(TimedOut {
+            duration: std::time::Duration::from_secs(1),
+        });
+        let res = ServiceResponse::new(req, HttpResponse::ServiceUnavailable().finish());
+        for unit in &mut format.0 {
+            unit.render_service_response(&res);
+        }
+
+        let entry_time = OffsetDateTime::now_utc();
+        let render = |fmt: &mut fmt::Formatter<'_>| {
+            for unit in &format.0 {
+                unit.render(fmt, 1024, entry_time)?;
+            }
+            Ok(())
+        };
+        let s = format!("{}", FormatDisplay(&render));
+        assert_eq!(s, "503 timeout");
+    }
+
+    #[actix_rt::test]
+    async fn test_request_id() {
+        let mut format = Format::new("%{request_id} %{other} %s");
+
+        let req = TestRequest::default().to_http_request();
+        req.extensions_mut().insert(RequestId::new(
+            HeaderName::from_static("x-request-id"),
+            "abc-123".to_owned(),
+        ));
+        let res = ServiceResponse::new(req, HttpResponse::Ok().finish());
+        for unit in &mut format.0 {
+            unit.render_service_response(&res);
+        }
+
+        let entry_time = OffsetDateTime::now_utc();
+        let render = |fmt: &mut fmt::Formatter<'_>| {
+            for unit in &format.0 {
+                unit.render(fmt, 1024, entry_time)?;
+            }
+            Ok(())
+        };
+        let s = format!("{}", FormatDisplay(&render));
+        assert_eq!(s, "abc-123 %{other} 200");
+    }
+
+    #[actix_rt::test]
+    async fn test_json() {
+        let logger = Logger::json()
+            .field("status", "%s (%{X-Test}o)")
+            .field("accept", "%{Accept}i")
+            .field("content_type", "%{Content-Type}i");
+        let mut format = logger.0.format.clone();
+
+        let req = TestRequest::default()
+            .insert_header((header::USER_AGENT, "ACTIX-WEB"))
+            .insert_header((header::CONTENT_TYPE, "text/plain"))
+            .to_srv_request();
+
+        let now = OffsetDateTime::now_utc();
+        for unit in &mut format.0 {
+            unit.render_request(now, &req);
+        }
+
+        let res = req.into_response(
+            HttpResponse::Created()
+                .insert_header(("X-Test", "ttt"))
+                .finish(),
+        );
+        for unit in &mut format.0 {
+            unit.render_service_response(&res);
+        }
+
+        let json = format.render_json(1024, now);
+        assert_eq!(json["request"], "GET / HTTP/1.1");
+        assert_eq!(json["match_pattern"], serde_json::Value::Null);
+        assert_eq!(json["response_size"], 1024);
+        assert_eq!(json["user_agent"], "ACTIX-WEB");
+        assert_eq!(json["referer"], serde_json::Value::Null);
+        assert!(json["duration"].is_f64());
+        assert_eq!(json["status"], "201 (ttt)");
+        assert_eq!(json["accept"], serde_json::Value::Null);
+        assert_eq!(json["content_type"], "text/plain");
+        assert_eq!(json.as_object().unwrap().len(), 11);
+    }
+
+    #[actix_rt::test]
+    async fn test_json_in_middleware() {
+        let srv = test::init_service(crate::App::new().wrap(Logger::json()).route(
+            "/user/{id}",
+            crate::web::post().to(|body: Bytes| HttpResponse::Ok().body(body)),
+        ))
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/user/1")
+            .set_payload("hello")
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let lines = capture_logs(move || async move { test::read_body(res).await }).await;
+        assert_eq!(lines.len(), 1);
+
+        let json: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(json["request_size"], 5);
+        assert_eq!(json["match_pattern"], "/user/{id}");
+        assert_eq!(json["response_size"], 5);
+    }
+
+    thread_local! {
+        static CAPTURED: std::cell::RefCell<Option<Vec<String>>> = Default::default();
+    }
+
+    /// Logger that records messages of the `Logger` middleware, for the current thread only.
+    struct CaptureLogger;
+
+    impl log::Log for CaptureLogger {
+        fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+            metadata.target() == "actix_web::middleware::logger"
+        }
+
+        fn log(&self, record: &log::Record<'_>) {
+            if self.enabled(record.metadata()) {
+                CAPTURED.with(|captured| {
+                    if let Some(lines) = &mut *captured.borrow_mut() {
+                        lines.push(record.args().to_string());
+                    }
+                });
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    /// Runs `f`, returning the messages logged by the `Logger` middleware on the current thread.
+    async fn capture_logs<F, Fut>(f: F) -> Vec<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future,
+    {
+        static INIT: std::sync::Once = std::sync::Once::new();
+        INIT.call_once(|| {
+            log::set_logger(&CaptureLogger).unwrap();
+            log::set_max_level(log::LevelFilter::Info);
+        });
+
+        CAPTURED.with(|captured| *captured.borrow_mut() = Some(Vec::new()));
+        f().await;
+        CAPTURED.with(|captured| captured.borrow_mut().take().unwrap())
+    }
+
     #[actix_rt::test]
     async fn test_custom_closure_log() {
         let mut logger = Logger::new("test %{CUSTOM}xi")
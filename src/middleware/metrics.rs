@@ -0,0 +1,579 @@
+//! For middleware documentation, see [`Metrics`].
+
+use std::{
+    fmt::{self, Write as _},
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use actix_http::ConnectionMetrics;
+use actix_utils::future::{ready, Ready};
+use ahash::AHashMap;
+use futures_core::ready;
+
+use crate::{
+    dev::{fn_service, AppService, HttpServiceFactory, Service, Transform},
+    guard,
+    http::{header, Method, StatusCode},
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpResponse, Resource, Route,
+};
+
+/// Default upper bounds, in seconds, of the request duration histogram buckets.
+const DEFAULT_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Content type of the Prometheus text exposition format.
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Middleware for collecting request metrics in the Prometheus format.
+///
+/// The following metrics are collected, labelled by request method, matched resource pattern
+/// (see [`HttpRequest::match_pattern`](crate::HttpRequest::match_pattern)) and status class such
+/// as `2xx`:
+///
+/// - `http_requests_total`: counter of handled requests;
+/// - `http_request_duration_seconds`: histogram of the time taken to produce responses, not
+///   including streaming their bodies.
+///
+/// The number of requests being handled is reported by the `http_requests_in_flight` gauge,
+/// labelled by method only. Requests that match no resource have an empty `route` label, and
+/// non-standard methods are reported as `OTHER`.
+///
+/// Rendered metrics also include the process-wide [`ConnectionMetrics`] of actix-http:
+/// `http_connections_open`, `http_connections_total` and `http_connection_requests_total`,
+/// labelled by protocol, and `http_keep_alive_requests_total`.
+///
+/// Clones of a `Metrics` share the same counters, so create the middleware outside of the
+/// `HttpServer` app factory and clone it into each app to aggregate metrics from all workers.
+/// The metrics are served by the [`MetricsExporter`] service returned by
+/// [`exporter`](Self::exporter), or can be rendered with [`render`](Self::render).
+///
+/// # Examples
+/// ```
+/// use actix_web::{middleware::Metrics, web, App, HttpResponse, HttpServer};
+///
+/// # fn run() -> std::io::Result<()> {
+/// let metrics = Metrics::new();
+///
+/// let server = HttpServer::new(move || {
+///     App::new()
+///         .wrap(metrics.clone())
+///         .service(metrics.exporter())
+///         .route("/user/{id}", web::get().to(HttpResponse::Ok))
+/// });
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Metrics {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    buckets: Vec<f64>,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    in_flight: AHashMap<&'static str, u64>,
+    requests: AHashMap<Labels, Histogram>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Labels {
+    method: &'static str,
+    route: String,
+    status: &'static str,
+}
+
+struct Histogram {
+    /// Number of observations in each bucket, not including the smaller buckets.
+    buckets: Vec<u64>,
+    count: u64,
+    sum: f64,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics {
+            inner: Arc::new(Inner {
+                buckets: DEFAULT_BUCKETS.to_vec(),
+                state: Mutex::new(State::default()),
+            }),
+        }
+    }
+}
+
+impl Metrics {
+    /// Constructs a `Metrics` middleware with the default histogram buckets.
+    pub fn new() -> Metrics {
+        Metrics::default()
+    }
+
+    /// Sets the upper bounds, in seconds, of the request duration histogram buckets.
+    ///
+    /// The default buckets range from 5 milliseconds to 10 seconds.
+    ///
+    /// # Panics
+    /// Panics if a bound is not a finite number, or if called after the middleware was cloned.
+    pub fn buckets(mut self, buckets: impl Into<Vec<f64>>) -> Self {
+        let mut buckets = buckets.into();
+        assert!(
+            buckets.iter().all(|bound| bound.is_finite()),
+            "Histogram bucket bounds must be finite"
+        );
+
+        buckets.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        buckets.dedup();
+
+        Arc::get_mut(&mut self.inner)
+            .expect("Multiple `Inner` copies exist.")
+            .buckets = buckets;
+        self
+    }
+
+    /// Returns a service serving the metrics at `/metrics`.
+    pub fn exporter(&self) -> MetricsExporter {
+        MetricsExporter {
+            metrics: self.clone(),
+            path: "/metrics".to_owned(),
+        }
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        self.inner.render(&mut out).unwrap();
+        out
+    }
+}
+
+impl fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Metrics")
+            .field("buckets", &self.inner.buckets)
+            .finish()
+    }
+}
+
+impl Inner {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn observe(&self, labels: Labels, seconds: f64) {
+        let bucket = self
+            .buckets
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(self.buckets.len());
+
+        let mut state = self.state();
+        let histogram = state.requests.entry(labels).or_insert_with(|| Histogram {
+            buckets: vec![0; self.buckets.len() + 1],
+            count: 0,
+            sum: 0.0,
+        });
+
+        histogram.buckets[bucket] += 1;
+        histogram.count += 1;
+        histogram.sum += seconds;
+    }
+
+    fn render(&self, out: &mut String) -> fmt::Result {
+        let state = self.state();
+
+        let mut requests = state.requests.iter().collect::<Vec<_>>();
+        requests.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        describe(
+            out,
+            "http_requests_total",
+            "counter",
+            "Total number of HTTP requests handled.",
+        )?;
+        for (labels, histogram) in &requests {
+            writeln!(out, "http_requests_total{{{}}} {}", labels, histogram.count)?;
+        }
+
+        let mut in_flight = state.in_flight.iter().collect::<Vec<_>>();
+        in_flight.sort();
+
+        describe(
+            out,
+            "http_requests_in_flight",
+            "gauge",
+            "Number of HTTP requests being handled.",
+        )?;
+        for (method, count) in in_flight {
+            writeln!(
+                out,
+                "http_requests_in_flight{{method=\"{}\"}} {}",
+                method, count
+            )?;
+        }
+
+        describe(
+            out,
+            "http_request_duration_seconds",
+            "histogram",
+            "Time taken to produce HTTP responses, in seconds.",
+        )?;
+        for (labels, histogram) in &requests {
+            let mut cumulative = 0;
+
+            for (idx, count) in histogram.buckets.iter().enumerate() {
+                cumulative += count;
+
+                match self.buckets.get(idx) {
+                    Some(bound) => writeln!(
+                        out,
+                        "http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                        labels, bound, cumulative
+                    )?,
+                    None => writeln!(
+                        out,
+                        "http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                        labels, cumulative
+                    )?,
+                }
+            }
+
+            writeln!(
+                out,
+                "http_request_duration_seconds_sum{{{}}} {}",
+                labels, histogram.sum
+            )?;
+            writeln!(
+                out,
+                "http_request_duration_seconds_count{{{}}} {}",
+                labels, histogram.count
+            )?;
+        }
+
+        drop(state);
+
+        let conns = ConnectionMetrics::snapshot();
+        let protocols = [
+            ("http1", conns.h1_open, conns.h1_accepted, conns.h1_requests),
+            ("http2", conns.h2_open, conns.h2_accepted, conns.h2_requests),
+        ];
+
+        describe(
+            out,
+            "http_connections_open",
+            "gauge",
+            "Number of open HTTP connections.",
+        )?;
+        for (protocol, open, _, _) in &protocols {
+            writeln!(
+                out,
+                "http_connections_open{{protocol=\"{}\"}} {}",
+                protocol, open
+            )?;
+        }
+
+        describe(
+            out,
+            "http_connections_total",
+            "counter",
+            "Total number of HTTP connections accepted.",
+        )?;
+        for (protocol, _, accepted, _) in &protocols {
+            writeln!(
+                out,
+                "http_connections_total{{protocol=\"{}\"}} {}",
+                protocol, accepted
+            )?;
+        }
+
+        describe(
+            out,
+            "http_connection_requests_total",
+            "counter",
+            "Total number of requests received on HTTP connections.",
+        )?;
+        for (protocol, _, _, requests) in &protocols {
+            writeln!(
+                out,
+                "http_connection_requests_total{{protocol=\"{}\"}} {}",
+                protocol, requests
+            )?;
+        }
+
+        describe(
+            out,
+            "http_keep_alive_requests_total",
+            "counter",
+            "Total number of HTTP/1.1 requests received on a reused connection.",
+        )?;
+        writeln!(
+            out,
+            "http_keep_alive_requests_total {}",
+            conns.keep_alive_requests
+        )
+    }
+}
+
+/// Writes the `HELP` and `TYPE` lines of a metric.
+fn describe(out: &mut String, name: &str, kind: &str, help: &str) -> fmt::Result {
+    writeln!(out, "# HELP {} {}", name, help)?;
+    writeln!(out, "# TYPE {} {}", name, kind)
+}
+
+impl fmt::Display for Labels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "method=\"{}\",route=\"", self.method)?;
+
+        for ch in self.route.chars() {
+            match ch {
+                '\\' => f.write_str("\\\\")?,
+                '"' => f.write_str("\\\"")?,
+                '\n' => f.write_str("\\n")?,
+                ch => f.write_char(ch)?,
+            }
+        }
+
+        write!(f, "\",status=\"{}\"", self.status)
+    }
+}
+
+/// Returns the label of a method, grouping non-standard methods to bound label cardinality.
+fn method_label(method: &Method) -> &'static str {
+    match *method {
+        Method::GET => "GET",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::DELETE => "DELETE",
+        Method::HEAD => "HEAD",
+        Method::OPTIONS => "OPTIONS",
+        Method::CONNECT => "CONNECT",
+        Method::PATCH => "PATCH",
+        Method::TRACE => "TRACE",
+        _ => "OTHER",
+    }
+}
+
+/// Returns the class of a status code, such as `2xx`.
+fn status_label(status: StatusCode) -> &'static str {
+    match status.as_u16() / 100 {
+        1 => "1xx",
+        2 => "2xx",
+        3 => "3xx",
+        4 => "4xx",
+        _ => "5xx",
+    }
+}
+
+/// Service serving the metrics collected by a [`Metrics`] middleware.
+///
+/// Responds to `GET` requests at `/metrics`, or at the path set with [`path`](Self::path).
+#[derive(Debug, Clone)]
+pub struct MetricsExporter {
+    metrics: Metrics,
+    path: String,
+}
+
+impl MetricsExporter {
+    /// Sets the path at which the metrics are served.
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+}
+
+impl HttpServiceFactory for MetricsExporter {
+    fn register(self, config: &mut AppService) {
+        let metrics = self.metrics;
+
+        let serve = fn_service(move |req: ServiceRequest| {
+            let res = HttpResponse::Ok()
+                .insert_header((header::CONTENT_TYPE, CONTENT_TYPE))
+                .body(metrics.render());
+            ready(Ok::<_, Error>(req.into_response(res)))
+        });
+
+        let route = Route::new().guard(guard::Get()).service(serve);
+
+        HttpServiceFactory::register(Resource::new(self.path).route(route), config)
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Metrics
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = MetricsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(MetricsMiddleware {
+            service,
+            inner: Arc::clone(&self.inner),
+        }))
+    }
+}
+
+pub struct MetricsMiddleware<S> {
+    service: S,
+    inner: Arc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for MetricsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = MetricsFuture<S, B>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let in_flight = InFlight::new(Arc::clone(&self.inner), method_label(req.method()));
+
+        MetricsFuture {
+            fut: self.service.call(req),
+            in_flight: Some(in_flight),
+            start: Instant::now(),
+            _body: PhantomData,
+        }
+    }
+}
+
+/// Counts a request as in flight until dropped.
+struct InFlight {
+    inner: Arc<Inner>,
+    method: &'static str,
+}
+
+impl InFlight {
+    fn new(inner: Arc<Inner>, method: &'static str) -> Self {
+        *inner.state().in_flight.entry(method).or_insert(0) += 1;
+        InFlight { inner, method }
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if let Some(count) = self.inner.state().in_flight.get_mut(self.method) {
+            *count -= 1;
+        }
+    }
+}
+
+#[pin_project::pin_project]
+pub struct MetricsFuture<S: Service<ServiceRequest>, B> {
+    #[pin]
+    fut: S::Future,
+    in_flight: Option<InFlight>,
+    start: Instant,
+    _body: PhantomData<B>,
+}
+
+impl<S, B> Future for MetricsFuture<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Output = <S::Future as Future>::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = ready!(this.fut.poll(cx));
+
+        let in_flight = this.in_flight.take().unwrap();
+        let seconds = this.start.elapsed().as_secs_f64();
+
+        let (route, status) = match res {
+            Ok(ref res) => (res.request().match_pattern(), res.status()),
+            Err(ref err) => (None, err.as_response_error().status_code()),
+        };
+
+        let labels = Labels {
+            method: in_flight.method,
+            route: route.unwrap_or_default(),
+            status: status_label(status),
+        };
+
+        in_flight.inner.observe(labels, seconds);
+
+        Poll::Ready(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    #[actix_rt::test]
+    async fn collects_request_metrics() {
+        let metrics = Metrics::new().buckets(vec![0.5, 0.1]);
+
+        let srv = test::init_service(
+            App::new()
+                .wrap(metrics.clone())
+                .service(metrics.exporter())
+                .route("/user/{id}", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        for uri in &["/user/1", "/user/2", "/missing"] {
+            let req = TestRequest::with_uri(uri).to_request();
+            test::call_service(&srv, req).await;
+        }
+
+        let req = TestRequest::with_uri("/metrics").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            CONTENT_TYPE
+        );
+
+        let body = test::read_body(res).await;
+        let body = std::str::from_utf8(&body).unwrap();
+
+        let labels = r#"method="GET",route="/user/{id}",status="2xx""#;
+        assert!(body.contains(&format!("http_requests_total{{{}}} 2\n", labels)));
+        assert!(body.contains(r#"http_requests_total{method="GET",route="",status="4xx"} 1"#));
+        assert!(body.contains(&format!(
+            "http_request_duration_seconds_bucket{{{},le=\"0.1\"}} 2\n",
+            labels
+        )));
+        assert!(body.contains(&format!(
+            "http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} 2\n",
+            labels
+        )));
+        assert!(body.contains(r#"http_requests_in_flight{method="GET"} 1"#));
+        assert!(body.contains("# TYPE http_connections_open gauge\n"));
+        assert!(body.contains("http_keep_alive_requests_total "));
+    }
+
+    #[test]
+    fn escapes_labels() {
+        let labels = Labels {
+            method: "GET",
+            route: "/a\"b\\c".to_owned(),
+            status: "2xx",
+        };
+
+        assert_eq!(
+            labels.to_string(),
+            r#"method="GET",route="/a\"b\\c",status="2xx""#
+        );
+    }
+}
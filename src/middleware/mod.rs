@@ -1,27 +1,57 @@
 //! Commonly used middleware.
 
+pub(crate) mod body_limit;
+mod cache;
 mod compat;
 mod condition;
+pub(crate) mod conditional_get;
+mod cors;
+#[cfg(feature = "cookies")]
+mod csrf;
 mod default_headers;
 mod err_handlers;
 mod logger;
+mod metrics;
 mod normalize;
+mod rate_limit;
+mod request_id;
+mod security_headers;
+mod timeout;
 
+pub use self::body_limit::BodyLimit;
+pub use self::cache::Cache;
 pub use self::compat::Compat;
 pub use self::condition::Condition;
+pub use self::conditional_get::ConditionalGet;
+pub use self::cors::{Cors, CorsError};
+#[cfg(feature = "cookies")]
+pub use self::csrf::{Csrf, CsrfError};
 pub use self::default_headers::DefaultHeaders;
 pub use self::err_handlers::{ErrorHandlerResponse, ErrorHandlers};
 pub use self::logger::Logger;
+pub use self::metrics::{Metrics, MetricsExporter};
 pub use self::normalize::{NormalizePath, TrailingSlash};
+pub use self::rate_limit::{
+    MemoryRateLimitStore, Quota, RateLimit, RateLimitAlgorithm, RateLimitStatus, RateLimitStore,
+};
+pub use self::request_id::RequestId;
+pub use self::security_headers::SecurityHeaders;
+pub use self::timeout::{TimedOut, Timeout};
 
 #[cfg(feature = "__compress")]
 mod compress;
+#[cfg(feature = "__compress")]
+mod decompress;
 
 #[cfg(feature = "__compress")]
 pub use self::compress::Compress;
+#[cfg(feature = "__compress")]
+pub use self::decompress::Decompress;
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use crate::{http::StatusCode, App};
 
     use super::*;
@@ -38,9 +68,26 @@ mod tests {
                 Ok(ErrorHandlerResponse::Response(res))
             }))
             .wrap(Logger::default())
-            .wrap(NormalizePath::new(TrailingSlash::Trim));
+            .wrap(NormalizePath::new(TrailingSlash::Trim))
+            .wrap(Cors::new().allow_any_origin())
+            .wrap(Timeout::new(Duration::from_secs(1)))
+            .wrap(RequestId::new())
+            .wrap(SecurityHeaders::new())
+            .wrap(ConditionalGet::new())
+            .wrap(Cache::new())
+            .wrap(BodyLimit::new(1024))
+            .wrap(Metrics::new())
+            .wrap(RateLimit::new(Quota::token_bucket(
+                10,
+                Duration::from_secs(1),
+            )));
 
         let _ = App::new()
+            .wrap(RateLimit::new(Quota::sliding_window(
+                10,
+                Duration::from_secs(1),
+            )))
+            .wrap(Cors::new().allow_any_origin())
             .wrap(NormalizePath::new(TrailingSlash::Trim))
             .wrap(Logger::default())
             .wrap(ErrorHandlers::new().handler(StatusCode::FORBIDDEN, |res| {
@@ -56,6 +103,10 @@ mod tests {
             let _ = App::new().wrap(Logger::default()).wrap(Compress::default());
             let _ = App::new().wrap(Compat::new(Compress::default()));
             let _ = App::new().wrap(Condition::new(true, Compat::new(Compress::default())));
+            let _ = App::new()
+                .wrap(BodyLimit::new(1024))
+                .wrap(Decompress::new())
+                .wrap(Compress::default());
         }
     }
 }
@@ -0,0 +1,702 @@
+//! For middleware documentation, see [`RateLimit`].
+
+use std::{
+    convert::TryFrom,
+    error::Error as StdError,
+    fmt,
+    net::SocketAddr,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use actix_utils::future::{ready, Ready};
+use ahash::AHashMap;
+use futures_core::future::LocalBoxFuture;
+
+use crate::{
+    body::{Body, MessageBody},
+    dev::{Service, Transform},
+    http::{
+        header::{self, HeaderMap, HeaderName, HeaderValue},
+        Error as HttpError,
+    },
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpResponse,
+};
+
+type KeyFn = dyn Fn(&ServiceRequest) -> Option<String> + Send + Sync;
+
+/// Middleware for limiting the rate of requests from each client.
+///
+/// Requests are grouped by a key identifying the client, by default the IP address of the peer
+/// connecting to the server. Behind a reverse proxy, the client's address can be used instead with
+/// [`key_real_ip`](Self::key_real_ip). Each key may make requests at the rate allowed by a [`Quota`]; requests over the limit are answered
+/// with a *429 Too Many Requests* response and never reach the wrapped service. Requests for
+/// which no key can be determined are not limited.
+///
+/// All responses include `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset` headers
+/// describing the client's quota. Rejected responses also include a `Retry-After` header.
+///
+/// Request counts are kept in a [`RateLimitStore`], by default a [`MemoryRateLimitStore`]. Clones
+/// of a `RateLimit` share the same store, so create the middleware outside of the `HttpServer`
+/// app factory and clone it into each app to enforce a single limit across all workers.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use actix_web::{middleware::{Quota, RateLimit}, web, App, HttpResponse, HttpServer};
+///
+/// # fn run() -> std::io::Result<()> {
+/// let rate_limit = RateLimit::new(Quota::token_bucket(20, Duration::from_secs(60)));
+///
+/// let server = HttpServer::new(move || {
+///     App::new()
+///         .wrap(rate_limit.clone())
+///         .route("/index.html", web::get().to(HttpResponse::Ok))
+/// });
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RateLimit {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    quota: Quota,
+    key: Box<KeyFn>,
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl RateLimit {
+    /// Constructs a `RateLimit` middleware limiting each peer IP address to `quota`.
+    pub fn new(quota: Quota) -> RateLimit {
+        RateLimit {
+            inner: Arc::new(Inner {
+                quota,
+                key: Box::new(|req| req.peer_addr().map(|addr| addr.ip().to_string())),
+                store: Arc::new(MemoryRateLimitStore::new()),
+            }),
+        }
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Arc::get_mut(&mut self.inner).expect("Multiple `Inner` copies exist.")
+    }
+
+    /// Uses the "real IP" remote address of the client as the key identifying it.
+    ///
+    /// The address is read from the `Forwarded` or `X-Forwarded-For` header, falling back to the
+    /// peer address; see
+    /// [`ConnectionInfo::realip_remote_addr`](crate::dev::ConnectionInfo::realip_remote_addr).
+    ///
+    /// # Security
+    /// Only use this key if all requests come through a trusted proxy setting these headers.
+    /// Otherwise, clients can evade the limit by sending a different address in each request.
+    pub fn key_real_ip(mut self) -> Self {
+        self.inner_mut().key =
+            Box::new(|req| req.connection_info().realip_remote_addr().map(strip_port));
+        self
+    }
+
+    /// Uses the value of a request header as the key identifying the client.
+    ///
+    /// Requests without the header, or with a value that is not visible ASCII, are not limited.
+    ///
+    /// # Panics
+    /// Panics if the header name is invalid.
+    pub fn key_header<H>(mut self, header: H) -> Self
+    where
+        HeaderName: TryFrom<H>,
+        <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+    {
+        let header = match HeaderName::try_from(header) {
+            Ok(header) => header,
+            Err(_) => panic!("Can not create header name"),
+        };
+
+        self.inner_mut().key = Box::new(move |req| {
+            req.headers()
+                .get(&header)
+                .and_then(|val| val.to_str().ok())
+                .map(str::to_owned)
+        });
+
+        self
+    }
+
+    /// Uses a function of the request as the key identifying the client.
+    ///
+    /// Requests for which the function returns `None` are not limited.
+    pub fn key_fn<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&ServiceRequest) -> Option<String> + Send + Sync + 'static,
+    {
+        self.inner_mut().key = Box::new(f);
+        self
+    }
+
+    /// Sets the store keeping track of requests, replacing the default in-memory store.
+    pub fn store<S>(mut self, store: S) -> Self
+    where
+        S: RateLimitStore,
+    {
+        self.inner_mut().store = Arc::new(store);
+        self
+    }
+}
+
+impl fmt::Debug for RateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimit")
+            .field("quota", &self.inner.quota)
+            .finish()
+    }
+}
+
+/// Returns the IP address of a `host:port` address, or the address unchanged if it has no port.
+fn strip_port(addr: &str) -> String {
+    match addr.parse::<SocketAddr>() {
+        Ok(addr) => addr.ip().to_string(),
+        Err(_) => addr.to_owned(),
+    }
+}
+
+/// Rate at which a client is allowed to make requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    algorithm: RateLimitAlgorithm,
+    limit: u32,
+    period: Duration,
+}
+
+/// Algorithm used to enforce a [`Quota`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitAlgorithm {
+    /// Requests take tokens from a bucket holding up to `limit` tokens, which is refilled
+    /// continuously at `limit` tokens per `period`. Allows bursts of up to `limit` requests.
+    TokenBucket,
+
+    /// Up to `limit` requests are allowed in any window of length `period`. The count of the
+    /// sliding window is estimated from the counts of the current and previous fixed windows.
+    SlidingWindow,
+}
+
+impl Quota {
+    /// Constructs a quota allowing bursts of up to `burst` requests, replenished at a rate of
+    /// `burst` requests per `period`.
+    ///
+    /// # Panics
+    /// Panics if `burst` or `period` is zero.
+    pub fn token_bucket(burst: u32, period: Duration) -> Quota {
+        Quota::new(RateLimitAlgorithm::TokenBucket, burst, period)
+    }
+
+    /// Constructs a quota allowing up to `limit` requests in any window of length `window`.
+    ///
+    /// # Panics
+    /// Panics if `limit` or `window` is zero.
+    pub fn sliding_window(limit: u32, window: Duration) -> Quota {
+        Quota::new(RateLimitAlgorithm::SlidingWindow, limit, window)
+    }
+
+    fn new(algorithm: RateLimitAlgorithm, limit: u32, period: Duration) -> Quota {
+        assert!(
+            limit > 0,
+            "rate limit quota must allow at least one request"
+        );
+        assert!(
+            period > Duration::from_secs(0),
+            "rate limit quota period must not be zero"
+        );
+
+        Quota {
+            algorithm,
+            limit,
+            period,
+        }
+    }
+
+    /// Returns the algorithm used to enforce this quota.
+    pub fn algorithm(&self) -> RateLimitAlgorithm {
+        self.algorithm
+    }
+
+    /// Returns the number of requests allowed per period.
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    /// Returns the period over which requests are counted.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+}
+
+/// State of a client's quota after a request, as reported by a [`RateLimitStore`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitStatus {
+    /// Whether the request is allowed.
+    pub allowed: bool,
+
+    /// Number of requests allowed per period.
+    pub limit: u32,
+
+    /// Number of requests that can be made right now.
+    pub remaining: u32,
+
+    /// Time until the quota is fully replenished.
+    pub reset: Duration,
+
+    /// Time until the next request is allowed, for rejected requests.
+    pub retry_after: Option<Duration>,
+}
+
+/// Store keeping track of requests made by each client of a [`RateLimit`] middleware.
+///
+/// A store is shared by all workers, so implementations must synchronize access to their state.
+/// Implementations backed by external services can perform I/O in the returned future.
+pub trait RateLimitStore: Send + Sync + 'static {
+    /// Records a request made by the client identified by `key` and reports whether it is
+    /// allowed by `quota`.
+    ///
+    /// Rejected requests must not be counted against the client's quota.
+    fn acquire(
+        &self,
+        key: String,
+        quota: Quota,
+    ) -> LocalBoxFuture<'static, Result<RateLimitStatus, Error>>;
+}
+
+/// In-memory [`RateLimitStore`].
+///
+/// Clients that have not made requests for a full quota period are evicted from time to time, so
+/// memory use is proportional to the number of recently active clients.
+#[derive(Debug, Default)]
+pub struct MemoryRateLimitStore {
+    state: Mutex<MemoryState>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryState {
+    entries: AHashMap<String, Entry>,
+
+    /// Number of entries at which expired entries are evicted next.
+    next_sweep: usize,
+}
+
+#[derive(Debug)]
+enum Entry {
+    TokenBucket {
+        tokens: f64,
+        updated: Instant,
+    },
+    SlidingWindow {
+        window_start: Instant,
+        previous: u32,
+        current: u32,
+    },
+}
+
+const MIN_SWEEP_ENTRIES: usize = 1024;
+
+impl MemoryRateLimitStore {
+    /// Constructs an empty store.
+    pub fn new() -> MemoryRateLimitStore {
+        MemoryRateLimitStore::default()
+    }
+
+    fn acquire_at(&self, key: String, quota: Quota, now: Instant) -> RateLimitStatus {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+
+        if state.entries.len() >= state.next_sweep {
+            state
+                .entries
+                .retain(|_, entry| !entry.is_expired(&quota, now));
+            state.next_sweep = (state.entries.len() * 2).max(MIN_SWEEP_ENTRIES);
+        }
+
+        let entry = state
+            .entries
+            .entry(key)
+            .or_insert_with(|| Entry::new(&quota, now));
+
+        entry.acquire(&quota, now)
+    }
+}
+
+impl RateLimitStore for MemoryRateLimitStore {
+    fn acquire(
+        &self,
+        key: String,
+        quota: Quota,
+    ) -> LocalBoxFuture<'static, Result<RateLimitStatus, Error>> {
+        let status = self.acquire_at(key, quota, Instant::now());
+        Box::pin(ready(Ok(status)))
+    }
+}
+
+impl Entry {
+    fn new(quota: &Quota, now: Instant) -> Entry {
+        match quota.algorithm {
+            RateLimitAlgorithm::TokenBucket => Entry::TokenBucket {
+                tokens: f64::from(quota.limit),
+                updated: now,
+            },
+            RateLimitAlgorithm::SlidingWindow => Entry::SlidingWindow {
+                window_start: now,
+                previous: 0,
+                current: 0,
+            },
+        }
+    }
+
+    /// Returns `true` if the entry is indistinguishable from a new one.
+    fn is_expired(&self, quota: &Quota, now: Instant) -> bool {
+        match *self {
+            Entry::TokenBucket { updated, .. } => now.duration_since(updated) >= quota.period,
+            Entry::SlidingWindow { window_start, .. } => {
+                now.duration_since(window_start) >= quota.period * 2
+            }
+        }
+    }
+
+    fn acquire(&mut self, quota: &Quota, now: Instant) -> RateLimitStatus {
+        let limit = f64::from(quota.limit);
+        let period = quota.period.as_secs_f64();
+
+        match self {
+            Entry::TokenBucket { tokens, updated } => {
+                let rate = limit / period;
+                let elapsed = now.duration_since(*updated).as_secs_f64();
+
+                *tokens = (*tokens + elapsed * rate).min(limit);
+                *updated = now;
+
+                let allowed = *tokens >= 1.0;
+                if allowed {
+                    *tokens -= 1.0;
+                }
+
+                RateLimitStatus {
+                    allowed,
+                    limit: quota.limit,
+                    remaining: tokens.floor() as u32,
+                    reset: quota.period.mul_f64((limit - *tokens) / limit),
+                    retry_after: if allowed {
+                        None
+                    } else {
+                        Some(quota.period.mul_f64((1.0 - *tokens) / limit))
+                    },
+                }
+            }
+
+            Entry::SlidingWindow {
+                window_start,
+                previous,
+                current,
+            } => {
+                let windows = (now.duration_since(*window_start).as_secs_f64() / period) as u32;
+
+                if windows > 0 {
+                    *previous = if windows == 1 { *current } else { 0 };
+                    *current = 0;
+                    *window_start += quota.period * windows;
+                }
+
+                let elapsed = now.duration_since(*window_start);
+                let weight = 1.0 - elapsed.as_secs_f64() / period;
+                let estimate = f64::from(*previous) * weight + f64::from(*current);
+
+                let allowed = estimate + 1.0 <= limit;
+                if allowed {
+                    *current += 1;
+                }
+
+                let used = (f64::from(*previous) * weight + f64::from(*current)).ceil();
+                let window_end = quota.period.checked_sub(elapsed).unwrap_or_default();
+
+                let retry_after = if allowed {
+                    None
+                } else if *current < quota.limit && *previous > 0 {
+                    // wait until enough of the previous window has slid out
+                    let free = limit - 1.0 - f64::from(*current);
+                    let needed = quota.period.mul_f64(1.0 - free / f64::from(*previous));
+                    Some(needed.checked_sub(elapsed).unwrap_or_default())
+                } else {
+                    Some(window_end)
+                };
+
+                RateLimitStatus {
+                    allowed,
+                    limit: quota.limit,
+                    remaining: (limit - used).max(0.0) as u32,
+                    reset: window_end + quota.period,
+                    retry_after,
+                }
+            }
+        }
+    }
+}
+
+/// Sets the `RateLimit-*` headers describing `status`.
+fn set_status_headers(headers: &mut HeaderMap, status: &RateLimitStatus) {
+    headers.insert(
+        HeaderName::from_static("ratelimit-limit"),
+        HeaderValue::from(status.limit),
+    );
+    headers.insert(
+        HeaderName::from_static("ratelimit-remaining"),
+        HeaderValue::from(status.remaining),
+    );
+    headers.insert(
+        HeaderName::from_static("ratelimit-reset"),
+        HeaderValue::from(ceil_secs(status.reset)),
+    );
+}
+
+/// Returns the number of whole seconds in `dur`, rounded up.
+fn ceil_secs(dur: Duration) -> u64 {
+    dur.as_secs() + u64::from(dur.subsec_nanos() > 0)
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service: Rc::new(service),
+            inner: Arc::clone(&self.inner),
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<S>,
+    inner: Arc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        let key = match (self.inner.key)(&req) {
+            Some(key) => key,
+            None => {
+                let fut = service.call(req);
+                return Box::pin(async move {
+                    let res = fut.await?;
+                    Ok(res.map_body(|_, body| Body::from_message(body)))
+                });
+            }
+        };
+
+        let acquire = self.inner.store.acquire(key, self.inner.quota);
+
+        Box::pin(async move {
+            let status = acquire.await?;
+
+            if !status.allowed {
+                let retry_after = status.retry_after.map_or(1, ceil_secs).max(1);
+
+                let mut res = HttpResponse::TooManyRequests()
+                    .insert_header((header::RETRY_AFTER, retry_after))
+                    .body("Too many requests.");
+                set_status_headers(res.headers_mut(), &status);
+
+                return Ok(req.into_response(res));
+            }
+
+            let mut res = service.call(req).await?;
+            set_status_headers(res.headers_mut(), &status);
+
+            Ok(res.map_body(|_, body| Body::from_message(body)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    async fn init(
+        rate_limit: RateLimit,
+    ) -> impl Service<actix_http::Request, Response = ServiceResponse, Error = Error> {
+        test::init_service(
+            App::new()
+                .wrap(rate_limit)
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await
+    }
+
+    fn request(addr: &str) -> actix_http::Request {
+        TestRequest::default()
+            .peer_addr(addr.parse().unwrap())
+            .to_request()
+    }
+
+    #[actix_rt::test]
+    async fn limits_by_ip() {
+        let quota = Quota::token_bucket(2, Duration::from_secs(3600));
+        let srv = init(RateLimit::new(quota)).await;
+
+        for remaining in &["1", "0"] {
+            let res = test::call_service(&srv, request("127.0.0.1:8080")).await;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(res.headers().get("ratelimit-limit").unwrap(), "2");
+            assert_eq!(
+                res.headers().get("ratelimit-remaining").unwrap(),
+                *remaining
+            );
+        }
+
+        // the port is not part of the key
+        let res = test::call_service(&srv, request("127.0.0.1:9090")).await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers().get("ratelimit-remaining").unwrap(), "0");
+        assert_eq!(res.headers().get(header::RETRY_AFTER).unwrap(), "1800");
+        assert_eq!(test::read_body(res).await, "Too many requests.");
+
+        let res = test::call_service(&srv, request("10.0.0.1:8080")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // forwarding headers are ignored by default
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.1:8080".parse().unwrap())
+            .insert_header(("x-forwarded-for", "192.0.2.1"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.headers().get("ratelimit-remaining").unwrap(), "0");
+
+        // no key can be determined without a peer address
+        let req = TestRequest::default().to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key("ratelimit-limit"));
+    }
+
+    #[actix_rt::test]
+    async fn limits_by_real_ip() {
+        let quota = Quota::token_bucket(1, Duration::from_secs(3600));
+        let srv = init(RateLimit::new(quota).key_real_ip()).await;
+
+        for client in &["192.0.2.1", "192.0.2.2"] {
+            let req = TestRequest::default()
+                .peer_addr("10.0.0.1:8080".parse().unwrap())
+                .insert_header(("x-forwarded-for", *client))
+                .to_request();
+            let res = test::call_service(&srv, req).await;
+            assert_eq!(res.status(), StatusCode::OK);
+        }
+
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.1:8080".parse().unwrap())
+            .insert_header(("x-forwarded-for", "192.0.2.1"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // the peer address is used without forwarding headers
+        let res = test::call_service(&srv, request("10.0.0.1:9090")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let res = test::call_service(&srv, request("10.0.0.1:8080")).await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[actix_rt::test]
+    async fn shared_between_apps() {
+        let rate_limit = RateLimit::new(Quota::sliding_window(1, Duration::from_secs(60)))
+            .key_header("x-api-key");
+
+        let srv1 = init(rate_limit.clone()).await;
+        let srv2 = init(rate_limit).await;
+
+        let req = TestRequest::default()
+            .insert_header(("x-api-key", "abc"))
+            .to_request();
+        let res = test::call_service(&srv1, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::default()
+            .insert_header(("x-api-key", "abc"))
+            .to_request();
+        let res = test::call_service(&srv2, req).await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[test]
+    fn token_bucket_refills() {
+        let store = MemoryRateLimitStore::new();
+        let quota = Quota::token_bucket(2, Duration::from_secs(2));
+        let now = Instant::now();
+
+        assert!(store.acquire_at("a".to_owned(), quota, now).allowed);
+        assert!(store.acquire_at("a".to_owned(), quota, now).allowed);
+
+        let status = store.acquire_at("a".to_owned(), quota, now);
+        assert!(!status.allowed);
+        assert_eq!(status.retry_after, Some(Duration::from_secs(1)));
+        assert_eq!(status.reset, Duration::from_secs(2));
+
+        let status = store.acquire_at("a".to_owned(), quota, now + Duration::from_secs(1));
+        assert!(status.allowed);
+        assert_eq!(status.remaining, 0);
+    }
+
+    #[test]
+    fn sliding_window_slides() {
+        let store = MemoryRateLimitStore::new();
+        let quota = Quota::sliding_window(2, Duration::from_secs(10));
+        let now = Instant::now();
+
+        assert!(store.acquire_at("a".to_owned(), quota, now).allowed);
+        assert!(store.acquire_at("a".to_owned(), quota, now).allowed);
+
+        let status = store.acquire_at("a".to_owned(), quota, now + Duration::from_secs(9));
+        assert!(!status.allowed);
+        assert_eq!(status.retry_after, Some(Duration::from_secs(1)));
+
+        // half of the previous window still counts
+        let later = now + Duration::from_secs(15);
+        let status = store.acquire_at("a".to_owned(), quota, later);
+        assert!(status.allowed);
+        assert_eq!(status.remaining, 0);
+        assert!(!store.acquire_at("a".to_owned(), quota, later).allowed);
+
+        assert!(
+            store
+                .acquire_at("a".to_owned(), quota, now + Duration::from_secs(40))
+                .allowed
+        );
+    }
+}
@@ -0,0 +1,440 @@
+//! For middleware documentation, see [`RequestId`].
+
+use std::{
+    convert::TryFrom,
+    fmt::{self, Write as _},
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use actix_http::client::with_propagated_header;
+use actix_utils::future::{ready, Ready};
+use futures_core::ready;
+
+use crate::{
+    dev::{Service, Transform},
+    http::{
+        header::{HeaderName, HeaderValue},
+        Error as HttpError,
+    },
+    service::{ServiceRequest, ServiceResponse},
+    web, Error, HttpMessage as _,
+};
+
+/// Maximum length of request IDs accepted from clients.
+const MAX_INCOMING_LEN: usize = 128;
+
+/// Middleware for assigning an identifier to each request.
+///
+/// The ID is read from the `X-Request-Id` header of the request, or generated as a random UUID
+/// if the header is missing or invalid. Incoming IDs are only accepted if they are visible ASCII
+/// and at most 128 bytes long. The header name can be changed with [`header`](Self::header).
+///
+/// The ID is stored in the request extensions, from where it can be extracted by handlers with
+/// [`web::RequestId`], and set on the response unless the response already has the header.
+/// [`Logger`](super::Logger) reports it with the `%{request_id}` format unit.
+///
+/// Requests created with `awc` while the request is handled are sent with the ID in the same
+/// header, unless disabled with [`forward`](Self::forward). Requests created by tasks spawned
+/// from the handler do not get the ID.
+///
+/// # Examples
+/// ```
+/// use actix_web::{middleware, web, App, HttpResponse};
+///
+/// let app = App::new()
+///     .wrap(middleware::Logger::new(r#"%{request_id} "%r" %s"#))
+///     .wrap(middleware::RequestId::new().header("X-Correlation-Id"))
+///     .route(
+///         "/",
+///         web::get().to(|id: web::RequestId| HttpResponse::Ok().body(id.into_inner())),
+///     );
+/// ```
+#[derive(Clone)]
+pub struct RequestId {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    header: HeaderName,
+    trust_incoming: bool,
+    forward: bool,
+    generator: Box<dyn Fn() -> String>,
+}
+
+impl Default for RequestId {
+    fn default() -> Self {
+        RequestId {
+            inner: Rc::new(Inner {
+                header: HeaderName::from_static("x-request-id"),
+                trust_incoming: true,
+                forward: true,
+                generator: Box::new(generate_uuid),
+            }),
+        }
+    }
+}
+
+impl RequestId {
+    /// Constructs a `RequestId` middleware using the `X-Request-Id` header.
+    pub fn new() -> RequestId {
+        RequestId::default()
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("Multiple `Inner` copies exist.")
+    }
+
+    /// Sets the name of the header carrying request IDs.
+    ///
+    /// # Panics
+    /// Panics if the header name is invalid.
+    pub fn header<H>(mut self, header: H) -> Self
+    where
+        HeaderName: TryFrom<H>,
+        <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+    {
+        self.inner_mut().header = match HeaderName::try_from(header) {
+            Ok(header) => header,
+            Err(_) => panic!("Can not create header name"),
+        };
+        self
+    }
+
+    /// Sets whether IDs sent by clients are used, instead of always generating a new ID.
+    ///
+    /// Default is `true`. Disable it for services exposed to untrusted clients, which could
+    /// otherwise make their requests hard to tell apart in logs.
+    pub fn trust_incoming(mut self, trust: bool) -> Self {
+        self.inner_mut().trust_incoming = trust;
+        self
+    }
+
+    /// Sets whether the ID is sent on `awc` requests created while the request is handled.
+    ///
+    /// Default is `true`. Disable it if the app calls services that should not see the IDs.
+    pub fn forward(mut self, forward: bool) -> Self {
+        self.inner_mut().forward = forward;
+        self
+    }
+
+    /// Sets the function generating IDs for requests without one.
+    ///
+    /// Generated IDs that are not valid header values are replaced with a random UUID.
+    pub fn generator<F>(mut self, f: F) -> Self
+    where
+        F: Fn() -> String + 'static,
+    {
+        self.inner_mut().generator = Box::new(f);
+        self
+    }
+}
+
+impl fmt::Debug for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestId")
+            .field("header", &self.inner.header)
+            .field("trust_incoming", &self.inner.trust_incoming)
+            .field("forward", &self.inner.forward)
+            .finish()
+    }
+}
+
+impl Inner {
+    /// Returns the ID of `req`, generating one if it has none.
+    fn request_id(&self, req: &ServiceRequest) -> String {
+        if self.trust_incoming {
+            let incoming = req
+                .headers()
+                .get(&self.header)
+                .map(HeaderValue::as_bytes)
+                .filter(|id| {
+                    !id.is_empty()
+                        && id.len() <= MAX_INCOMING_LEN
+                        && id.iter().all(u8::is_ascii_graphic)
+                })
+                .and_then(|id| std::str::from_utf8(id).ok());
+
+            if let Some(id) = incoming {
+                return id.to_owned();
+            }
+        }
+
+        let id = (self.generator)();
+
+        if HeaderValue::from_str(&id).is_ok() {
+            id
+        } else {
+            log::warn!("Generated request ID {:?} is not a valid header value", id);
+            generate_uuid()
+        }
+    }
+}
+
+/// Generates a random (version 4) UUID in its hyphenated form.
+fn generate_uuid() -> String {
+    let mut bytes = rand::random::<[u8; 16]>();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let mut uuid = String::with_capacity(36);
+
+    for (idx, byte) in bytes.iter().enumerate() {
+        if matches!(idx, 4 | 6 | 8 | 10) {
+            uuid.push('-');
+        }
+
+        write!(uuid, "{:02x}", byte).unwrap();
+    }
+
+    uuid
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestId
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestIdMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdMiddleware {
+            service,
+            inner: Rc::clone(&self.inner),
+        }))
+    }
+}
+
+pub struct RequestIdMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = RequestIdFuture<S, B>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let id = self.inner.request_id(&req);
+        let request_id = web::RequestId::new(self.inner.header.clone(), id);
+
+        req.extensions_mut().insert(request_id.clone());
+
+        let forwarded = if self.inner.forward {
+            let id = HeaderValue::from_str(request_id.as_str()).unwrap();
+            Some((self.inner.header.clone(), id))
+        } else {
+            None
+        };
+
+        let fut = match forwarded {
+            Some((ref header, ref id)) => {
+                with_propagated_header(header.clone(), id.clone(), || self.service.call(req))
+            }
+            None => self.service.call(req),
+        };
+
+        RequestIdFuture {
+            fut,
+            request_id: Some(request_id),
+            forwarded,
+            _body: PhantomData,
+        }
+    }
+}
+
+#[pin_project::pin_project]
+pub struct RequestIdFuture<S: Service<ServiceRequest>, B> {
+    #[pin]
+    fut: S::Future,
+    request_id: Option<web::RequestId>,
+    forwarded: Option<(HeaderName, HeaderValue)>,
+    _body: PhantomData<B>,
+}
+
+impl<S, B> Future for RequestIdFuture<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Output = <S::Future as Future>::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let fut = this.fut;
+
+        let mut res = ready!(match this.forwarded {
+            Some((header, id)) => {
+                with_propagated_header(header.clone(), id.clone(), || fut.poll(cx))
+            }
+            None => fut.poll(cx),
+        })?;
+
+        let request_id = this.request_id.take().unwrap();
+        let header = request_id.header_name().clone();
+
+        // responses built by inner middleware, such as `Timeout`, may be given a new request
+        if !res.request().extensions().contains::<web::RequestId>() {
+            res.request().extensions_mut().insert(request_id.clone());
+        }
+
+        if !res.headers().contains_key(&header) {
+            let id = HeaderValue::try_from(request_id.into_inner()).unwrap();
+            res.headers_mut().insert(header, id);
+        }
+
+        Poll::Ready(Ok(res))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::StatusCode,
+        test::{self, TestRequest},
+        App, HttpResponse,
+    };
+
+    async fn init(
+        request_id: RequestId,
+    ) -> impl Service<actix_http::Request, Response = ServiceResponse, Error = Error> {
+        test::init_service(App::new().wrap(request_id).route(
+            "/",
+            web::get().to(|id: web::RequestId| HttpResponse::Ok().body(id.into_inner())),
+        ))
+        .await
+    }
+
+    #[actix_rt::test]
+    async fn generates_ids() {
+        let srv = init(RequestId::new()).await;
+
+        let req = TestRequest::default().to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let header = res.headers().get("x-request-id").unwrap().clone();
+        let id = header.to_str().unwrap();
+        assert_eq!(id.len(), 36);
+        assert_eq!(&id[14..15], "4");
+        assert_eq!(test::read_body(res).await, id.as_bytes());
+
+        let req = TestRequest::default().to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_ne!(res.headers().get("x-request-id").unwrap(), &header);
+    }
+
+    #[actix_rt::test]
+    async fn propagates_incoming_ids() {
+        let srv = init(RequestId::new().header("x-correlation-id")).await;
+
+        let req = TestRequest::default()
+            .insert_header(("x-correlation-id", "abc-123"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.headers().get("x-correlation-id").unwrap(), "abc-123");
+        assert!(!res.headers().contains_key("x-request-id"));
+        assert_eq!(test::read_body(res).await, "abc-123");
+
+        let long = "a".repeat(MAX_INCOMING_LEN + 1);
+        let req = TestRequest::default()
+            .insert_header(("x-correlation-id", long.as_str()))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_ne!(
+            res.headers().get("x-correlation-id").unwrap(),
+            long.as_str()
+        );
+
+        for invalid in &["abc 123", "abc\t123"] {
+            let req = TestRequest::default()
+                .insert_header(("x-correlation-id", *invalid))
+                .to_request();
+            let res = test::call_service(&srv, req).await;
+            assert_eq!(res.headers().get("x-correlation-id").unwrap().len(), 36);
+        }
+
+        let srv = init(
+            RequestId::new()
+                .trust_incoming(false)
+                .generator(|| "generated".to_owned()),
+        )
+        .await;
+
+        let req = TestRequest::default()
+            .insert_header(("x-request-id", "abc-123"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.headers().get("x-request-id").unwrap(), "generated");
+    }
+
+    #[actix_rt::test]
+    async fn forwards_ids() {
+        async fn outgoing() -> HttpResponse {
+            actix_rt::time::sleep(std::time::Duration::from_millis(1)).await;
+
+            let client = awc::Client::new();
+            let req = client.get("http://localhost/");
+
+            match req.headers().get("x-request-id") {
+                Some(id) => HttpResponse::Ok().body(id.as_bytes().to_vec()),
+                None => HttpResponse::NoContent().finish(),
+            }
+        }
+
+        let srv = test::init_service(
+            App::new()
+                .wrap(RequestId::new())
+                .route("/", web::get().to(outgoing)),
+        )
+        .await;
+
+        let req = TestRequest::default()
+            .insert_header(("x-request-id", "abc-123"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(test::read_body(res).await, "abc-123");
+
+        let req = awc::Client::new().get("http://localhost/");
+        assert!(!req.headers().contains_key("x-request-id"));
+
+        let srv = test::init_service(
+            App::new()
+                .wrap(RequestId::new().forward(false))
+                .route("/", web::get().to(outgoing)),
+        )
+        .await;
+
+        let req = TestRequest::default()
+            .insert_header(("x-request-id", "abc-123"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    }
+
+    #[actix_rt::test]
+    async fn missing_middleware() {
+        let srv = test::init_service(
+            App::new().route("/", web::get().to(|_: web::RequestId| HttpResponse::Ok())),
+        )
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -0,0 +1,431 @@
+//! For middleware documentation, see [`SecurityHeaders`].
+
+use std::{
+    fmt::{self, Write as _},
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use actix_utils::future::{ready, Ready};
+use futures_core::ready;
+
+use crate::{
+    dev::{Service, Transform},
+    http::header::{
+        ContentSecurityPolicy, Header, HeaderMap, PermissionsPolicy, ReferrerPolicy,
+        StrictTransportSecurity, XContentTypeOptions, XFrameOptions,
+    },
+    service::{ServiceRequest, ServiceResponse},
+    web, Error, HttpMessage as _,
+};
+
+/// Number of random bytes in generated CSP nonces.
+const NONCE_LEN: usize = 16;
+
+/// Middleware for setting security-related response headers.
+///
+/// By default, the following headers are set:
+/// - `Strict-Transport-Security: max-age=31536000`, only on connections whose
+///   [scheme](crate::dev::ConnectionInfo::scheme) is `https`
+/// - `X-Content-Type-Options: nosniff`
+/// - `Referrer-Policy: strict-origin-when-cross-origin`
+/// - `X-Frame-Options: DENY`
+///
+/// A `Content-Security-Policy` and a `Permissions-Policy` can be added, and each header can be
+/// changed or disabled with its builder method. Headers already present on the response are left
+/// untouched, so handlers can override the preset for individual responses.
+///
+/// When [`csp_nonce`](Self::csp_nonce) is enabled, a random nonce is generated for each request
+/// and added to the `script-src` and `style-src` directives of the policy. Handlers can extract
+/// it with [`web::CspNonce`] to allow their inline scripts and styles.
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     http::header::{ContentSecurityPolicy, PermissionsPolicy, XFrameOptions},
+///     middleware, App,
+/// };
+///
+/// let app = App::new().wrap(
+///     middleware::SecurityHeaders::new()
+///         .content_security_policy(
+///             ContentSecurityPolicy::new()
+///                 .default_src(&["'self'"])
+///                 .frame_ancestors(&["'self'"]),
+///         )
+///         .csp_nonce(true)
+///         .permissions_policy(PermissionsPolicy::new().deny("camera"))
+///         .frame_options(XFrameOptions::SameOrigin),
+/// );
+/// ```
+#[derive(Clone)]
+pub struct SecurityHeaders {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    hsts: Option<StrictTransportSecurity>,
+    csp: Option<ContentSecurityPolicy>,
+    csp_nonce: bool,
+    content_type_options: Option<XContentTypeOptions>,
+    referrer_policy: Option<ReferrerPolicy>,
+    permissions_policy: Option<PermissionsPolicy>,
+    frame_options: Option<XFrameOptions>,
+}
+
+impl Default for SecurityHeaders {
+    fn default() -> Self {
+        SecurityHeaders {
+            inner: Rc::new(Inner {
+                hsts: Some(StrictTransportSecurity::default()),
+                csp: None,
+                csp_nonce: false,
+                content_type_options: Some(XContentTypeOptions),
+                referrer_policy: Some(ReferrerPolicy::StrictOriginWhenCrossOrigin),
+                permissions_policy: None,
+                frame_options: Some(XFrameOptions::Deny),
+            }),
+        }
+    }
+}
+
+impl SecurityHeaders {
+    /// Constructs a `SecurityHeaders` middleware with the default preset.
+    pub fn new() -> SecurityHeaders {
+        SecurityHeaders::default()
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("Multiple `Inner` copies exist.")
+    }
+
+    /// Sets the `Strict-Transport-Security` header sent on secure connections.
+    ///
+    /// Pass `None` to disable it.
+    pub fn strict_transport_security(
+        mut self,
+        hsts: impl Into<Option<StrictTransportSecurity>>,
+    ) -> Self {
+        self.inner_mut().hsts = hsts.into();
+        self
+    }
+
+    /// Sets the `Content-Security-Policy` header.
+    ///
+    /// Pass `None` to disable it, which is the default.
+    pub fn content_security_policy(
+        mut self,
+        csp: impl Into<Option<ContentSecurityPolicy>>,
+    ) -> Self {
+        self.inner_mut().csp = csp.into();
+        self
+    }
+
+    /// Sets whether a nonce is generated for each request and added to the
+    /// `Content-Security-Policy` header.
+    ///
+    /// Default is `false`. Has no effect unless a policy is set.
+    pub fn csp_nonce(mut self, enabled: bool) -> Self {
+        self.inner_mut().csp_nonce = enabled;
+        self
+    }
+
+    /// Sets whether the `X-Content-Type-Options: nosniff` header is sent.
+    ///
+    /// Default is `true`.
+    pub fn content_type_options(mut self, enabled: bool) -> Self {
+        self.inner_mut().content_type_options = if enabled {
+            Some(XContentTypeOptions)
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Sets the `Referrer-Policy` header.
+    ///
+    /// Pass `None` to disable it.
+    pub fn referrer_policy(mut self, policy: impl Into<Option<ReferrerPolicy>>) -> Self {
+        self.inner_mut().referrer_policy = policy.into();
+        self
+    }
+
+    /// Sets the `Permissions-Policy` header.
+    ///
+    /// Pass `None` to disable it, which is the default.
+    pub fn permissions_policy(mut self, policy: impl Into<Option<PermissionsPolicy>>) -> Self {
+        self.inner_mut().permissions_policy = policy.into();
+        self
+    }
+
+    /// Sets the `X-Frame-Options` header.
+    ///
+    /// Pass `None` to disable it.
+    pub fn frame_options(mut self, options: impl Into<Option<XFrameOptions>>) -> Self {
+        self.inner_mut().frame_options = options.into();
+        self
+    }
+}
+
+impl fmt::Debug for SecurityHeaders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecurityHeaders")
+            .field("hsts", &self.inner.hsts)
+            .field("csp", &self.inner.csp)
+            .field("csp_nonce", &self.inner.csp_nonce)
+            .field("content_type_options", &self.inner.content_type_options)
+            .field("referrer_policy", &self.inner.referrer_policy)
+            .field("permissions_policy", &self.inner.permissions_policy)
+            .field("frame_options", &self.inner.frame_options)
+            .finish()
+    }
+}
+
+impl Inner {
+    /// Inserts the configured headers that are missing from `headers`.
+    fn apply(&self, headers: &mut HeaderMap, secure: bool, nonce: Option<&web::CspNonce>) {
+        if secure {
+            if let Some(ref hsts) = self.hsts {
+                insert_missing(headers, hsts.clone());
+            }
+        }
+
+        if let Some(ref csp) = self.csp {
+            match nonce {
+                Some(nonce) => insert_missing(headers, csp.with_nonce(nonce.as_str())),
+                None => insert_missing(headers, csp.clone()),
+            }
+        }
+
+        if let Some(options) = self.content_type_options {
+            insert_missing(headers, options);
+        }
+
+        if let Some(policy) = self.referrer_policy {
+            insert_missing(headers, policy);
+        }
+
+        if let Some(ref policy) = self.permissions_policy {
+            insert_missing(headers, policy.clone());
+        }
+
+        if let Some(options) = self.frame_options {
+            insert_missing(headers, options);
+        }
+    }
+}
+
+fn insert_missing<H: Header>(headers: &mut HeaderMap, header: H) {
+    let name = H::name();
+
+    if headers.contains_key(&name) {
+        return;
+    }
+
+    match header.try_into_value() {
+        Ok(value) => {
+            headers.insert(name, value);
+        }
+        Err(_) => log::warn!("Security header {} has an invalid value", name),
+    }
+}
+
+/// Generates a random nonce.
+fn generate_nonce() -> String {
+    let mut nonce = String::with_capacity(NONCE_LEN * 2);
+
+    for byte in rand::random::<[u8; NONCE_LEN]>().iter() {
+        write!(nonce, "{:02x}", byte).unwrap();
+    }
+
+    nonce
+}
+
+impl<S, B> Transform<S, ServiceRequest> for SecurityHeaders
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = SecurityHeadersMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(SecurityHeadersMiddleware {
+            service,
+            inner: Rc::clone(&self.inner),
+        }))
+    }
+}
+
+pub struct SecurityHeadersMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for SecurityHeadersMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = SecurityHeadersFuture<S, B>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let secure = req.connection_info().scheme() == "https";
+
+        let nonce = if self.inner.csp.is_some() && self.inner.csp_nonce {
+            let nonce = web::CspNonce::new(generate_nonce());
+            req.extensions_mut().insert(nonce.clone());
+            Some(nonce)
+        } else {
+            None
+        };
+
+        SecurityHeadersFuture {
+            fut: self.service.call(req),
+            inner: Rc::clone(&self.inner),
+            secure,
+            nonce,
+            _body: PhantomData,
+        }
+    }
+}
+
+#[pin_project::pin_project]
+pub struct SecurityHeadersFuture<S: Service<ServiceRequest>, B> {
+    #[pin]
+    fut: S::Future,
+    inner: Rc<Inner>,
+    secure: bool,
+    nonce: Option<web::CspNonce>,
+    _body: PhantomData<B>,
+}
+
+impl<S, B> Future for SecurityHeadersFuture<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Output = <S::Future as Future>::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let mut res = ready!(this.fut.poll(cx))?;
+
+        this.inner
+            .apply(res.headers_mut(), *this.secure, this.nonce.as_ref());
+
+        Poll::Ready(Ok(res))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::{header, StatusCode},
+        test::{self, TestRequest},
+        App, HttpResponse,
+    };
+
+    #[actix_rt::test]
+    async fn sets_default_headers() {
+        let srv = test::init_service(App::new().wrap(SecurityHeaders::new()).route(
+            "/",
+            web::get().to(|| {
+                HttpResponse::Ok()
+                    .insert_header(XFrameOptions::SameOrigin)
+                    .finish()
+            }),
+        ))
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res
+            .headers()
+            .contains_key(header::STRICT_TRANSPORT_SECURITY));
+        assert!(!res.headers().contains_key(header::CONTENT_SECURITY_POLICY));
+        assert_eq!(
+            res.headers().get(header::X_CONTENT_TYPE_OPTIONS).unwrap(),
+            "nosniff"
+        );
+        assert_eq!(
+            res.headers().get(header::REFERRER_POLICY).unwrap(),
+            "strict-origin-when-cross-origin"
+        );
+        assert_eq!(
+            res.headers().get(header::X_FRAME_OPTIONS).unwrap(),
+            "SAMEORIGIN"
+        );
+
+        let req = TestRequest::default()
+            .insert_header(("x-forwarded-proto", "https"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(
+            res.headers()
+                .get(header::STRICT_TRANSPORT_SECURITY)
+                .unwrap(),
+            "max-age=31536000"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn generates_csp_nonces() {
+        let srv = test::init_service(
+            App::new()
+                .wrap(
+                    SecurityHeaders::new()
+                        .content_security_policy(
+                            ContentSecurityPolicy::new().default_src(&["'self'"]),
+                        )
+                        .csp_nonce(true)
+                        .frame_options(None),
+                )
+                .route(
+                    "/",
+                    web::get()
+                        .to(|nonce: web::CspNonce| HttpResponse::Ok().body(nonce.into_inner())),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key(header::X_FRAME_OPTIONS));
+
+        let csp = res
+            .headers()
+            .get(header::CONTENT_SECURITY_POLICY)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned();
+        let nonce = test::read_body(res).await;
+        let nonce = std::str::from_utf8(&nonce).unwrap();
+        assert_eq!(nonce.len(), NONCE_LEN * 2);
+        assert_eq!(
+            csp,
+            format!(
+                "default-src 'self'; script-src 'self' 'nonce-{0}'; \
+                 style-src 'self' 'nonce-{0}'",
+                nonce
+            )
+        );
+
+        let req = TestRequest::default().to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_ne!(test::read_body(res).await, nonce.as_bytes());
+    }
+}
@@ -0,0 +1,290 @@
+//! For middleware documentation, see [`Timeout`].
+
+use std::{error::Error as StdError, net::SocketAddr, rc::Rc, time::Duration};
+
+use actix_http::{Extensions, Message, RequestHead};
+use actix_router::{Path, Url};
+use actix_rt::time::timeout;
+use actix_utils::future::{ready, Ready};
+use futures_core::future::LocalBoxFuture;
+
+use crate::{
+    app_service::AppInitServiceState,
+    body::{Body, MessageBody},
+    dev::{Service, Transform},
+    http::{Method, StatusCode, Uri, Version},
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpRequest, HttpResponse,
+};
+
+/// Middleware for limiting the time taken to handle a request.
+///
+/// If the wrapped service does not produce a response within the configured duration, its future
+/// is dropped, cancelling the handler at its next `.await`, and a *503 Service Unavailable*
+/// response is returned instead. The status code can be changed with [`status`](Self::status).
+/// The timer only covers producing the response; streaming the response body is not limited.
+///
+/// The request is dropped along with the cancelled future, so the response to a timed out request
+/// is given a new request with the same method, URI, version and peer address, but without headers
+/// or the extensions inserted by other middleware. It has a [`TimedOut`] value in its extensions,
+/// which [`Logger`](super::Logger) reports with the `%X` format unit.
+///
+/// The middleware can be applied to an `App`, a `Scope` or a `Resource`. When nested, the
+/// shortest applicable timeout takes effect.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use actix_web::{http::StatusCode, middleware::Timeout, web, App, HttpResponse};
+///
+/// let app = App::new()
+///     .wrap(Timeout::new(Duration::from_secs(30)))
+///     .service(
+///         web::resource("/report")
+///             .wrap(Timeout::new(Duration::from_secs(5)).status(StatusCode::GATEWAY_TIMEOUT))
+///             .route(web::get().to(HttpResponse::Ok)),
+///     );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Timeout {
+    inner: Rc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    duration: Duration,
+    status: StatusCode,
+}
+
+impl Timeout {
+    /// Constructs a `Timeout` middleware cancelling requests not handled within `duration`.
+    pub fn new(duration: Duration) -> Timeout {
+        Timeout {
+            inner: Rc::new(Inner {
+                duration,
+                status: StatusCode::SERVICE_UNAVAILABLE,
+            }),
+        }
+    }
+
+    /// Sets the status code of responses to timed out requests.
+    ///
+    /// Default is *503 Service Unavailable*. *504 Gateway Timeout* is common for services that
+    /// mostly wait on upstream servers.
+    pub fn status(mut self, status: StatusCode) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple `Inner` copies exist.")
+            .status = status;
+        self
+    }
+}
+
+/// Marker inserted in the extensions of requests cancelled by the [`Timeout`] middleware.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedOut {
+    /// Duration after which the request was cancelled.
+    pub duration: Duration,
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Timeout
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Transform = TimeoutMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(TimeoutMiddleware {
+            service,
+            inner: Rc::clone(&self.inner),
+        }))
+    }
+}
+
+pub struct TimeoutMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for TimeoutMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let inner = Rc::clone(&self.inner);
+        let parts = RequestParts::new(req.parts_mut().0);
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            match timeout(inner.duration, fut).await {
+                Ok(res) => Ok(res?.map_body(|_, body| Body::from_message(body))),
+
+                Err(_) => {
+                    log::debug!(
+                        "Request to {} timed out after {:?}",
+                        parts.uri.path(),
+                        inner.duration
+                    );
+
+                    let req = parts.into_request();
+                    req.extensions_mut().insert(TimedOut {
+                        duration: inner.duration,
+                    });
+
+                    let res = HttpResponse::build(inner.status).finish();
+                    Ok(ServiceResponse::new(req, res))
+                }
+            }
+        })
+    }
+}
+
+/// Parts of a request kept to respond to it after it was dropped along with the service future.
+///
+/// Only cheap to clone parts are kept; the request is only built if the service times out.
+struct RequestParts {
+    method: Method,
+    uri: Uri,
+    version: Version,
+    peer_addr: Option<SocketAddr>,
+    app_state: Rc<AppInitServiceState>,
+    app_data: Rc<Extensions>,
+}
+
+impl RequestParts {
+    fn new(req: &HttpRequest) -> RequestParts {
+        let inner = &req.inner;
+
+        RequestParts {
+            method: req.method().clone(),
+            uri: req.uri().clone(),
+            version: req.version(),
+            peer_addr: req.peer_addr(),
+            app_state: Rc::clone(&inner.app_state),
+            app_data: Rc::clone(&inner.app_data[0]),
+        }
+    }
+
+    fn into_request(self) -> HttpRequest {
+        let mut head = Message::<RequestHead>::new();
+        head.method = self.method;
+        head.uri = self.uri.clone();
+        head.version = self.version;
+        head.peer_addr = self.peer_addr;
+
+        HttpRequest::new(
+            Path::new(Url::new(self.uri)),
+            head,
+            self.app_state,
+            self.app_data,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_rt::time::sleep;
+
+    use super::*;
+    use crate::{
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    async fn slow() -> HttpResponse {
+        sleep(Duration::from_millis(200)).await;
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_rt::test]
+    async fn cancels_slow_requests() {
+        let srv = test::init_service(
+            App::new()
+                .wrap(Timeout::new(Duration::from_millis(50)))
+                .route("/slow", web::get().to(slow))
+                .route("/fast", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/slow").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            res.request().extensions().get::<TimedOut>(),
+            Some(&TimedOut {
+                duration: Duration::from_millis(50)
+            })
+        );
+
+        let req = TestRequest::with_uri("/fast").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.request().extensions().get::<TimedOut>().is_none());
+    }
+
+    #[actix_rt::test]
+    async fn keeps_request_id() {
+        let srv = test::init_service(
+            App::new()
+                .wrap(Timeout::new(Duration::from_millis(50)))
+                .wrap(crate::middleware::RequestId::new())
+                .route("/slow", web::get().to(slow)),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/slow?page=2")
+            .insert_header(("x-request-id", "abc-123"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(res.headers().get("x-request-id").unwrap(), "abc-123");
+
+        let req = res.request();
+        assert_eq!(req.method(), Method::GET);
+        assert_eq!(req.uri(), "/slow?page=2");
+        assert!(req.extensions().get::<TimedOut>().is_some());
+        assert_eq!(
+            req.extensions()
+                .get::<crate::web::RequestId>()
+                .map(|id| id.as_str()),
+            Some("abc-123")
+        );
+    }
+
+    #[actix_rt::test]
+    async fn nested_timeouts() {
+        let srv = test::init_service(
+            App::new()
+                .wrap(Timeout::new(Duration::from_secs(10)))
+                .service(
+                    web::scope("/api")
+                        .wrap(
+                            Timeout::new(Duration::from_millis(50))
+                                .status(StatusCode::GATEWAY_TIMEOUT),
+                        )
+                        .route("/slow", web::get().to(slow)),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/api/slow").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::GATEWAY_TIMEOUT);
+    }
+}
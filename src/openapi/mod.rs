@@ -0,0 +1,1122 @@
+//! OpenAPI document generation.
+//!
+//! Each route with a handler registered through [`Route::to`](crate::Route::to) (or any of its
+//! shorthands, including the [route macros](crate::get)) is described by an [`Operation`]. The
+//! operation's parameters, request body and responses are derived from the handler's extractors
+//! and return type, through [`FromRequest::schema`] and [`Responder::schema`]. Types that
+//! implement `serde::Deserialize`, such as the `T` in [`Json<T>`](crate::web::Json), are described
+//! by observing how they deserialize.
+//!
+//! [`OpenApi`] walks the [`ResourceMap`] of an app and produces an OpenAPI 3 document for all
+//! described routes with known methods. It can also be registered as a service that serves the
+//! document.
+//!
+//! ```
+//! use actix_web::{get, openapi::OpenApi, web, App, Responder};
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Pagination {
+//!     page: u32,
+//!     per_page: Option<u32>,
+//! }
+//!
+//! /// List pets.
+//! #[get("/pets")]
+//! async fn list_pets(query: web::Query<Pagination>) -> impl Responder {
+//!     format!("page {}", query.page)
+//! }
+//!
+//! let app = App::new()
+//!     .service(list_pets)
+//!     .service(OpenApi::new("Pet Store", "1.0.0")); // <- serves GET /openapi.json
+//! ```
+//!
+//! [`FromRequest::schema`]: crate::FromRequest::schema
+//! [`Responder::schema`]: crate::Responder::schema
+
+use std::{collections::BTreeMap, fmt, future::ready, rc::Rc};
+
+use actix_http::http::{Method, StatusCode};
+use mime::Mime;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    dev::{
+        fn_service, AppService, HttpServiceFactory, ResourceEntry, ResourceMap, ServiceRequest,
+    },
+    guard, Error, HttpResponse, Resource, Route,
+};
+
+mod shape;
+
+pub(crate) use self::shape::Shape;
+
+/// The version of the OpenAPI specification generated documents conform to.
+const OPENAPI_VERSION: &str = "3.0.3";
+
+/// Returns the OpenAPI schema of a type, as observed from its `Deserialize` implementation.
+///
+/// Useful for describing types that are only serialized, such as a handler's JSON response.
+///
+/// ```
+/// use actix_web::openapi::schema_for;
+/// use serde::Deserialize;
+/// use serde_json::json;
+///
+/// #[derive(Deserialize)]
+/// struct Pet {
+///     name: String,
+///     age: Option<u8>,
+/// }
+///
+/// assert_eq!(
+///     schema_for::<Pet>(),
+///     json!({
+///         "type": "object",
+///         "properties": {
+///             "name": { "type": "string" },
+///             "age": { "type": "integer", "format": "int32", "minimum": 0, "nullable": true },
+///         },
+///         "required": ["name"],
+///     })
+/// );
+/// ```
+pub fn schema_for<T: DeserializeOwned>() -> Value {
+    Shape::of::<T>().to_schema()
+}
+
+/// The location of an operation [`Parameter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterIn {
+    /// A dynamic segment of the path.
+    Path,
+
+    /// A query string parameter.
+    Query,
+
+    /// A request header.
+    Header,
+
+    /// A cookie.
+    Cookie,
+}
+
+impl ParameterIn {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ParameterIn::Path => "path",
+            ParameterIn::Query => "query",
+            ParameterIn::Header => "header",
+            ParameterIn::Cookie => "cookie",
+        }
+    }
+}
+
+/// A parameter of an [`Operation`].
+///
+/// Parameters are required and accept any value unless configured otherwise.
+///
+/// ```
+/// use actix_web::openapi::{Parameter, ParameterIn};
+/// use serde_json::json;
+///
+/// let param = Parameter::new("x-request-id", ParameterIn::Header)
+///     .required(false)
+///     .schema(json!({ "type": "string", "format": "uuid" }));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    name: String,
+    location: ParameterIn,
+    description: Option<String>,
+    required: bool,
+    schema: Value,
+}
+
+impl Parameter {
+    /// Constructs a required parameter with the given name and location.
+    pub fn new(name: impl Into<String>, location: ParameterIn) -> Self {
+        Parameter {
+            name: name.into(),
+            location,
+            description: None,
+            required: true,
+            schema: json!({}),
+        }
+    }
+
+    /// Sets the parameter description.
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Sets whether the parameter is required.
+    ///
+    /// Path parameters are always required.
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// Sets the schema of the parameter's value.
+    pub fn schema(mut self, schema: Value) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    fn to_json(&self) -> Value {
+        let mut param = json!({
+            "name": self.name,
+            "in": self.location.as_str(),
+            "required": self.required || self.location == ParameterIn::Path,
+            "schema": self.schema,
+        });
+
+        if let Some(description) = &self.description {
+            param["description"] = json!(description);
+        }
+
+        param
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RequestBody {
+    required: bool,
+    content: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Response {
+    description: String,
+    content: BTreeMap<String, Value>,
+}
+
+/// Hook deriving the parameters, request body and responses of an operation from the types of a
+/// handler, run when the operation is documented.
+#[derive(Clone, Copy)]
+struct SchemaFn(fn(&mut Operation));
+
+impl fmt::Debug for SchemaFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SchemaFn")
+    }
+}
+
+impl PartialEq for SchemaFn {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 as usize == other.0 as usize
+    }
+}
+
+/// Describes an API operation, provided by the handler of a route, in generated OpenAPI
+/// documents.
+///
+/// Operations are populated from the handler's extractors and return type. Further details can
+/// be added with [`Route::operation`](crate::Route::operation).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Operation {
+    methods: Option<Vec<Method>>,
+    operation_id: Option<String>,
+    summary: Option<String>,
+    description: Option<String>,
+    tags: Vec<String>,
+    parameters: Vec<Parameter>,
+    path: Option<Shape>,
+    request_body: Option<RequestBody>,
+    responses: BTreeMap<u16, Response>,
+    schema: Option<SchemaFn>,
+}
+
+impl Operation {
+    /// Sets the unique identifier of the operation.
+    ///
+    /// The [route macros](crate::get) use the resource name, which defaults to the name of the
+    /// handler function.
+    pub fn operation_id(&mut self, operation_id: impl Into<String>) -> &mut Self {
+        self.operation_id = Some(operation_id.into());
+        self
+    }
+
+    /// Sets a short summary of what the operation does.
+    pub fn summary(&mut self, summary: impl Into<String>) -> &mut Self {
+        self.summary = Some(summary.into());
+        self
+    }
+
+    /// Sets a verbose explanation of the operation.
+    pub fn description(&mut self, description: impl Into<String>) -> &mut Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Adds a tag, used for grouping operations.
+    pub fn tag(&mut self, tag: impl Into<String>) -> &mut Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Adds a parameter, replacing any previously added parameter with the same name and
+    /// location.
+    ///
+    /// Parameters for the dynamic segments of the route's path are added to generated documents
+    /// automatically. Adding a path parameter overrides the generated one.
+    pub fn parameter(&mut self, parameter: Parameter) -> &mut Self {
+        self.parameters
+            .retain(|p| p.name != parameter.name || p.location != parameter.location);
+        self.parameters.push(parameter);
+        self
+    }
+
+    /// Adds an accepted request body content type, and marks the request body as required.
+    pub fn request_body(&mut self, content_type: Mime, schema: Value) -> &mut Self {
+        let body = self.request_body.get_or_insert_with(|| RequestBody {
+            required: true,
+            content: BTreeMap::new(),
+        });
+
+        body.required = true;
+        body.content
+            .insert(content_type.to_string(), json!({ "schema": schema }));
+        self
+    }
+
+    /// Adds a response with the given status code and description.
+    ///
+    /// If the response was already added, its description is replaced.
+    pub fn response(
+        &mut self,
+        status: StatusCode,
+        description: impl Into<String>,
+    ) -> &mut Self {
+        self.response_mut(status).description = description.into();
+        self
+    }
+
+    /// Adds a response content type for the given status code.
+    ///
+    /// The response is added, described by the status code's canonical reason, if it was not
+    /// already.
+    pub fn response_content(
+        &mut self,
+        status: StatusCode,
+        content_type: Mime,
+        schema: Value,
+    ) -> &mut Self {
+        self.response_mut(status)
+            .content
+            .insert(content_type.to_string(), json!({ "schema": schema }));
+        self
+    }
+
+    /// Returns the methods the operation is available for, or `None` if requests with any method
+    /// are handled.
+    ///
+    /// Only known for operations returned by [`ResourceEntry::operations`].
+    ///
+    /// [`ResourceEntry::operations`]: crate::dev::ResourceEntry::operations
+    pub fn methods(&self) -> Option<&[Method]> {
+        self.methods.as_deref()
+    }
+
+    pub(crate) fn set_methods(&mut self, methods: Option<Vec<Method>>) {
+        self.methods = methods;
+    }
+
+    /// Records the shape of the path segments extracted by the handler.
+    ///
+    /// Used to type the path parameters of generated documents.
+    pub(crate) fn set_path(&mut self, shape: Shape) {
+        self.path = Some(shape);
+    }
+
+    /// Replaces parameters, request body and responses with those of `other`.
+    pub(crate) fn replace_schema(&mut self, other: Operation) {
+        self.parameters = other.parameters;
+        self.path = other.path;
+        self.request_body = other.request_body;
+        self.responses = other.responses;
+    }
+
+    /// Defers deriving the parameters, request body and responses to `schema`, replacing those
+    /// previously derived or added.
+    ///
+    /// Extractor types are only traced once the operation is [resolved](Self::resolved), so that
+    /// apps not generating documents do not pay for it.
+    pub(crate) fn set_schema(&mut self, schema: fn(&mut Operation)) {
+        self.replace_schema(Operation::default());
+        self.schema = Some(SchemaFn(schema));
+    }
+
+    /// Returns the operation with its deferred schema derived, overlaid with the parameters,
+    /// request body and responses added since.
+    pub(crate) fn resolved(&self) -> Operation {
+        let mut op = self.clone();
+
+        let schema = match op.schema.take() {
+            Some(schema) => schema,
+            None => return op,
+        };
+
+        let mut derived = Operation::default();
+        (schema.0)(&mut derived);
+
+        for param in std::mem::take(&mut op.parameters) {
+            derived.parameter(param);
+        }
+
+        if let Some(body) = op.request_body.take() {
+            let own = derived.request_body.get_or_insert_with(|| RequestBody {
+                required: true,
+                content: BTreeMap::new(),
+            });
+            own.required = body.required;
+            own.content.extend(body.content);
+        }
+
+        for (status, response) in std::mem::take(&mut op.responses) {
+            let own = derived.responses.entry(status).or_insert_with(|| Response {
+                description: String::new(),
+                content: BTreeMap::new(),
+            });
+            own.description = response.description;
+            own.content.extend(response.content);
+        }
+
+        op.replace_schema(derived);
+        op
+    }
+
+    /// Merges the parameters, request body and responses of `other`, treating its parameters and
+    /// request body as optional.
+    ///
+    /// Used for extractors that tolerate a failing inner extractor.
+    pub(crate) fn merge_optional(&mut self, other: Operation) {
+        for param in other.parameters {
+            let required = param.required && param.location == ParameterIn::Path;
+            self.parameter(param.required(required));
+        }
+
+        if other.path.is_some() {
+            self.path = other.path;
+        }
+
+        if let Some(body) = other.request_body {
+            let own = self.request_body.get_or_insert_with(|| RequestBody {
+                required: false,
+                content: BTreeMap::new(),
+            });
+            own.content.extend(body.content);
+        }
+
+        for (status, response) in other.responses {
+            self.responses.entry(status).or_insert(response);
+        }
+    }
+
+    fn response_mut(&mut self, status: StatusCode) -> &mut Response {
+        self.responses
+            .entry(status.as_u16())
+            .or_insert_with(|| Response {
+                description: status.canonical_reason().unwrap_or_default().to_owned(),
+                content: BTreeMap::new(),
+            })
+    }
+
+    /// Converts to an OpenAPI operation object, for a path of `entry` with the given parameter
+    /// names.
+    fn to_json(
+        &self,
+        entry: &ResourceEntry,
+        path_params: &[String],
+        id_suffix: Option<&str>,
+    ) -> Value {
+        let mut op = Map::new();
+
+        if let Some(id) = &self.operation_id {
+            let id = match id_suffix {
+                Some(suffix) => format!("{}_{}", id, suffix),
+                None => id.clone(),
+            };
+            op.insert("operationId".to_owned(), json!(id));
+        }
+
+        if let Some(summary) = &self.summary {
+            op.insert("summary".to_owned(), json!(summary));
+        }
+
+        if let Some(description) = &self.description {
+            op.insert("description".to_owned(), json!(description));
+        }
+
+        if !self.tags.is_empty() {
+            op.insert("tags".to_owned(), json!(self.tags));
+        }
+
+        let mut params = path_params
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| {
+                !self
+                    .parameters
+                    .iter()
+                    .any(|p| p.location == ParameterIn::Path && &p.name == *name)
+            })
+            .map(|(idx, name)| {
+                Parameter::new(name.as_str(), ParameterIn::Path)
+                    .schema(self.path_param_schema(entry, path_params.len(), idx, name))
+                    .to_json()
+            })
+            .collect::<Vec<_>>();
+        params.extend(self.parameters.iter().map(Parameter::to_json));
+
+        if !params.is_empty() {
+            op.insert("parameters".to_owned(), Value::Array(params));
+        }
+
+        if let Some(body) = &self.request_body {
+            op.insert(
+                "requestBody".to_owned(),
+                json!({ "required": body.required, "content": body.content }),
+            );
+        }
+
+        let responses = if self.responses.is_empty() {
+            json!({ "default": { "description": "Default response" } })
+        } else {
+            self.responses
+                .iter()
+                .map(|(status, res)| {
+                    let mut response = json!({ "description": res.description });
+                    if !res.content.is_empty() {
+                        response["content"] = json!(res.content);
+                    }
+                    (status.to_string(), response)
+                })
+                .collect::<Map<_, _>>()
+                .into()
+        };
+        op.insert("responses".to_owned(), responses);
+
+        Value::Object(op)
+    }
+
+    /// Returns the schema of the path parameter at `idx`, out of `count` parameters.
+    ///
+    /// Falls back to the schema of the segment's matcher when the extracted type is unknown.
+    fn path_param_schema(
+        &self,
+        entry: &ResourceEntry,
+        count: usize,
+        idx: usize,
+        name: &str,
+    ) -> Value {
+        let shape = match &self.path {
+            Some(Shape::Struct(fields)) => fields
+                .iter()
+                .find(|(field, _)| *field == name)
+                .map(|(_, shape)| shape),
+            Some(Shape::Tuple(items)) => items.get(idx),
+            Some(shape @ Shape::Primitive(_)) | Some(shape @ Shape::Enum(_)) if count == 1 => {
+                Some(shape)
+            }
+            _ => None,
+        };
+
+        match shape {
+            Some(shape) if *shape != Shape::Unknown => shape.to_schema(),
+            _ => entry
+                .segment_matcher(name)
+                .and_then(matcher_schema)
+                .unwrap_or_else(|| json!({ "type": "string" })),
+        }
+    }
+}
+
+/// OpenAPI document generator.
+///
+/// When registered as a service, serves the document describing the app at `/openapi.json`, or
+/// at the path set with [`path`](Self::path).
+///
+/// Only routes with a known set of methods, such as routes registered with [`web::get`] or the
+/// [route macros](crate::get), are included in the document. Default services and routes with
+/// custom services, such as the document itself, are not.
+///
+/// [`web::get`]: crate::web::get
+#[derive(Debug, Clone)]
+pub struct OpenApi {
+    title: String,
+    version: String,
+    description: Option<String>,
+    path: String,
+}
+
+impl OpenApi {
+    /// Constructs a generator for an API with the given title and version.
+    pub fn new(title: impl Into<String>, version: impl Into<String>) -> Self {
+        OpenApi {
+            title: title.into(),
+            version: version.into(),
+            description: None,
+            path: "/openapi.json".to_owned(),
+        }
+    }
+
+    /// Sets the API description.
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Sets the path at which the document is served when registered as a service.
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Generates the document describing the routes registered in `rmap`.
+    ///
+    /// ```
+    /// use actix_web::{openapi::OpenApi, test, web, App, HttpResponse};
+    ///
+    /// # actix_web::rt::System::new().block_on(async {
+    /// let app = App::new().route("/", web::get().to(HttpResponse::Ok));
+    /// let rmap = test::init_resource_map(app).await;
+    ///
+    /// let doc = OpenApi::new("Example", "0.1.0").document(&rmap);
+    /// assert_eq!(doc["info"]["title"], "Example");
+    /// assert!(doc["paths"]["/"]["get"].is_object());
+    /// # });
+    /// ```
+    pub fn document(&self, rmap: &ResourceMap) -> Value {
+        let mut paths = Map::new();
+
+        for entry in rmap.entries() {
+            let operations = entry.operations();
+
+            for pattern in entry.patterns() {
+                let (path, params) = openapi_path(pattern);
+
+                for operation in &operations {
+                    let methods = match operation.methods() {
+                        Some(methods) => methods,
+                        None => continue,
+                    };
+
+                    let methods = methods
+                        .iter()
+                        .filter_map(|method| openapi_method(method))
+                        .collect::<Vec<_>>();
+
+                    if methods.is_empty() {
+                        continue;
+                    }
+
+                    let item = paths
+                        .entry(path.clone())
+                        .or_insert_with(|| json!({}))
+                        .as_object_mut()
+                        .unwrap();
+
+                    for method in &methods {
+                        // as when routing, the first registered route for a method takes priority
+                        if item.contains_key(*method) {
+                            continue;
+                        }
+
+                        let suffix = (methods.len() > 1).then(|| *method);
+                        item.insert(
+                            (*method).to_owned(),
+                            operation.to_json(&entry, &params, suffix),
+                        );
+                    }
+                }
+            }
+        }
+
+        let mut info = json!({ "title": self.title, "version": self.version });
+        if let Some(description) = &self.description {
+            info["description"] = json!(description);
+        }
+
+        json!({
+            "openapi": OPENAPI_VERSION,
+            "info": info,
+            "paths": paths,
+        })
+    }
+}
+
+impl HttpServiceFactory for OpenApi {
+    fn register(self, config: &mut AppService) {
+        let path = self.path.clone();
+        let openapi = Rc::new(self);
+
+        let serve = fn_service(move |req: ServiceRequest| {
+            let res = HttpResponse::Ok().json(openapi.document(req.resource_map()));
+            ready(Ok::<_, Error>(req.into_response(res)))
+        });
+
+        let route = Route::new().guard(guard::Get()).service(serve);
+
+        HttpServiceFactory::register(Resource::new(path).route(route), config)
+    }
+}
+
+/// Returns the lowercase OpenAPI name of a method, if it can be described by OpenAPI.
+fn openapi_method(method: &Method) -> Option<&'static str> {
+    Some(match *method {
+        Method::GET => "get",
+        Method::PUT => "put",
+        Method::POST => "post",
+        Method::DELETE => "delete",
+        Method::OPTIONS => "options",
+        Method::HEAD => "head",
+        Method::PATCH => "patch",
+        Method::TRACE => "trace",
+        _ => return None,
+    })
+}
+
+/// Returns the schema of values accepted by a built-in segment matcher.
+fn matcher_schema(matcher: &str) -> Option<Value> {
+    Some(match matcher {
+        "i8" | "i16" | "i32" => json!({ "type": "integer", "format": "int32" }),
+        "i64" | "isize" => json!({ "type": "integer", "format": "int64" }),
+        "i128" => json!({ "type": "integer" }),
+        "u8" | "u16" => json!({ "type": "integer", "format": "int32", "minimum": 0 }),
+        "u32" | "u64" | "usize" => {
+            json!({ "type": "integer", "format": "int64", "minimum": 0 })
+        }
+        "u128" => json!({ "type": "integer", "minimum": 0 }),
+        "uuid" => json!({ "type": "string", "format": "uuid" }),
+        "slug" => json!({ "type": "string", "pattern": "^[a-z0-9]+(-[a-z0-9]+)*$" }),
+        _ => return None,
+    })
+}
+
+/// Converts a resource pattern to an OpenAPI path template, returning the template and the names
+/// of its parameters.
+///
+/// Custom regular expressions and tail markers are removed from dynamic segments.
+fn openapi_path(pattern: &str) -> (String, Vec<String>) {
+    let mut path = String::with_capacity(pattern.len() + 1);
+    let mut params = Vec::new();
+
+    if !pattern.starts_with('/') {
+        path.push('/');
+    }
+
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        path.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+
+        // find matching closing brace, allowing for braces in custom regular expressions
+        let mut depth = 1;
+        let end = rest
+            .char_indices()
+            .find(|&(_, ch)| {
+                match ch {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+                depth == 0
+            })
+            .map_or(rest.len(), |(idx, _)| idx);
+
+        let name = rest[..end].split(':').next().unwrap_or_default();
+        path.push('{');
+        path.push_str(name);
+        path.push('}');
+        params.push(name.to_owned());
+
+        rest = rest.get(end + 1..).unwrap_or_default();
+        if rest == "*" {
+            rest = "";
+        }
+    }
+    path.push_str(rest);
+
+    (path, params)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::{
+        http::header,
+        test::{call_service, init_resource_map, init_service, read_body_json, TestRequest},
+        web, App, HttpRequest,
+    };
+
+    #[derive(Deserialize, Serialize)]
+    struct Pet {
+        name: String,
+        age: Option<u8>,
+    }
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct PetPath {
+        owner: String,
+        id: u64,
+    }
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Search {
+        q: String,
+        limit: Option<u32>,
+    }
+
+    #[test]
+    fn paths() {
+        assert_eq!(openapi_path(""), ("/".to_owned(), vec![]));
+        assert_eq!(openapi_path("/pets"), ("/pets".to_owned(), vec![]));
+        assert_eq!(
+            openapi_path("/pets/{id}/photos/{photo}"),
+            (
+                "/pets/{id}/photos/{photo}".to_owned(),
+                vec!["id".to_owned(), "photo".to_owned()]
+            )
+        );
+        assert_eq!(
+            openapi_path(r"/codes/{code:\d{3}}.json"),
+            ("/codes/{code}.json".to_owned(), vec!["code".to_owned()])
+        );
+        assert_eq!(
+            openapi_path("/static/{tail}*"),
+            ("/static/{tail}".to_owned(), vec!["tail".to_owned()])
+        );
+    }
+
+    #[actix_rt::test]
+    async fn document() {
+        async fn search(_: web::Query<Search>) -> web::Json<Vec<Pet>> {
+            web::Json(vec![])
+        }
+
+        async fn create(_: web::Path<String>, _: web::Json<Pet>) -> HttpResponse {
+            HttpResponse::Created().finish()
+        }
+
+        async fn show(_: web::Path<PetPath>) -> Option<String> {
+            None
+        }
+
+        async fn photo(_: web::Path<(String, u64)>, _: web::Bytes) -> &'static str {
+            "ok"
+        }
+
+        let app = App::new()
+            .service(
+                web::scope("/owners/{owner}")
+                    .route("/pets", web::get().to(search))
+                    .route(
+                        "/pets",
+                        web::post().to(create).operation(|op| {
+                            op.operation_id("create_pet")
+                                .summary("Create a pet")
+                                .tag("pets")
+                                .response(StatusCode::CREATED, "Pet created");
+                        }),
+                    )
+                    .route(r"/pets/{id:\d+}", web::get().to(show))
+                    .service(
+                        web::resource("/pets/{id}/photo")
+                            .guard(guard::Any(guard::Put()).or(guard::Post()))
+                            .route(web::route().to(photo).operation(|op| {
+                                op.operation_id("upload_photo");
+                            })),
+                    ),
+            )
+            .route("/any", web::route().to(HttpResponse::Ok))
+            .default_service(web::to(HttpResponse::NotFound));
+
+        let rmap = init_resource_map(app).await;
+        let doc = OpenApi::new("Pets", "1.0.0")
+            .description("Pet owners and their pets")
+            .document(&rmap);
+
+        assert_eq!(doc["openapi"], "3.0.3");
+        assert_eq!(
+            doc["info"],
+            json!({
+                "title": "Pets",
+                "version": "1.0.0",
+                "description": "Pet owners and their pets",
+            })
+        );
+
+        let paths = doc["paths"].as_object().unwrap();
+        assert_eq!(
+            paths.keys().collect::<Vec<_>>(),
+            [
+                "/owners/{owner}/pets",
+                "/owners/{owner}/pets/{id}",
+                "/owners/{owner}/pets/{id}/photo",
+            ]
+        );
+
+        let owner = json!({
+            "name": "owner",
+            "in": "path",
+            "required": true,
+            "schema": { "type": "string" },
+        });
+
+        assert_eq!(
+            paths["/owners/{owner}/pets"]["get"],
+            json!({
+                "parameters": [
+                    owner,
+                    {
+                        "name": "q",
+                        "in": "query",
+                        "required": true,
+                        "schema": { "type": "string" },
+                    },
+                    {
+                        "name": "limit",
+                        "in": "query",
+                        "required": false,
+                        "schema": {
+                            "type": "integer",
+                            "format": "int64",
+                            "minimum": 0,
+                            "nullable": true,
+                        },
+                    },
+                ],
+                "responses": {
+                    "200": {
+                        "description": "OK",
+                        "content": { "application/json": { "schema": {} } },
+                    },
+                },
+            })
+        );
+
+        assert_eq!(
+            paths["/owners/{owner}/pets"]["post"],
+            json!({
+                "operationId": "create_pet",
+                "summary": "Create a pet",
+                "tags": ["pets"],
+                "parameters": [owner],
+                "requestBody": {
+                    "required": true,
+                    "content": {
+                        "application/json": { "schema": schema_for::<Pet>() },
+                    },
+                },
+                "responses": { "201": { "description": "Pet created" } },
+            })
+        );
+
+        assert_eq!(
+            paths["/owners/{owner}/pets/{id}"]["get"],
+            json!({
+                "parameters": [
+                    owner,
+                    {
+                        "name": "id",
+                        "in": "path",
+                        "required": true,
+                        "schema": { "type": "integer", "format": "int64", "minimum": 0 },
+                    },
+                ],
+                "responses": {
+                    "200": {
+                        "description": "OK",
+                        "content": { "text/plain; charset=utf-8": { "schema": { "type": "string" } } },
+                    },
+                    "404": { "description": "Not Found" },
+                },
+            })
+        );
+
+        let photo = &paths["/owners/{owner}/pets/{id}/photo"];
+        assert_eq!(
+            photo.as_object().unwrap().keys().collect::<Vec<_>>(),
+            ["post", "put"]
+        );
+        assert_eq!(photo["put"]["operationId"], "upload_photo_put");
+        assert_eq!(
+            photo["put"]["parameters"][1]["schema"],
+            json!({ "type": "integer", "format": "int64", "minimum": 0 })
+        );
+        assert_eq!(
+            photo["put"]["requestBody"]["content"],
+            json!({
+                "application/octet-stream": {
+                    "schema": { "type": "string", "format": "binary" },
+                },
+            })
+        );
+    }
+
+    #[actix_rt::test]
+    async fn optional_extractors() {
+        async fn handler(
+            _: Option<web::Json<Pet>>,
+            _: Result<web::Query<Search>, Error>,
+            _: web::Header<header::ContentType>,
+            _: HttpRequest,
+        ) -> HttpResponse {
+            HttpResponse::Ok().finish()
+        }
+
+        let app = App::new().route("/", web::put().to(handler));
+        let rmap = init_resource_map(app).await;
+        let doc = OpenApi::new("Test", "0.0.0").document(&rmap);
+        let op = &doc["paths"]["/"]["put"];
+
+        assert_eq!(op["requestBody"]["required"], false);
+        assert_eq!(op["parameters"][0]["name"], "q");
+        assert_eq!(op["parameters"][0]["required"], false);
+        assert_eq!(
+            op["parameters"][2],
+            json!({
+                "name": "content-type",
+                "in": "header",
+                "required": true,
+                "schema": { "type": "string" },
+            })
+        );
+        assert_eq!(
+            op["responses"],
+            json!({ "default": { "description": "Default response" } })
+        );
+    }
+
+    #[actix_rt::test]
+    async fn typed_segments() {
+        async fn handler(_: web::Path<(String, u8)>) -> HttpResponse {
+            HttpResponse::Ok().finish()
+        }
+
+        let app = App::new()
+            .segment_matcher("lang", crate::dev::SegmentMatcher::new("en|fr"))
+            .route(
+                "/{lang:lang}/{id:uuid}/{page:u32}",
+                web::get().to(HttpResponse::Ok),
+            )
+            .route("/{name:slug}/{num:i64}", web::get().to(handler));
+
+        let rmap = init_resource_map(app).await;
+        let doc = OpenApi::new("Test", "0.0.0").document(&rmap);
+
+        let params = &doc["paths"]["/{lang}/{id}/{page}"]["get"]["parameters"];
+        assert_eq!(params[0]["schema"], json!({ "type": "string" }));
+        assert_eq!(
+            params[1]["schema"],
+            json!({ "type": "string", "format": "uuid" })
+        );
+        assert_eq!(
+            params[2]["schema"],
+            json!({ "type": "integer", "format": "int64", "minimum": 0 })
+        );
+
+        // extracted types take precedence over matchers
+        let params = &doc["paths"]["/{name}/{num}"]["get"]["parameters"];
+        assert_eq!(params[0]["schema"], json!({ "type": "string" }));
+        assert_eq!(
+            params[1]["schema"],
+            json!({ "type": "integer", "format": "int32", "minimum": 0 })
+        );
+    }
+
+    #[actix_rt::test]
+    async fn recursive_extractors() {
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct Node {
+            name: String,
+            children: Vec<Node>,
+        }
+
+        async fn handler(_: web::Json<Node>) -> HttpResponse {
+            HttpResponse::Ok().finish()
+        }
+
+        let app = App::new().route("/", web::post().to(handler));
+        let rmap = init_resource_map(app).await;
+        let doc = OpenApi::new("Test", "0.0.0").document(&rmap);
+
+        assert_eq!(
+            doc["paths"]["/"]["post"]["requestBody"]["content"]["application/json"]["schema"],
+            json!({
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "children": { "type": "array", "items": {} },
+                },
+                "required": ["name", "children"],
+            })
+        );
+    }
+
+    #[actix_rt::test]
+    async fn deferred_schemas() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static TRACED: AtomicUsize = AtomicUsize::new(0);
+
+        struct Traced;
+
+        impl<'de> Deserialize<'de> for Traced {
+            fn deserialize<D: serde::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+                TRACED.fetch_add(1, Ordering::SeqCst);
+                serde::de::IgnoredAny::deserialize(de)?;
+                Ok(Traced)
+            }
+        }
+
+        async fn handler(_: web::Json<Traced>) -> HttpResponse {
+            HttpResponse::Ok().finish()
+        }
+
+        let app = App::new().route(
+            "/",
+            web::post().to(handler).operation(|op| {
+                op.summary("Create");
+            }),
+        );
+        let rmap = init_resource_map(app).await;
+        assert_eq!(TRACED.load(Ordering::SeqCst), 0);
+
+        let doc = OpenApi::new("Test", "0.0.0").document(&rmap);
+        assert_eq!(TRACED.load(Ordering::SeqCst), 1);
+        assert_eq!(doc["paths"]["/"]["post"]["summary"], "Create");
+        assert!(doc["paths"]["/"]["post"]["requestBody"].is_object());
+    }
+
+    #[actix_rt::test]
+    async fn serve_document() {
+        let srv = init_service(
+            App::new()
+                .service(OpenApi::new("Test", "0.0.0").path("/api.json"))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = TestRequest::get().uri("/api.json").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let doc: Value = read_body_json(res).await;
+        assert_eq!(doc["info"]["title"], "Test");
+        assert_eq!(
+            doc["paths"].as_object().unwrap().keys().collect::<Vec<_>>(),
+            ["/"]
+        );
+    }
+}
@@ -0,0 +1,685 @@
+//! Discovery of the structure of types from their `Deserialize` implementations.
+//!
+//! A type is traced by deserializing it from a [`Tracer`], which records the data model calls
+//! made by the type's `Deserialize` implementation and feeds it placeholder values. Types that
+//! reject the placeholders (for example, a UUID rejecting an empty string) are described up to
+//! the point of failure. Recursive types are described down to their first recursive occurrence,
+//! which is left [unknown](Shape::Unknown).
+
+use std::{cell::RefCell, error::Error as StdError, fmt};
+
+use serde::de::{
+    self, value::StrDeserializer, DeserializeOwned, DeserializeSeed, Deserializer, EnumAccess,
+    IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor,
+};
+use serde_json::{json, Map, Value};
+
+/// Structure of a type, as observed by driving its `Deserialize` implementation.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Shape {
+    /// Nothing is known about the type.
+    Unknown,
+
+    /// A primitive type, described by its schema.
+    Primitive(Value),
+
+    /// An optional value.
+    Nullable(Box<Shape>),
+
+    /// A sequence of values of the same type.
+    Array(Box<Shape>),
+
+    /// A fixed length sequence of values, such as a tuple.
+    Tuple(Vec<Shape>),
+
+    /// A map with arbitrary keys.
+    Map(Box<Shape>),
+
+    /// A struct with named fields.
+    Struct(Vec<(&'static str, Shape)>),
+
+    /// An enum, described by the names of its variants.
+    Enum(&'static [&'static str]),
+}
+
+impl Shape {
+    /// Traces the structure of `T`.
+    pub(crate) fn of<T: DeserializeOwned>() -> Self {
+        let mut shape = Shape::Unknown;
+        let path = RefCell::new(Vec::new());
+        // on failure, the shape describes everything traced up to the failing value
+        let _ = T::deserialize(Tracer::new(&mut shape, &path));
+        shape
+    }
+
+    /// Returns `true` if values of this shape may be omitted.
+    pub(crate) fn is_optional(&self) -> bool {
+        matches!(self, Shape::Nullable(_))
+    }
+
+    /// Converts the shape to an OpenAPI schema object.
+    pub(crate) fn to_schema(&self) -> Value {
+        match self {
+            Shape::Unknown => json!({}),
+
+            Shape::Primitive(schema) => schema.clone(),
+
+            Shape::Nullable(inner) => {
+                let mut schema = inner.to_schema();
+                if let Value::Object(map) = &mut schema {
+                    map.insert("nullable".to_owned(), Value::Bool(true));
+                }
+                schema
+            }
+
+            Shape::Array(item) => json!({ "type": "array", "items": item.to_schema() }),
+
+            Shape::Tuple(items) => {
+                let mut schemas = items.iter().map(Shape::to_schema);
+                let first = schemas.next().unwrap_or_else(|| json!({}));
+                let items = if schemas.all(|schema| schema == first) {
+                    first
+                } else {
+                    json!({})
+                };
+
+                json!({
+                    "type": "array",
+                    "items": items,
+                    "minItems": self.len(),
+                    "maxItems": self.len(),
+                })
+            }
+
+            Shape::Map(value) => {
+                json!({ "type": "object", "additionalProperties": value.to_schema() })
+            }
+
+            Shape::Struct(fields) => {
+                let properties = fields
+                    .iter()
+                    .map(|(name, shape)| ((*name).to_owned(), shape.to_schema()))
+                    .collect::<Map<_, _>>();
+
+                let required = fields
+                    .iter()
+                    .filter(|(_, shape)| !shape.is_optional())
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>();
+
+                let mut schema = json!({ "type": "object", "properties": properties });
+                if !required.is_empty() {
+                    schema["required"] = json!(required);
+                }
+                schema
+            }
+
+            Shape::Enum(variants) => json!({ "type": "string", "enum": variants }),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Shape::Tuple(items) => items.len(),
+            _ => 1,
+        }
+    }
+}
+
+/// Maximum nesting depth of traced values.
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug)]
+pub(crate) enum TraceError {
+    /// Error raised by the traced type.
+    Custom(String),
+
+    /// A type was found nested within itself, or values were nested too deeply.
+    Recursive,
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceError::Custom(msg) => f.write_str(msg),
+            TraceError::Recursive => f.write_str("recursive type"),
+        }
+    }
+}
+
+impl StdError for TraceError {}
+
+impl de::Error for TraceError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        TraceError::Custom(msg.to_string())
+    }
+}
+
+/// Names of the containers enclosing the value being traced, outermost first. Sequences, maps
+/// and options are unnamed.
+type Path = RefCell<Vec<&'static str>>;
+
+/// Deserializer that records the requested data model into a [`Shape`].
+struct Tracer<'a, 'p> {
+    shape: &'a mut Shape,
+    path: &'p Path,
+}
+
+impl<'a, 'p> Tracer<'a, 'p> {
+    fn new(shape: &'a mut Shape, path: &'p Path) -> Self {
+        Tracer { shape, path }
+    }
+
+    /// Traces a container named `name` with `f`, failing without calling it if the container
+    /// encloses itself or is nested too deeply.
+    ///
+    /// Types are identified by name, so generic types nested in themselves with other type
+    /// parameters are also treated as recursive.
+    fn nested<T>(
+        self,
+        name: &'static str,
+        f: impl FnOnce(&'a mut Shape, &'p Path) -> Result<T, TraceError>,
+    ) -> Result<T, TraceError> {
+        {
+            let mut path = self.path.borrow_mut();
+
+            if path.len() >= MAX_DEPTH || (!name.is_empty() && path.contains(&name)) {
+                return Err(TraceError::Recursive);
+            }
+
+            path.push(name);
+        }
+
+        let res = f(self.shape, self.path);
+        self.path.borrow_mut().pop();
+        res
+    }
+}
+
+macro_rules! trace_primitive {
+    ($($method:ident => $visit:ident($value:expr), $schema:tt;)+) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                *self.shape = Shape::Primitive(json!($schema));
+                visitor.$visit($value)
+            }
+        )+
+    };
+}
+
+impl<'de, 'a, 'p> Deserializer<'de> for Tracer<'a, 'p> {
+    type Error = TraceError;
+
+    trace_primitive! {
+        deserialize_bool => visit_bool(false), { "type": "boolean" };
+        deserialize_i8 => visit_i8(0), { "type": "integer", "format": "int32" };
+        deserialize_i16 => visit_i16(0), { "type": "integer", "format": "int32" };
+        deserialize_i32 => visit_i32(0), { "type": "integer", "format": "int32" };
+        deserialize_i64 => visit_i64(0), { "type": "integer", "format": "int64" };
+        deserialize_i128 => visit_i128(0), { "type": "integer" };
+        deserialize_u8 => visit_u8(0), { "type": "integer", "format": "int32", "minimum": 0 };
+        deserialize_u16 => visit_u16(0), { "type": "integer", "format": "int32", "minimum": 0 };
+        deserialize_u32 => visit_u32(0), { "type": "integer", "format": "int64", "minimum": 0 };
+        deserialize_u64 => visit_u64(0), { "type": "integer", "format": "int64", "minimum": 0 };
+        deserialize_u128 => visit_u128(0), { "type": "integer", "minimum": 0 };
+        deserialize_f32 => visit_f32(0.0), { "type": "number", "format": "float" };
+        deserialize_f64 => visit_f64(0.0), { "type": "number", "format": "double" };
+        deserialize_char => visit_char('\0'), { "type": "string", "minLength": 1, "maxLength": 1 };
+        deserialize_str => visit_str(""), { "type": "string" };
+        deserialize_string => visit_str(""), { "type": "string" };
+        deserialize_identifier => visit_str(""), { "type": "string" };
+        deserialize_bytes => visit_bytes(&[]), { "type": "string", "format": "binary" };
+        deserialize_byte_buf => visit_bytes(&[]), { "type": "string", "format": "binary" };
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        *self.shape = Shape::Unknown;
+        visitor.visit_unit()
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.nested("", |shape, path| {
+            *shape = Shape::Nullable(Box::new(Shape::Unknown));
+            match shape {
+                Shape::Nullable(inner) => visitor.visit_some(Tracer::new(inner, path)),
+                _ => unreachable!(),
+            }
+        })
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        *self.shape = Shape::Unknown;
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.nested(name, |shape, path| {
+            visitor.visit_newtype_struct(Tracer::new(shape, path))
+        })
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.nested("", |shape, path| {
+            *shape = Shape::Array(Box::new(Shape::Unknown));
+            match shape {
+                Shape::Array(item) => visitor.visit_seq(SeqTracer {
+                    items: std::iter::once(&mut **item),
+                    path,
+                    is_variable: true,
+                }),
+                _ => unreachable!(),
+            }
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_tuple_struct("", len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.nested(name, |shape, path| {
+            *shape = Shape::Tuple(vec![Shape::Unknown; len]);
+            match shape {
+                Shape::Tuple(items) => visitor.visit_seq(SeqTracer {
+                    items: items.iter_mut(),
+                    path,
+                    is_variable: false,
+                }),
+                _ => unreachable!(),
+            }
+        })
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.nested("", |shape, path| {
+            *shape = Shape::Map(Box::new(Shape::Unknown));
+            match shape {
+                Shape::Map(value) => visitor.visit_map(MapTracer {
+                    value: Some(value),
+                    key: Shape::Unknown,
+                    path,
+                }),
+                _ => unreachable!(),
+            }
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.nested(name, |shape, path| {
+            *shape = Shape::Struct(fields.iter().map(|&name| (name, Shape::Unknown)).collect());
+            match shape {
+                Shape::Struct(fields) => visitor.visit_map(StructTracer {
+                    fields: fields.iter_mut(),
+                    value: None,
+                    path,
+                }),
+                _ => unreachable!(),
+            }
+        })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.nested(name, |shape, path| {
+            *shape = Shape::Enum(variants);
+
+            let variant = variants
+                .first()
+                .ok_or_else(|| de::Error::custom("enum has no variants"))?;
+
+            visitor.visit_enum(EnumTracer { variant, path })
+        })
+    }
+}
+
+/// Yields one element for each shape produced by the iterator.
+struct SeqTracer<'p, I> {
+    items: I,
+    path: &'p Path,
+    /// Whether the sequence may be shorter than the number of shapes.
+    is_variable: bool,
+}
+
+impl<'de, 'a, 'p, I> SeqAccess<'de> for SeqTracer<'p, I>
+where
+    I: Iterator<Item = &'a mut Shape>,
+{
+    type Error = TraceError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        let shape = match self.items.next() {
+            Some(shape) => shape,
+            None => return Ok(None),
+        };
+
+        match seed.deserialize(Tracer::new(shape, self.path)) {
+            Ok(value) => Ok(Some(value)),
+            // a recursive sequence ends before its first element, letting its parent complete
+            Err(TraceError::Recursive) if self.is_variable => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Yields a single map entry.
+struct MapTracer<'a, 'p> {
+    value: Option<&'a mut Shape>,
+    key: Shape,
+    path: &'p Path,
+}
+
+impl<'de, 'a, 'p> MapAccess<'de> for MapTracer<'a, 'p> {
+    type Error = TraceError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        if self.value.is_none() {
+            return Ok(None);
+        }
+
+        seed.deserialize(Tracer::new(&mut self.key, self.path))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let shape = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value requested before key"))?;
+
+        seed.deserialize(Tracer::new(shape, self.path))
+    }
+}
+
+/// Yields an entry for each field of a struct.
+struct StructTracer<'a, 'p> {
+    fields: std::slice::IterMut<'a, (&'static str, Shape)>,
+    value: Option<&'a mut Shape>,
+    path: &'p Path,
+}
+
+impl<'de, 'a, 'p> MapAccess<'de> for StructTracer<'a, 'p> {
+    type Error = TraceError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.fields.next() {
+            Some((name, shape)) => {
+                self.value = Some(shape);
+                let name: StrDeserializer<'_, TraceError> = name.into_deserializer();
+                seed.deserialize(name).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let shape = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value requested before key"))?;
+
+        seed.deserialize(Tracer::new(shape, self.path))
+    }
+}
+
+/// Selects the given variant of an enum.
+struct EnumTracer<'p> {
+    variant: &'static str,
+    path: &'p Path,
+}
+
+impl<'de, 'p> EnumAccess<'de> for EnumTracer<'p> {
+    type Error = TraceError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Self::Error> {
+        let name: StrDeserializer<'_, TraceError> = self.variant.into_deserializer();
+        Ok((seed.deserialize(name)?, self))
+    }
+}
+
+impl<'de, 'p> VariantAccess<'de> for EnumTracer<'p> {
+    type Error = TraceError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, Self::Error> {
+        seed.deserialize(Tracer::new(&mut Shape::Unknown, self.path))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        Tracer::new(&mut Shape::Unknown, self.path).deserialize_tuple(len, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        Tracer::new(&mut Shape::Unknown, self.path).deserialize_struct("", fields, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    #[serde(rename_all = "lowercase")]
+    enum Kind {
+        Cat,
+        Dog,
+    }
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Pet {
+        id: u64,
+        name: String,
+        kind: Kind,
+        tags: Vec<String>,
+        owner: Option<Owner>,
+        attributes: HashMap<String, f64>,
+    }
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Owner {
+        #[serde(rename = "fullName")]
+        full_name: String,
+    }
+
+    #[test]
+    fn primitives() {
+        assert_eq!(
+            Shape::of::<u32>(),
+            Shape::Primitive(json!({ "type": "integer", "format": "int64", "minimum": 0 }))
+        );
+        assert_eq!(
+            Shape::of::<String>().to_schema(),
+            json!({ "type": "string" })
+        );
+        assert_eq!(
+            Shape::of::<Option<bool>>().to_schema(),
+            json!({ "type": "boolean", "nullable": true })
+        );
+        assert_eq!(Shape::of::<serde_json::Value>(), Shape::Unknown);
+    }
+
+    #[test]
+    fn tuples() {
+        let shape = Shape::of::<(u64, String)>();
+        match &shape {
+            Shape::Tuple(items) => assert_eq!(items.len(), 2),
+            _ => panic!("expected a tuple, got {:?}", shape),
+        }
+
+        assert_eq!(
+            shape.to_schema(),
+            json!({ "type": "array", "items": {}, "minItems": 2, "maxItems": 2 })
+        );
+    }
+
+    #[test]
+    fn structs() {
+        assert_eq!(
+            Shape::of::<Pet>().to_schema(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "id": { "type": "integer", "format": "int64", "minimum": 0 },
+                    "name": { "type": "string" },
+                    "kind": { "type": "string", "enum": ["cat", "dog"] },
+                    "tags": { "type": "array", "items": { "type": "string" } },
+                    "owner": {
+                        "type": "object",
+                        "properties": { "fullName": { "type": "string" } },
+                        "required": ["fullName"],
+                        "nullable": true,
+                    },
+                    "attributes": {
+                        "type": "object",
+                        "additionalProperties": { "type": "number", "format": "double" },
+                    },
+                },
+                "required": ["id", "name", "kind", "tags", "attributes"],
+            })
+        );
+    }
+
+    #[test]
+    fn rejected_placeholder() {
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct Strict {
+            id: u64,
+            #[serde(deserialize_with = "reject")]
+            code: String,
+            name: String,
+        }
+
+        fn reject<'de, D: Deserializer<'de>>(de: D) -> Result<String, D::Error> {
+            let code = String::deserialize(de)?;
+            Err(de::Error::custom(format!("invalid code {:?}", code)))
+        }
+
+        // fields after the one that failed are known by name only
+        assert_eq!(
+            Shape::of::<Strict>().to_schema(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "id": { "type": "integer", "format": "int64", "minimum": 0 },
+                    "code": { "type": "string" },
+                    "name": {},
+                },
+                "required": ["id", "code", "name"],
+            })
+        );
+    }
+
+    #[test]
+    fn recursive_types() {
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct Node {
+            name: String,
+            children: Vec<Node>,
+            parent: Option<Box<Node>>,
+            depth: u32,
+        }
+
+        // recursive sequences are empty and recursive options unknown; fields after a recursive
+        // option are known by name only
+        assert_eq!(
+            Shape::of::<Node>().to_schema(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "children": { "type": "array", "items": {} },
+                    "parent": { "nullable": true },
+                    "depth": {},
+                },
+                "required": ["name", "children", "depth"],
+            })
+        );
+
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct Tree(Vec<Tree>);
+
+        assert_eq!(
+            Shape::of::<Tree>().to_schema(),
+            json!({ "type": "array", "items": {} })
+        );
+    }
+}
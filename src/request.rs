@@ -13,6 +13,7 @@ use actix_router::{Path, Url};
 use actix_utils::future::{ok, Ready};
 #[cfg(feature = "cookies")]
 use cookie::{Cookie, ParseError as CookieParseError};
+use serde::Serialize;
 use smallvec::SmallVec;
 
 use crate::{
@@ -37,7 +38,7 @@ pub(crate) struct HttpRequestInner {
     pub(crate) head: Message<RequestHead>,
     pub(crate) path: Path<Url>,
     pub(crate) app_data: SmallVec<[Rc<Extensions>; 4]>,
-    app_state: Rc<AppInitServiceState>,
+    pub(crate) app_state: Rc<AppInitServiceState>,
 }
 
 impl HttpRequest {
@@ -187,6 +188,52 @@ impl HttpRequest {
         self.resource_map().url_for(self, name, elements)
     }
 
+    /// Generate url for named resource from serializable parameters.
+    ///
+    /// This is the inverse of extracting [`web::Path`](crate::web::Path) parameters. Fields of a
+    /// struct or entries of a map fill the dynamic segments with the same names and any leftover
+    /// fields are appended to the URL as a query string. Elements of a tuple, or a single value,
+    /// fill dynamic segments in order, like [`url_for`](Self::url_for). Fields with `None` values
+    /// are skipped. Segment values are percent-encoded, so they may contain characters such as `/`
+    /// and `?`.
+    ///
+    /// Segments without a value are reported with [`UrlGenerationError::MissingParams`] and
+    /// positional values beyond the last segment with [`UrlGenerationError::ExtraParams`].
+    ///
+    /// ```
+    /// # use actix_web::{web, App, HttpRequest, HttpResponse};
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct PostParams<'a> {
+    ///     user_id: u64,
+    ///     title: &'a str,
+    ///     page: Option<u32>,
+    /// }
+    ///
+    /// fn index(req: HttpRequest) -> HttpResponse {
+    ///     let params = PostParams { user_id: 42, title: "hello", page: Some(2) };
+    ///     // <- generates "/user/42/post/hello?page=2"
+    ///     let url = req.url_for_with("post", &params);
+    ///     HttpResponse::Ok().into()
+    /// }
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .service(web::resource("/user/{user_id}/post/{title}")
+    ///              .name("post")
+    ///              .route(web::get().to(|| HttpResponse::Ok()))
+    ///         );
+    /// }
+    /// ```
+    pub fn url_for_with<T: Serialize>(
+        &self,
+        name: &str,
+        params: &T,
+    ) -> Result<url::Url, UrlGenerationError> {
+        self.resource_map().url_for_with(self, name, params)
+    }
+
     /// Generate url for named resource
     ///
     /// This method is similar to `HttpRequest::url_for()` but it can be used
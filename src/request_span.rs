@@ -0,0 +1,141 @@
+//! Per-request tracing spans, enabled by the `tracing` feature.
+//!
+//! Each request is handled within an `http_request` span carrying its method, target and peer
+//! address. Once the response is produced, the span records the matched route pattern, the status
+//! code and the latency, and is stored in the response extensions so that actix-http enters it
+//! again while sending the response body.
+
+use actix_service::Service;
+
+use crate::{
+    service::{ServiceRequest, ServiceResponse},
+    Error,
+};
+
+#[cfg(feature = "tracing")]
+pub use self::traced::Instrumented;
+
+/// Without the `tracing` feature, service futures are returned as is.
+#[cfg(not(feature = "tracing"))]
+pub(crate) type Instrumented<F> = F;
+
+/// Calls `service` with `req`, within a new request span.
+#[cfg(feature = "tracing")]
+pub(crate) fn call<S, B>(service: &S, req: ServiceRequest) -> Instrumented<S::Future>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    traced::call(service, req)
+}
+
+/// Calls `service` with `req`.
+#[cfg(not(feature = "tracing"))]
+#[inline]
+pub(crate) fn call<S, B>(service: &S, req: ServiceRequest) -> Instrumented<S::Future>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    service.call(req)
+}
+
+#[cfg(feature = "tracing")]
+mod traced {
+    use std::{
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll},
+        time::Instant,
+    };
+
+    use futures_core::ready;
+    use tracing_crate::{field, info_span, Span};
+
+    use super::*;
+
+    pub(crate) fn call<S, B>(service: &S, req: ServiceRequest) -> Instrumented<S::Future>
+    where
+        S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    {
+        let span = info_span!(
+            "http_request",
+            http.method = %req.method(),
+            http.target = %req.uri(),
+            http.route = field::Empty,
+            http.status_code = field::Empty,
+            peer_addr = ?req.peer_addr(),
+            latency_ms = field::Empty
+        );
+
+        let start = Instant::now();
+        let fut = span.in_scope(|| service.call(req));
+
+        Instrumented { fut, span, start }
+    }
+
+    /// Service future polled within its request span.
+    #[doc(hidden)]
+    #[pin_project::pin_project]
+    pub struct Instrumented<F> {
+        #[pin]
+        fut: F,
+        span: Span,
+        start: Instant,
+    }
+
+    impl<F, B> Future for Instrumented<F>
+    where
+        F: Future<Output = Result<ServiceResponse<B>, Error>>,
+    {
+        type Output = F::Output;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            let this = self.project();
+            let _enter = this.span.enter();
+
+            let res = ready!(this.fut.poll(cx));
+
+            let status = match res {
+                Ok(ref res) => {
+                    if let Some(pattern) = res.request().match_pattern() {
+                        this.span.record("http.route", &pattern.as_str());
+                    }
+
+                    res.status()
+                }
+                Err(ref err) => err.as_response_error().status_code(),
+            };
+
+            this.span.record("http.status_code", &status.as_u16());
+            this.span
+                .record("latency_ms", &(this.start.elapsed().as_millis() as u64));
+
+            let span = this.span.clone();
+
+            Poll::Ready(res.map(|mut res| {
+                res.response_mut().extensions_mut().insert(span);
+                res
+            }))
+        }
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use tracing_crate::Span;
+
+    use crate::{
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    #[actix_rt::test]
+    async fn stores_span_in_response() {
+        let srv =
+            test::init_service(App::new().route("/user/{id}", web::get().to(HttpResponse::Ok)))
+                .await;
+
+        let req = TestRequest::with_uri("/user/1").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert!(res.response().extensions().get::<Span>().is_some());
+    }
+}
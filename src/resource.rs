@@ -1,9 +1,9 @@
 use std::cell::RefCell;
 use std::fmt;
-use std::future::Future;
+use std::future::{ready, Future};
 use std::rc::Rc;
 
-use actix_http::Extensions;
+use actix_http::{http::Method, Extensions};
 use actix_router::{IntoPatterns, Patterns};
 use actix_service::boxed::{self, BoxService, BoxServiceFactory};
 use actix_service::{
@@ -16,10 +16,13 @@ use futures_util::future::join_all;
 use crate::{
     data::Data,
     dev::{ensure_leading_slash, AppService, HttpServiceFactory, ResourceDef},
-    guard::Guard,
+    guard::{self, Guard, HostPattern},
     handler::Handler,
+    http::header,
     responder::Responder,
+    rmap::ServiceInfo,
     route::{Route, RouteService},
+    routing_trace,
     service::{ServiceRequest, ServiceResponse},
     Error, FromRequest, HttpResponse,
 };
@@ -47,16 +50,19 @@ type HttpNewService = BoxServiceFactory<(), ServiceRequest, ServiceResponse, Err
 /// }
 /// ```
 ///
-/// If no matching route could be found, *405* response code get returned.
-/// Default behavior could be overridden with `default_resource()` method.
+/// If no matching route could be found, *405* response code get returned. When every route is
+/// restricted to specific methods, the response includes an `Allow` header listing them and
+/// `OPTIONS` requests are answered with the same header, unless a route for `OPTIONS` is
+/// registered. Default behavior could be overridden with `default_service()` method.
 pub struct Resource<T = ResourceEndpoint> {
     endpoint: T,
     rdef: Patterns,
     name: Option<String>,
+    host: Option<String>,
     routes: Vec<Route>,
     app_data: Option<Extensions>,
     guards: Vec<Box<dyn Guard>>,
-    default: HttpNewService,
+    default: Option<HttpNewService>,
     factory_ref: Rc<RefCell<Option<ResourceFactory>>>,
 }
 
@@ -68,13 +74,12 @@ impl Resource {
             routes: Vec::new(),
             rdef: path.patterns(),
             name: None,
+            host: None,
             endpoint: ResourceEndpoint::new(fref.clone()),
             factory_ref: fref,
             guards: Vec::new(),
             app_data: None,
-            default: boxed::factory(fn_service(|req: ServiceRequest| async {
-                Ok(req.into_response(HttpResponse::MethodNotAllowed()))
-            })),
+            default: None,
         }
     }
 }
@@ -125,6 +130,39 @@ where
         self
     }
 
+    /// Restricts the resource to requests for hosts matching a pattern.
+    ///
+    /// The pattern uses the same syntax as resource patterns and is matched case-insensitively
+    /// against the request's host name, without port. Dynamic segments match a single host label
+    /// unless given a custom regex or matcher. Their values are captured into the request's match info ahead of
+    /// the path segments, so they can be read with [`web::Path`](crate::web::Path) or
+    /// [`HttpRequest::match_info`].
+    ///
+    /// URLs generated for named resources with a host pattern include the host, whose segments
+    /// are filled before those of the path.
+    ///
+    /// [`HttpRequest::match_info`]: crate::HttpRequest::match_info
+    ///
+    /// ```
+    /// use actix_web::{web, App, HttpRequest};
+    ///
+    /// async fn index(req: HttpRequest, tenant: web::Path<String>) -> String {
+    ///     let url = req.url_for("index", &[tenant.as_str()]).unwrap();
+    ///     format!("Welcome to {}!", url)
+    /// }
+    ///
+    /// let app = App::new().service(
+    ///     web::resource("/")
+    ///         .host("{tenant}.example.com")
+    ///         .name("index")
+    ///         .route(web::get().to(index))
+    /// );
+    /// ```
+    pub fn host(mut self, pattern: &str) -> Self {
+        self.host = Some(pattern.to_owned());
+        self
+    }
+
     pub(crate) fn add_guards(mut self, guards: Vec<Box<dyn Guard>>) -> Self {
         self.guards.extend(guards);
         self
@@ -279,6 +317,7 @@ where
             endpoint: apply(mw, self.endpoint),
             rdef: self.rdef,
             name: self.name,
+            host: self.host,
             guards: self.guards,
             routes: self.routes,
             default: self.default,
@@ -341,6 +380,7 @@ where
             endpoint: apply_fn_factory(self.endpoint, mw),
             rdef: self.rdef,
             name: self.name,
+            host: self.host,
             guards: self.guards,
             routes: self.routes,
             default: self.default,
@@ -350,8 +390,8 @@ where
     }
 
     /// Default service to be used if no matching route could be found.
-    /// By default *405* response get returned. Resource does not use
-    /// default handler from `App` or `Scope`.
+    /// By default *405* response get returned, see [`Resource`] for details. Resource does not
+    /// use default handler from `App` or `Scope`.
     pub fn default_service<F, U>(mut self, f: F) -> Self
     where
         F: IntoServiceFactory<U, ServiceRequest>,
@@ -364,10 +404,9 @@ where
         U::InitError: fmt::Debug,
     {
         // create and configure default resource
-        self.default = boxed::factory(
-            f.into_factory()
-                .map_init_err(|e| log::error!("Can not construct default service: {:?}", e)),
-        );
+        self.default = Some(boxed::factory(f.into_factory().map_init_err(|e| {
+            log::error!("Can not construct default service: {:?}", e)
+        })));
 
         self
     }
@@ -384,6 +423,15 @@ where
         > + 'static,
 {
     fn register(mut self, config: &mut AppService) {
+        let host = self
+            .host
+            .as_deref()
+            .map(|host| HostPattern::new(host, config.segment_matchers()));
+
+        if let Some(ref host) = host {
+            self.guards.push(Box::new(host.clone()));
+        }
+
         let guards = if self.guards.is_empty() {
             None
         } else {
@@ -394,32 +442,83 @@ where
             ResourceDef::new(ensure_leading_slash(self.rdef.clone()))
         } else {
             ResourceDef::new(self.rdef.clone())
-        };
+        }
+        .with_matchers(config.segment_matchers());
 
         if let Some(ref name) = self.name {
             rdef.set_name(name);
         }
 
+        let info = ServiceInfo {
+            methods: guard::any_methods(self.routes.iter().map(Route::methods)),
+            operations: self
+                .routes
+                .iter()
+                .filter_map(Route::documented_operation)
+                .collect(),
+            host: host.clone(),
+        };
+
+        let default = match self.default {
+            Some(default) => default,
+            None => method_not_allowed(info.methods.clone()),
+        };
+
         *self.factory_ref.borrow_mut() = Some(ResourceFactory {
             routes: self.routes,
-            default: self.default,
+            default,
         });
 
         let resource_data = self.app_data.map(Rc::new);
 
-        // wraps endpoint service (including middleware) call and injects app data for this scope
+        // wraps endpoint service (including middleware) call, injects app data for this resource
+        // and captures host segments
         let endpoint = apply_fn_factory(self.endpoint, move |mut req: ServiceRequest, srv| {
             if let Some(ref data) = resource_data {
                 req.add_data_container(Rc::clone(data));
             }
 
+            if let Some(ref host) = host {
+                host.capture(&mut req);
+            }
+
             srv.call(req)
         });
 
-        config.register_service(rdef, guards, endpoint, None)
+        config.register_service_with_info(rdef, guards, endpoint, None, info)
     }
 }
 
+/// Constructs the built-in default service of a resource whose routes accept `methods`.
+///
+/// Responds with *405 Method Not Allowed* including an `Allow` header when the methods are known.
+/// Unless a route accepts `OPTIONS`, these requests are answered with the `Allow` header instead.
+fn method_not_allowed(methods: Option<Vec<Method>>) -> HttpNewService {
+    let allowed = methods.map(|mut methods| {
+        let auto_options = !methods.contains(&Method::OPTIONS);
+        if auto_options {
+            methods.push(Method::OPTIONS);
+        }
+        (header::Allow(methods), auto_options)
+    });
+
+    boxed::factory(fn_service(move |req: ServiceRequest| {
+        let res = match &allowed {
+            Some((allow, true)) if req.method() == Method::OPTIONS => {
+                HttpResponse::Ok().insert_header(allow.clone()).finish()
+            }
+            Some((allow, _)) => {
+                let mut res = HttpResponse::MethodNotAllowed();
+                res.insert_header(allow.clone());
+                routing_trace::respond(&req, res)
+            }
+            None => routing_trace::respond(&req, HttpResponse::MethodNotAllowed()),
+        };
+
+        ready(Ok(req.into_response(res)))
+    }))
+}
+
 pub struct ResourceFactory {
     routes: Vec<Route>,
     default: HttpNewService,
@@ -465,13 +564,10 @@ impl Service<ServiceRequest> for ResourceService {
     actix_service::always_ready!();
 
     fn call(&self, mut req: ServiceRequest) -> Self::Future {
-        for route in &self.routes {
-            if route.check(&mut req) {
-                return route.call(req);
-            }
+        match routing_trace::select_route(&self.routes, &mut req) {
+            Some(route) => route.call(req),
+            None => self.default.call(req),
         }
-
-        self.default.call(req)
     }
 }
 
@@ -640,6 +736,123 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
 
+    #[actix_rt::test]
+    async fn test_allowed_methods() {
+        let srv = init_service(
+            App::new()
+                .service(
+                    web::resource("/test")
+                        .route(web::get().to(HttpResponse::Ok))
+                        .route(
+                            web::route()
+                                .guard(guard::Any(guard::Put()).or(guard::Patch()))
+                                .guard(guard::Header("x-test", "1"))
+                                .to(HttpResponse::Ok),
+                        ),
+                )
+                .service(web::resource("/any").to(HttpResponse::Ok))
+                .service(
+                    web::resource("/guarded")
+                        .route(web::get().to(HttpResponse::Ok))
+                        .route(
+                            web::route()
+                                .to(HttpResponse::Ok)
+                                .guard(guard::Header("x", "y")),
+                        ),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/test")
+            .method(Method::DELETE)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(
+            resp.headers().get(header::ALLOW).unwrap(),
+            "GET, PUT, PATCH, OPTIONS"
+        );
+
+        // allowed method with failing guards
+        let req = TestRequest::with_uri("/test")
+            .method(Method::PUT)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert!(resp.headers().contains_key(header::ALLOW));
+
+        let req = TestRequest::with_uri("/test")
+            .method(Method::OPTIONS)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::ALLOW).unwrap(),
+            "GET, PUT, PATCH, OPTIONS"
+        );
+
+        let req = TestRequest::with_uri("/any")
+            .method(Method::OPTIONS)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(!resp.headers().contains_key(header::ALLOW));
+
+        // allowed methods are unknown when a route accepts any method
+        let req = TestRequest::with_uri("/guarded")
+            .method(Method::POST)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert!(!resp.headers().contains_key(header::ALLOW));
+    }
+
+    #[actix_rt::test]
+    async fn test_options_route() {
+        let srv = init_service(
+            App::new()
+                .service(
+                    web::resource("/test")
+                        .route(web::get().to(HttpResponse::Ok))
+                        .route(
+                            web::route()
+                                .method(Method::OPTIONS)
+                                .guard(guard::Header("x-test", "1"))
+                                .to(HttpResponse::NoContent),
+                        ),
+                )
+                .service(
+                    web::resource("/default")
+                        .route(web::get().to(HttpResponse::Ok))
+                        .default_service(web::to(HttpResponse::BadRequest)),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/test")
+            .method(Method::OPTIONS)
+            .insert_header(("x-test", "1"))
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+        // registered OPTIONS route disables automatic responses
+        let req = TestRequest::with_uri("/test")
+            .method(Method::OPTIONS)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(resp.headers().get(header::ALLOW).unwrap(), "GET, OPTIONS");
+
+        // custom default service handles everything else
+        let req = TestRequest::with_uri("/default")
+            .method(Method::OPTIONS)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert!(!resp.headers().contains_key(header::ALLOW));
+    }
+
     #[actix_rt::test]
     async fn test_resource_guards() {
         let srv = init_service(
@@ -6,7 +6,7 @@ use actix_http::{
 };
 use bytes::{Bytes, BytesMut};
 
-use crate::{Error, HttpRequest, HttpResponse, HttpResponseBuilder};
+use crate::{openapi::Operation, Error, HttpRequest, HttpResponse, HttpResponseBuilder};
 
 /// Trait implemented by types that can be converted to an HTTP response.
 ///
@@ -15,6 +15,18 @@ pub trait Responder {
     /// Convert self to `HttpResponse`.
     fn respond_to(self, req: &HttpRequest) -> HttpResponse;
 
+    /// Describe the responses of this type in an OpenAPI [`Operation`].
+    ///
+    /// Called when a handler returning this type is registered. By default, nothing is described.
+    ///
+    /// See [`openapi`](crate::openapi) for details.
+    #[allow(unused_variables)]
+    fn schema(operation: &mut Operation)
+    where
+        Self: Sized,
+    {
+    }
+
     /// Override a status code for a Responder.
     ///
     /// ```
@@ -93,6 +105,11 @@ impl<T: Responder> Responder for Option<T> {
             None => HttpResponse::new(StatusCode::NOT_FOUND),
         }
     }
+
+    fn schema(operation: &mut Operation) {
+        T::schema(operation);
+        operation.response(StatusCode::NOT_FOUND, "Not Found");
+    }
 }
 
 impl<T, E> Responder for Result<T, E>
@@ -106,6 +123,10 @@ where
             Err(e) => HttpResponse::from_error(e.into()),
         }
     }
+
+    fn schema(operation: &mut Operation) {
+        T::schema(operation);
+    }
 }
 
 impl<T: Responder> Responder for (T, StatusCode) {
@@ -114,31 +135,51 @@ impl<T: Responder> Responder for (T, StatusCode) {
         *res.status_mut() = self.1;
         res
     }
+
+    fn schema(operation: &mut Operation) {
+        T::schema(operation);
+    }
 }
 
 macro_rules! impl_responder {
-    ($res: ty, $ct: path) => {
+    ($res: ty, $ct: path, $schema: tt) => {
         impl Responder for $res {
             fn respond_to(self, _: &HttpRequest) -> HttpResponse {
                 HttpResponse::Ok().content_type($ct).body(self)
             }
+
+            fn schema(operation: &mut Operation) {
+                operation.response_content(StatusCode::OK, $ct, serde_json::json!($schema));
+            }
         }
     };
 }
 
-impl_responder!(&'static str, mime::TEXT_PLAIN_UTF_8);
+impl_responder!(&'static str, mime::TEXT_PLAIN_UTF_8, { "type": "string" });
 
-impl_responder!(String, mime::TEXT_PLAIN_UTF_8);
+impl_responder!(String, mime::TEXT_PLAIN_UTF_8, { "type": "string" });
 
-impl_responder!(&'_ String, mime::TEXT_PLAIN_UTF_8);
+impl_responder!(&'_ String, mime::TEXT_PLAIN_UTF_8, { "type": "string" });
 
-impl_responder!(Cow<'_, str>, mime::TEXT_PLAIN_UTF_8);
+impl_responder!(Cow<'_, str>, mime::TEXT_PLAIN_UTF_8, { "type": "string" });
 
-impl_responder!(&'static [u8], mime::APPLICATION_OCTET_STREAM);
+impl_responder!(
+    &'static [u8],
+    mime::APPLICATION_OCTET_STREAM,
+    { "type": "string", "format": "binary" }
+);
 
-impl_responder!(Bytes, mime::APPLICATION_OCTET_STREAM);
+impl_responder!(
+    Bytes,
+    mime::APPLICATION_OCTET_STREAM,
+    { "type": "string", "format": "binary" }
+);
 
-impl_responder!(BytesMut, mime::APPLICATION_OCTET_STREAM);
+impl_responder!(
+    BytesMut,
+    mime::APPLICATION_OCTET_STREAM,
+    { "type": "string", "format": "binary" }
+);
 
 /// Allows overriding status code and headers for a responder.
 pub struct CustomResponder<T> {
@@ -224,6 +265,10 @@ impl<T: Responder> Responder for CustomResponder<T> {
 
         res
     }
+
+    fn schema(operation: &mut Operation) {
+        T::schema(operation);
+    }
 }
 
 #[cfg(test)]
@@ -1,13 +1,34 @@
 use std::cell::RefCell;
 use std::rc::{Rc, Weak};
 
+use actix_http::http::{Method, Uri};
 use actix_router::ResourceDef;
 use ahash::AHashMap;
-use url::Url;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use serde::Serialize;
+use serde_json::Value;
+use url::{form_urlencoded, Url};
 
 use crate::error::UrlGenerationError;
+use crate::guard::{self, HostPattern};
+use crate::openapi::Operation;
 use crate::request::HttpRequest;
 
+/// Characters percent-encoded in path segment values: the URL path percent-encode set, plus the
+/// segment separator and the percent sign itself.
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'<')
+    .add(b'>')
+    .add(b'`')
+    .add(b'?')
+    .add(b'{')
+    .add(b'}')
+    .add(b'/')
+    .add(b'%');
+
 #[derive(Clone, Debug)]
 pub struct ResourceMap {
     pattern: ResourceDef,
@@ -20,6 +41,18 @@ pub struct ResourceMap {
 
     /// Must be `None` for "edge" nodes.
     nodes: Option<Vec<Rc<ResourceMap>>>,
+
+    /// Methods accepted by this node's guards and routes; `None` if any method is accepted.
+    methods: Option<Vec<Method>>,
+
+    /// Operations of the routes of an "edge" node.
+    operations: Vec<Operation>,
+
+    /// Host pattern that requests for this node must match.
+    host: Option<HostPattern>,
+
+    /// Whether a custom default service is registered for this _container_ node.
+    has_default: bool,
 }
 
 impl ResourceMap {
@@ -30,15 +63,46 @@ impl ResourceMap {
             named: AHashMap::default(),
             parent: RefCell::new(Weak::new()),
             nodes: Some(Vec::new()),
+            methods: None,
+            operations: Vec::new(),
+            host: None,
+            has_default: false,
         }
     }
 
+    /// Records the methods accepted by this node, as reported by [`entries`](Self::entries).
+    pub(crate) fn set_methods(&mut self, methods: Option<Vec<Method>>) {
+        self.methods = methods;
+    }
+
+    /// Records the host pattern of this container node.
+    pub(crate) fn set_host(&mut self, host: Option<HostPattern>) {
+        self.host = host;
+    }
+
+    /// Records that a custom default service is registered for this container node.
+    pub(crate) fn set_has_default(&mut self, has_default: bool) {
+        self.has_default = has_default;
+    }
+
     /// Adds a (possibly nested) resource.
     ///
     /// To add a non-prefix pattern, `nested` must be `None`.
     /// To add external resource, supply a pattern without a leading `/`.
     /// The root pattern of `nested`, if present, should match `pattern`.
     pub fn add(&mut self, pattern: &mut ResourceDef, nested: Option<Rc<ResourceMap>>) {
+        self.add_service(pattern, nested, ServiceInfo::default())
+    }
+
+    /// Adds a (possibly nested) resource, recording the routing details of edge nodes.
+    ///
+    /// Nested maps keep the details they were constructed with.
+    pub(crate) fn add_service(
+        &mut self,
+        pattern: &mut ResourceDef,
+        nested: Option<Rc<ResourceMap>>,
+        info: ServiceInfo,
+    ) {
         pattern.set_id(self.nodes.as_ref().unwrap().len() as u16);
 
         if let Some(new_node) = nested {
@@ -51,6 +115,10 @@ impl ResourceMap {
                 named: AHashMap::default(),
                 parent: RefCell::new(Weak::new()),
                 nodes: None,
+                methods: info.methods,
+                operations: info.operations,
+                host: info.host,
+                has_default: false,
             });
 
             if let Some(name) = pattern.name() {
@@ -91,10 +159,24 @@ impl ResourceMap {
     {
         let mut elements = elements.into_iter();
 
-        let path = self
+        let node = self
             .named
             .get(name)
-            .ok_or(UrlGenerationError::ResourceNotFound)?
+            .ok_or(UrlGenerationError::ResourceNotFound)?;
+
+        // host segments come first in generated URLs
+        let host = match node.host_pattern() {
+            Some(host) => {
+                let mut acc = String::new();
+                if !host.rdef().resource_path_from_iter(&mut acc, &mut elements) {
+                    return Err(UrlGenerationError::NotEnoughElements);
+                }
+                Some(acc)
+            }
+            None => None,
+        };
+
+        let path = node
             .root_rmap_fn(String::with_capacity(24), |mut acc, node| {
                 node.pattern
                     .resource_path_from_iter(&mut acc, &mut elements)
@@ -104,10 +186,14 @@ impl ResourceMap {
 
         if path.starts_with('/') {
             let conn = req.connection_info();
+            let host = match host {
+                Some(host) => with_port(host, conn.host()),
+                None => conn.host().to_owned(),
+            };
             Ok(Url::parse(&format!(
                 "{}://{}{}",
                 conn.scheme(),
-                conn.host(),
+                host,
                 path
             ))?)
         } else {
@@ -115,6 +201,75 @@ impl ResourceMap {
         }
     }
 
+    /// Generate url for named resource from serializable parameters.
+    ///
+    /// Check [`HttpRequest::url_for_with`] for detailed information.
+    pub fn url_for_with<T: Serialize>(
+        &self,
+        req: &HttpRequest,
+        name: &str,
+        params: &T,
+    ) -> Result<Url, UrlGenerationError> {
+        let node = self
+            .named
+            .get(name)
+            .ok_or(UrlGenerationError::ResourceNotFound)?;
+
+        // dynamic segments, in the order `url_for` consumes elements
+        let mut segments = node
+            .host_pattern()
+            .map(|host| host.rdef().segment_names().map(str::to_owned).collect())
+            .unwrap_or_else(Vec::new);
+        node.root_rmap_fn((), |(), node| {
+            segments.extend(node.pattern.segment_names().map(str::to_owned));
+            Some(())
+        });
+
+        let (elements, query) = match UrlParams::from_serialize(params)? {
+            UrlParams::Named(mut fields) => {
+                let mut missing = Vec::new();
+                let mut elements = Vec::with_capacity(segments.len());
+
+                for segment in &segments {
+                    match fields.iter().find(|(name, _)| name == segment) {
+                        Some((_, value)) => elements.push(encode_segment(value)),
+                        None => missing.push(segment.clone()),
+                    }
+                }
+
+                if !missing.is_empty() {
+                    return Err(UrlGenerationError::MissingParams(missing));
+                }
+
+                fields.retain(|(name, _)| !segments.contains(name));
+                (elements, fields)
+            }
+
+            UrlParams::Positional(mut values) => {
+                if values.len() < segments.len() {
+                    let missing = segments.split_off(values.len());
+                    return Err(UrlGenerationError::MissingParams(missing));
+                }
+
+                let extra = values.split_off(segments.len());
+                if !extra.is_empty() {
+                    return Err(UrlGenerationError::ExtraParams(extra));
+                }
+
+                let elements = values.iter().map(|value| encode_segment(value)).collect();
+                (elements, Vec::new())
+            }
+        };
+
+        let mut url = self.url_for(req, name, &elements)?;
+
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(&query);
+        }
+
+        Ok(url)
+    }
+
     pub fn has_resource(&self, path: &str) -> bool {
         self.find_matching_node(path).is_some()
     }
@@ -137,6 +292,156 @@ impl ResourceMap {
         )
     }
 
+    /// Returns an iterator over all resources registered within this map, in registration order.
+    ///
+    /// Resources of nested scopes are included, with patterns joined to those of their scopes.
+    /// Custom default services of the app and its scopes are listed after the resources they
+    /// fall back from. External resources are not included.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{http::Method, test, web, App, HttpResponse};
+    ///
+    /// # actix_web::rt::System::new().block_on(async {
+    /// let app = App::new()
+    ///     .service(web::resource("/").name("index").to(HttpResponse::Ok))
+    ///     .service(
+    ///         web::scope("/api")
+    ///             .route("/users", web::get().to(HttpResponse::Ok))
+    ///             .route("/users", web::post().to(HttpResponse::Created)),
+    ///     );
+    ///
+    /// let rmap = test::init_resource_map(app).await;
+    /// let entries = rmap.entries().collect::<Vec<_>>();
+    ///
+    /// assert_eq!(entries[0].pattern(), "/");
+    /// assert_eq!(entries[0].name(), Some("index"));
+    /// assert_eq!(entries[0].methods(), None);
+    ///
+    /// assert_eq!(entries[1].pattern(), "/api/users");
+    /// assert_eq!(entries[1].methods(), Some(&[Method::GET][..]));
+    /// assert_eq!(entries[1].scopes(), ["/api"]);
+    ///
+    /// assert_eq!(entries[2].pattern(), "/api/users");
+    /// assert_eq!(entries[2].methods(), Some(&[Method::POST][..]));
+    /// # });
+    /// ```
+    pub fn entries(&self) -> impl Iterator<Item = ResourceEntry> {
+        let mut entries = Vec::new();
+        self.collect_entries(&[String::new()], false, &Inherited::default(), &mut entries);
+        entries.into_iter()
+    }
+
+    /// Appends entries for `self` and its descendants to `entries`.
+    ///
+    /// `prefixes` are the full patterns of the parent node and `outer` the details inherited from
+    /// enclosing nodes. Container nodes are treated as scopes when `is_scope` is true.
+    fn collect_entries(
+        &self,
+        prefixes: &[String],
+        is_scope: bool,
+        outer: &Inherited,
+        entries: &mut Vec<ResourceEntry>,
+    ) {
+        let patterns = prefixes
+            .iter()
+            .flat_map(|prefix| {
+                self.pattern
+                    .pattern_iter()
+                    .map(move |pattern| [prefix.as_str(), pattern].concat())
+            })
+            .collect::<Vec<_>>();
+
+        let methods = match (&outer.methods, &self.methods) {
+            (Some(outer), Some(own)) => Some(guard::intersect_methods(outer.clone(), own)),
+            (outer, own) => own.clone().or_else(|| outer.clone()),
+        };
+
+        let mut segment_matchers = outer.segment_matchers.clone();
+        for (segment, matcher) in self.pattern.segment_matchers() {
+            if !segment_matchers.iter().any(|(name, _)| name == segment) {
+                segment_matchers.push((segment.to_owned(), matcher.to_owned()));
+            }
+        }
+
+        let host = match &self.host {
+            Some(host) => Some(host.pattern().to_owned()),
+            None => outer.host.clone(),
+        };
+
+        let nodes = match &self.nodes {
+            Some(nodes) => nodes,
+            None => {
+                let operations = self
+                    .operations
+                    .iter()
+                    .map(|op| {
+                        let mut op = op.clone();
+                        let op_methods = match (op.methods(), &methods) {
+                            (Some(own), Some(outer)) => {
+                                Some(guard::intersect_methods(own.to_vec(), outer))
+                            }
+                            (own, outer) => own.map(<[_]>::to_vec).or_else(|| outer.clone()),
+                        };
+                        op.set_methods(op_methods);
+                        op
+                    })
+                    .collect();
+
+                entries.push(ResourceEntry {
+                    patterns,
+                    name: self.pattern.name().map(ToOwned::to_owned),
+                    methods,
+                    operations,
+                    segment_matchers,
+                    host,
+                    scopes: outer.scopes.clone(),
+                    is_prefix: self.pattern.is_prefix(),
+                    is_default: false,
+                });
+                return;
+            }
+        };
+
+        let mut scopes = outer.scopes.clone();
+        if is_scope {
+            scopes.push(patterns[0].clone());
+        }
+
+        let inherited = Inherited {
+            scopes,
+            methods,
+            segment_matchers,
+            host,
+        };
+
+        for node in nodes {
+            node.collect_entries(&patterns, true, &inherited, entries);
+        }
+
+        if self.has_default {
+            entries.push(ResourceEntry {
+                patterns,
+                name: None,
+                methods: inherited.methods,
+                operations: Vec::new(),
+                segment_matchers: inherited.segment_matchers,
+                host: inherited.host,
+                scopes: inherited.scopes,
+                is_prefix: true,
+                is_default: true,
+            });
+        }
+    }
+
+    /// Returns the host pattern of this node or, if it has none, of its closest ancestor.
+    fn host_pattern(&self) -> Option<HostPattern> {
+        match &self.host {
+            Some(host) => Some(host.clone()),
+            None => self.parent.borrow().upgrade()?.host_pattern(),
+        }
+    }
+
     fn find_matching_node(&self, path: &str) -> Option<&ResourceMap> {
         self._find_matching_node(path).flatten()
     }
@@ -183,6 +488,198 @@ impl ResourceMap {
     }
 }
 
+/// Percent-encodes a value to be used as a single path segment.
+fn encode_segment(value: &str) -> String {
+    utf8_percent_encode(value, PATH_SEGMENT).to_string()
+}
+
+/// Parameters for generating the URL of a resource.
+enum UrlParams {
+    /// Values of struct fields or map entries, by name.
+    Named(Vec<(String, String)>),
+
+    /// Values of tuple elements or sequence items, in order.
+    Positional(Vec<String>),
+}
+
+impl UrlParams {
+    fn from_serialize<T: Serialize>(params: &T) -> Result<Self, UrlGenerationError> {
+        fn invalid(err: impl ToString) -> UrlGenerationError {
+            UrlGenerationError::InvalidParams(err.to_string())
+        }
+
+        fn scalar(value: Value) -> Result<String, UrlGenerationError> {
+            match value {
+                Value::String(value) => Ok(value),
+                Value::Number(value) => Ok(value.to_string()),
+                Value::Bool(value) => Ok(value.to_string()),
+                value => Err(invalid(format_args!("unsupported value `{}`", value))),
+            }
+        }
+
+        match serde_json::to_value(params).map_err(invalid)? {
+            // serialize again to keep the order of fields for the query string
+            Value::Object(_) => {
+                let query = serde_urlencoded::to_string(params).map_err(invalid)?;
+                let fields = form_urlencoded::parse(query.as_bytes())
+                    .into_owned()
+                    .collect();
+                Ok(UrlParams::Named(fields))
+            }
+
+            Value::Array(values) => values
+                .into_iter()
+                .map(scalar)
+                .collect::<Result<_, _>>()
+                .map(UrlParams::Positional),
+
+            Value::Null => Ok(UrlParams::Positional(Vec::new())),
+
+            value => Ok(UrlParams::Positional(vec![scalar(value)?])),
+        }
+    }
+}
+
+/// Routing details of a service, recorded in the resource map for introspection.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ServiceInfo {
+    /// Methods accepted by the service; `None` if any method is accepted.
+    pub(crate) methods: Option<Vec<Method>>,
+
+    /// Operations of the service's routes, each recording the methods of its route.
+    pub(crate) operations: Vec<Operation>,
+
+    /// Host pattern that requests for the service must match.
+    pub(crate) host: Option<HostPattern>,
+}
+
+/// Details of container nodes inherited by the entries of their descendants.
+#[derive(Default)]
+struct Inherited {
+    /// Full patterns of enclosing scopes.
+    scopes: Vec<String>,
+
+    /// Methods accepted by all enclosing nodes; `None` if any method is accepted.
+    methods: Option<Vec<Method>>,
+
+    /// Matchers constraining the dynamic segments of enclosing nodes.
+    segment_matchers: Vec<(String, String)>,
+
+    /// Host pattern of the closest enclosing node with one.
+    host: Option<String>,
+}
+
+/// Appends the port of `authority`, if any, to a generated host name.
+fn with_port(mut host: String, authority: &str) -> String {
+    if let Some(port) = authority.parse::<Uri>().ok().and_then(|uri| uri.port_u16()) {
+        host.push(':');
+        host.push_str(&port.to_string());
+    }
+
+    host
+}
+
+/// Describes a resource registered in a [`ResourceMap`].
+///
+/// Returned by [`ResourceMap::entries`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceEntry {
+    patterns: Vec<String>,
+    name: Option<String>,
+    methods: Option<Vec<Method>>,
+    operations: Vec<Operation>,
+    segment_matchers: Vec<(String, String)>,
+    host: Option<String>,
+    scopes: Vec<String>,
+    is_prefix: bool,
+    is_default: bool,
+}
+
+impl ResourceEntry {
+    /// Returns the full pattern of the resource, including the patterns of enclosing scopes.
+    ///
+    /// For multi-pattern resources, the first pattern is returned.
+    pub fn pattern(&self) -> &str {
+        self.patterns.first().map_or("", String::as_str)
+    }
+
+    /// Returns all full patterns of the resource.
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    /// Returns resource name, if set.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the HTTP methods accepted by the resource, according to the method guards of its
+    /// routes, the resource itself and enclosing scopes.
+    ///
+    /// Returns `None` if requests with any method can be handled.
+    pub fn methods(&self) -> Option<&[Method]> {
+        self.methods.as_deref()
+    }
+
+    /// Returns the operations provided by the handlers of the resource's routes, in registration
+    /// order.
+    ///
+    /// Routes with custom services are only included if described with
+    /// [`Route::operation`](crate::Route::operation). The parameters, request body and responses
+    /// derived from the handlers' extractors and return types are computed on each call.
+    pub fn operations(&self) -> Vec<Operation> {
+        self.operations.iter().map(Operation::resolved).collect()
+    }
+
+    /// Returns the name of the [matcher](crate::dev::SegmentMatcher) constraining the dynamic
+    /// segment `segment` in the resource's patterns or those of enclosing scopes, if any.
+    ///
+    /// ```
+    /// use actix_web::{test, web, App, HttpResponse};
+    ///
+    /// # actix_web::rt::System::new().block_on(async {
+    /// let app = App::new().service(
+    ///     web::scope("/user/{id:u64}").route("/{post:slug}", web::get().to(HttpResponse::Ok)),
+    /// );
+    ///
+    /// let rmap = test::init_resource_map(app).await;
+    /// let entry = rmap.entries().next().unwrap();
+    ///
+    /// assert_eq!(entry.segment_matcher("id"), Some("u64"));
+    /// assert_eq!(entry.segment_matcher("post"), Some("slug"));
+    /// # });
+    /// ```
+    pub fn segment_matcher(&self, segment: &str) -> Option<&str> {
+        self.segment_matchers
+            .iter()
+            .find(|(name, _)| name == segment)
+            .map(|(_, matcher)| matcher.as_str())
+    }
+
+    /// Returns the host pattern that requests for the resource must match, as registered with the
+    /// resource or the closest enclosing scope with one.
+    pub fn host(&self) -> Option<&str> {
+        self.host.as_deref()
+    }
+
+    /// Returns the full patterns of the scopes enclosing this resource, outermost first.
+    ///
+    /// Default service entries of a scope include that scope.
+    pub fn scopes(&self) -> &[String] {
+        &self.scopes
+    }
+
+    /// Returns `true` if the resource matches paths by prefix, such as a service serving files.
+    pub fn is_prefix(&self) -> bool {
+        self.is_prefix
+    }
+
+    /// Returns `true` if this entry is the custom default service of the app or a scope.
+    pub fn is_default(&self) -> bool {
+        self.is_default
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -406,6 +903,118 @@ mod tests {
         assert!(rmap.url_for(&req, "missing", &["u123"]).is_err());
     }
 
+    #[test]
+    fn url_for_with() {
+        use std::collections::BTreeMap;
+
+        #[derive(serde::Serialize)]
+        struct PostParams<'a> {
+            sub_id: &'a str,
+            page: Option<u32>,
+            user_id: u64,
+            sort: &'a str,
+        }
+
+        let mut root = ResourceMap::new(ResourceDef::prefix(""));
+
+        let mut user_scope_rdef = ResourceDef::prefix("/user/{user_id}");
+        let mut user_scope_map = ResourceMap::new(user_scope_rdef.clone());
+
+        let mut post_rdef = ResourceDef::new("/post/{sub_id}");
+        post_rdef.set_name("post");
+
+        let mut index_rdef = ResourceDef::new("/");
+        index_rdef.set_name("index");
+
+        user_scope_map.add(&mut post_rdef, None);
+        root.add(&mut user_scope_rdef, Some(Rc::new(user_scope_map)));
+        root.add(&mut index_rdef, None);
+
+        let rmap = Rc::new(root);
+        ResourceMap::finish(&rmap);
+
+        let mut req = crate::test::TestRequest::default();
+        req.set_server_hostname("localhost:8888");
+        let req = req.to_http_request();
+
+        let params = PostParams {
+            sub_id: "foobar",
+            page: Some(2),
+            user_id: 123,
+            sort: "new & hot",
+        };
+        let url = rmap.url_for_with(&req, "post", &params).unwrap();
+        assert_eq!(
+            url.as_str(),
+            "http://localhost:8888/user/123/post/foobar?page=2&sort=new+%26+hot"
+        );
+
+        let params = PostParams {
+            page: None,
+            ..params
+        };
+        let url = rmap.url_for_with(&req, "post", &params).unwrap();
+        assert_eq!(
+            url.as_str(),
+            "http://localhost:8888/user/123/post/foobar?sort=new+%26+hot"
+        );
+
+        let mut params = BTreeMap::new();
+        params.insert("user_id", "u123");
+        params.insert("sub_id", "foobar");
+        let url = rmap.url_for_with(&req, "post", &params).unwrap();
+        assert_eq!(url.as_str(), "http://localhost:8888/user/u123/post/foobar");
+
+        params.remove("user_id");
+        params.remove("sub_id");
+        assert_eq!(
+            rmap.url_for_with(&req, "post", &params),
+            Err(UrlGenerationError::MissingParams(vec![
+                "user_id".to_owned(),
+                "sub_id".to_owned()
+            ]))
+        );
+
+        let url = rmap.url_for_with(&req, "post", &("u123", 42)).unwrap();
+        assert_eq!(url.as_str(), "http://localhost:8888/user/u123/post/42");
+
+        assert_eq!(
+            rmap.url_for_with(&req, "post", &["u123"]),
+            Err(UrlGenerationError::MissingParams(vec!["sub_id".to_owned()]))
+        );
+        assert_eq!(
+            rmap.url_for_with(&req, "post", &("u123", 42, true)),
+            Err(UrlGenerationError::ExtraParams(vec!["true".to_owned()]))
+        );
+        assert!(matches!(
+            rmap.url_for_with(&req, "post", &("u123", ["a", "b"])),
+            Err(UrlGenerationError::InvalidParams(_))
+        ));
+
+        // segment values are escaped
+        let url = rmap
+            .url_for_with(&req, "post", &("a/b", "what?#top"))
+            .unwrap();
+        assert_eq!(
+            url.as_str(),
+            "http://localhost:8888/user/a%2Fb/post/what%3F%23top"
+        );
+
+        let mut params = BTreeMap::new();
+        params.insert("user_id", "100%");
+        params.insert("sub_id", "%2F");
+        let url = rmap.url_for_with(&req, "post", &params).unwrap();
+        assert_eq!(url.as_str(), "http://localhost:8888/user/100%25/post/%252F");
+
+        let url = rmap.url_for_with(&req, "index", &()).unwrap();
+        assert_eq!(url.as_str(), "http://localhost:8888/");
+
+        assert_eq!(
+            rmap.url_for_with(&req, "missing", &()),
+            Err(UrlGenerationError::ResourceNotFound)
+        );
+    }
+
     #[test]
     fn external_resource_with_no_name() {
         let mut root = ResourceMap::new(ResourceDef::prefix(""));
@@ -441,4 +1050,153 @@ mod tests {
             "https://duck.com/abcd"
         );
     }
+
+    #[actix_rt::test]
+    async fn entries() {
+        use crate::{guard, http::Method, test::init_resource_map, web, App, HttpResponse};
+
+        let app = App::new()
+            .service(web::resource("/").name("index").to(HttpResponse::Ok))
+            .service(
+                web::scope("/api/{version}")
+                    .guard(guard::Any(guard::Get()).or(guard::Post()))
+                    .service(
+                        web::resource(["/users", "/people"])
+                            .name("users")
+                            .route(web::get().to(HttpResponse::Ok))
+                            .route(web::post().to(HttpResponse::Created))
+                            .route(web::delete().to(HttpResponse::NoContent)),
+                    )
+                    .service(
+                        web::scope("/admin")
+                            .route("/stats", web::get().to(HttpResponse::Ok))
+                            .default_service(web::to(HttpResponse::NotFound)),
+                    ),
+            )
+            .service(
+                web::resource("/upload").guard(guard::Put()).route(
+                    web::route()
+                        .guard(guard::Header("x", "y"))
+                        .to(HttpResponse::Ok),
+                ),
+            )
+            .external_resource("youtube", "https://youtube.com/watch/{video_id}")
+            .default_service(web::to(HttpResponse::NotFound));
+
+        let rmap = init_resource_map(app).await;
+        let entries = rmap.entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 6);
+
+        assert_eq!(entries[0].pattern(), "/");
+        assert_eq!(entries[0].name(), Some("index"));
+        assert_eq!(entries[0].methods(), None);
+        assert!(entries[0].scopes().is_empty());
+        assert!(!entries[0].is_prefix());
+        assert!(!entries[0].is_default());
+
+        assert_eq!(entries[1].pattern(), "/api/{version}/users");
+        assert_eq!(
+            entries[1].patterns(),
+            ["/api/{version}/users", "/api/{version}/people"]
+        );
+        assert_eq!(entries[1].name(), Some("users"));
+        assert_eq!(entries[1].methods(), Some(&[Method::GET, Method::POST][..]));
+        assert_eq!(entries[1].scopes(), ["/api/{version}"]);
+
+        assert_eq!(entries[2].pattern(), "/api/{version}/admin/stats");
+        assert_eq!(entries[2].methods(), Some(&[Method::GET][..]));
+        assert_eq!(
+            entries[2].scopes(),
+            ["/api/{version}", "/api/{version}/admin"]
+        );
+
+        assert_eq!(entries[3].pattern(), "/api/{version}/admin");
+        assert_eq!(entries[3].methods(), Some(&[Method::GET, Method::POST][..]));
+        assert_eq!(
+            entries[3].scopes(),
+            ["/api/{version}", "/api/{version}/admin"]
+        );
+        assert!(entries[3].is_prefix());
+        assert!(entries[3].is_default());
+
+        assert_eq!(entries[4].pattern(), "/upload");
+        assert_eq!(entries[4].methods(), Some(&[Method::PUT][..]));
+
+        assert_eq!(entries[5].pattern(), "");
+        assert!(entries[5].is_prefix());
+        assert!(entries[5].is_default());
+        assert!(entries[5].scopes().is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn url_for_host() {
+        use crate::{test, web, App, HttpRequest, HttpResponse};
+
+        async fn urls(req: HttpRequest) -> String {
+            let invoice = req.url_for("invoice", &["acme", "42"]).unwrap();
+            let home = req.url_for("home", &["blog"]).unwrap();
+            let missing = req.url_for("invoice", &["acme"]);
+            assert!(matches!(
+                missing,
+                Err(UrlGenerationError::NotEnoughElements)
+            ));
+
+            format!("{} {}", invoice, home)
+        }
+
+        let app = App::new()
+            .service(
+                web::scope("/billing")
+                    .host("{tenant}.example.com")
+                    .service(web::resource("/invoices/{id}").name("invoice").to(urls)),
+            )
+            .service(
+                web::resource("/")
+                    .host("{site}.example.org")
+                    .name("home")
+                    .to(urls),
+            );
+
+        let srv = test::init_service(app).await;
+        let req = test::TestRequest::with_uri("/billing/invoices/1")
+            .insert_header(("host", "acme.example.com:8080"))
+            .to_request();
+        let body = test::read_response(&srv, req).await;
+        assert_eq!(
+            body,
+            "http://acme.example.com:8080/billing/invoices/42 http://blog.example.org:8080/"
+        );
+
+        let app = App::new().service(
+            web::scope("/billing")
+                .host("{tenant}.example.com")
+                .route("/invoices", web::get().to(HttpResponse::Ok)),
+        );
+
+        let rmap = test::init_resource_map(app).await;
+        let entry = rmap.entries().next().unwrap();
+        assert_eq!(entry.host(), Some("{tenant}.example.com"));
+    }
+
+    #[actix_rt::test]
+    async fn entries_from_request() {
+        use crate::{test, web, App, HttpRequest, HttpResponse};
+
+        let srv = test::init_service(App::new().service(web::resource("/routes").to(
+            |req: HttpRequest| {
+                let patterns = req
+                    .resource_map()
+                    .entries()
+                    .map(|entry| entry.pattern().to_owned())
+                    .collect::<Vec<_>>();
+
+                HttpResponse::Ok().body(patterns.join(","))
+            },
+        )))
+        .await;
+
+        let req = test::TestRequest::with_uri("/routes").to_request();
+        let body = test::read_response(&srv, req).await;
+        assert_eq!(body, "/routes");
+    }
 }
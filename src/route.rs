@@ -12,6 +12,7 @@ use futures_core::future::LocalBoxFuture;
 use crate::{
     guard::{self, Guard},
     handler::{Handler, HandlerService},
+    openapi::Operation,
     service::{ServiceRequest, ServiceResponse},
     Error, FromRequest, HttpResponse, Responder,
 };
@@ -23,6 +24,7 @@ use crate::{
 pub struct Route {
     service: BoxServiceFactory<(), ServiceRequest, ServiceResponse, Error, ()>,
     guards: Rc<Vec<Box<dyn Guard>>>,
+    operation: Option<Operation>,
 }
 
 impl Route {
@@ -32,12 +34,26 @@ impl Route {
         Route {
             service: boxed::factory(HandlerService::new(HttpResponse::NotFound)),
             guards: Rc::new(Vec::new()),
+            operation: None,
         }
     }
 
     pub(crate) fn take_guards(&mut self) -> Vec<Box<dyn Guard>> {
         std::mem::take(Rc::get_mut(&mut self.guards).unwrap())
     }
+
+    /// Returns the methods this route's guards accept, or `None` if they accept any method.
+    pub(crate) fn methods(&self) -> Option<Vec<Method>> {
+        guard::all_methods(&self.guards)
+    }
+
+    /// Returns the operation describing this route, recording the methods of its guards, if the
+    /// route is documented.
+    pub(crate) fn documented_operation(&self) -> Option<Operation> {
+        let mut operation = self.operation.clone()?;
+        operation.set_methods(self.methods());
+        Some(operation)
+    }
 }
 
 impl ServiceFactory<ServiceRequest> for Route {
@@ -65,6 +81,10 @@ pub struct RouteService {
 }
 
 impl RouteService {
+    pub(crate) fn guards(&self) -> &[Box<dyn Guard>] {
+        &self.guards
+    }
+
     pub fn check(&self, req: &mut ServiceRequest) -> bool {
         for f in self.guards.iter() {
             if !f.check(req.head()) {
@@ -182,10 +202,49 @@ impl Route {
         R: Future + 'static,
         R::Output: Responder + 'static,
     {
+        self.operation
+            .get_or_insert_with(Operation::default)
+            .set_schema(|operation| {
+                T::schema(operation);
+                <R::Output as Responder>::schema(operation);
+            });
+
         self.service = boxed::factory(HandlerService::new(handler));
         self
     }
 
+    /// Describe the operation provided by this route in generated OpenAPI documents.
+    ///
+    /// The operation's parameters, request body and responses are derived from the handler's
+    /// extractors and return type by [`to`](Self::to), which replaces those previously derived
+    /// or added. Routes with custom services are only documented once described with this method.
+    ///
+    /// See [`openapi`](crate::openapi) for details.
+    ///
+    /// ```
+    /// use actix_web::{http::StatusCode, web, App, HttpResponse};
+    ///
+    /// async fn delete_pet(id: web::Path<u64>) -> HttpResponse {
+    ///     HttpResponse::NoContent().finish()
+    /// }
+    ///
+    /// App::new().route(
+    ///     "/pets/{id}",
+    ///     web::delete().to(delete_pet).operation(|op| {
+    ///         op.summary("Delete a pet")
+    ///             .tag("pets")
+    ///             .response(StatusCode::NO_CONTENT, "Pet deleted");
+    ///     }),
+    /// );
+    /// ```
+    pub fn operation<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut Operation),
+    {
+        f(self.operation.get_or_insert_with(Operation::default));
+        self
+    }
+
     /// Set raw service to be constructed and called as the request handler.
     ///
     /// # Examples
@@ -0,0 +1,407 @@
+//! Routing diagnostics.
+
+use std::{cell::Cell, fmt};
+
+use actix_router::Router;
+
+use crate::{
+    guard::Guard, http::Method, route::RouteService, service::ServiceRequest, HttpMessage,
+    HttpResponse, HttpResponseBuilder,
+};
+
+/// Record of the routing decisions made for a request.
+///
+/// When [routing diagnostics](crate::App::debug_routing) are enabled, a trace is stored in the
+/// extensions of each request. The app, its scopes and resources record the candidates they try,
+/// in order, up to the one that matched. Candidates ruled out by the router's index of the
+/// constant prefixes of patterns are not tried and not recorded. Patterns are recorded in full, joined to the patterns of
+/// enclosing scopes.
+///
+/// The `Display` implementation renders a human-readable explanation, one candidate per line.
+///
+/// ```
+/// use actix_web::{dev::RoutingTrace, HttpRequest, HttpResponse};
+///
+/// async fn not_found(req: HttpRequest) -> HttpResponse {
+///     match req.extensions().get::<RoutingTrace>() {
+///         Some(trace) => HttpResponse::NotFound().body(trace.to_string()),
+///         None => HttpResponse::NotFound().finish(),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RoutingTrace {
+    method: Method,
+    path: String,
+    steps: Vec<RoutingStep>,
+
+    /// Full pattern of the last matched candidate, prepended to patterns of nested candidates.
+    prefix: String,
+}
+
+impl RoutingTrace {
+    pub(crate) fn new(req: &ServiceRequest) -> Self {
+        RoutingTrace {
+            method: req.method().clone(),
+            path: req.path().to_owned(),
+            steps: Vec::new(),
+            prefix: String::new(),
+        }
+    }
+
+    /// Returns the method of the traced request.
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// Returns the path of the traced request.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the candidates tried, in order.
+    pub fn steps(&self) -> &[RoutingStep] {
+        &self.steps
+    }
+
+    /// Returns `true` if a resource route matched the request.
+    pub fn is_matched(&self) -> bool {
+        self.steps
+            .last()
+            .map_or(false, |step| step.route.is_some() && step.is_matched())
+    }
+
+    fn push(&mut self, pattern: String, route: Option<usize>, outcome: RoutingOutcome) {
+        if outcome == RoutingOutcome::Matched {
+            self.prefix = pattern.clone();
+        }
+
+        self.steps.push(RoutingStep {
+            pattern,
+            route,
+            outcome,
+        });
+    }
+}
+
+impl fmt::Display for RoutingTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_matched() {
+            writeln!(f, "Routing trace for {} {}", self.method, self.path)?;
+        } else {
+            writeln!(f, "No route matched {} {}", self.method, self.path)?;
+        }
+
+        for step in &self.steps {
+            writeln!(f, "  {}", step)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A candidate tried while routing a request.
+#[derive(Debug, Clone)]
+pub struct RoutingStep {
+    pattern: String,
+    route: Option<usize>,
+    outcome: RoutingOutcome,
+}
+
+impl RoutingStep {
+    /// Returns the full pattern of the scope or resource.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns the index of the route within its resource, for steps trying resource routes.
+    pub fn route(&self) -> Option<usize> {
+        self.route
+    }
+
+    /// Returns the outcome of trying the candidate.
+    pub fn outcome(&self) -> &RoutingOutcome {
+        &self.outcome
+    }
+
+    /// Returns `true` if the candidate matched the request.
+    pub fn is_matched(&self) -> bool {
+        self.outcome == RoutingOutcome::Matched
+    }
+}
+
+impl fmt::Display for RoutingStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.route {
+            Some(idx) => write!(f, "{} (route #{}): {}", self.pattern, idx, self.outcome),
+            None => write!(f, "{}: {}", self.pattern, self.outcome),
+        }
+    }
+}
+
+/// Outcome of a [`RoutingStep`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RoutingOutcome {
+    /// The pattern did not match the request path.
+    PatternMismatch,
+
+    /// The pattern matched but the guard with the given [name](Guard::name) rejected the request.
+    GuardRejected(String),
+
+    /// The candidate matched the request.
+    Matched,
+}
+
+impl fmt::Display for RoutingOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoutingOutcome::PatternMismatch => f.write_str("pattern did not match"),
+            RoutingOutcome::GuardRejected(guard) => write!(f, "rejected by guard {}", guard),
+            RoutingOutcome::Matched => f.write_str("matched"),
+        }
+    }
+}
+
+/// Starts a routing trace for `req`, unless one was already started.
+pub(crate) fn start(req: &ServiceRequest) {
+    if !req.extensions().contains::<RoutingTrace>() {
+        let trace = RoutingTrace::new(req);
+        req.extensions_mut().insert(trace);
+    }
+}
+
+/// Recognizes `req` with `router`, checking the guards of each candidate whose pattern matches,
+/// and records the candidates tried if `req` is being traced.
+pub(crate) fn recognize<'a, T>(
+    router: &'a Router<T, Vec<Box<dyn Guard>>>,
+    req: &mut ServiceRequest,
+) -> Option<&'a T> {
+    if !req.extensions().contains::<RoutingTrace>() {
+        return router
+            .recognize_fn(req, |req, guards| {
+                guards.as_ref().map_or(true, |guards| {
+                    guards.iter().all(|guard| guard.check(req.head()))
+                })
+            })
+            .map(|(srv, _)| srv);
+    }
+
+    // name of the guard rejecting the last candidate whose pattern matched
+    let rejected = Cell::new(None);
+    let mut steps = Vec::new();
+
+    let res = router.recognize_fn_inspect(
+        req,
+        |req, guards| {
+            let guards = guards.as_ref().map_or(&[][..], Vec::as_slice);
+
+            match check_guards(guards, req) {
+                RoutingOutcome::GuardRejected(guard) => {
+                    rejected.set(Some(guard));
+                    false
+                }
+                _ => true,
+            }
+        },
+        |rdef, accepted| {
+            let outcome = match (accepted, rejected.take()) {
+                (true, _) => RoutingOutcome::Matched,
+                (false, Some(guard)) => RoutingOutcome::GuardRejected(guard),
+                (false, None) => RoutingOutcome::PatternMismatch,
+            };
+
+            steps.push((rdef.pattern().unwrap_or_default().to_owned(), outcome));
+        },
+    );
+
+    if let Some(trace) = req.extensions_mut().get_mut::<RoutingTrace>() {
+        let prefix = trace.prefix.clone();
+
+        for (pattern, outcome) in steps {
+            trace.push(format!("{}{}", prefix, pattern), None, outcome);
+        }
+    }
+
+    res.map(|(srv, _)| srv)
+}
+
+/// Returns the first of the resource `routes` whose guards accept `req`, and records the routes
+/// tried if `req` is being traced.
+pub(crate) fn select_route<'a>(
+    routes: &'a [RouteService],
+    req: &mut ServiceRequest,
+) -> Option<&'a RouteService> {
+    if !req.extensions().contains::<RoutingTrace>() {
+        return routes.iter().find(|route| route.check(req));
+    }
+
+    let mut outcomes = Vec::new();
+    let mut selected = None;
+
+    for route in routes {
+        let outcome = check_guards(route.guards(), req);
+        let matched = outcome == RoutingOutcome::Matched;
+        outcomes.push(outcome);
+
+        if matched {
+            selected = Some(route);
+            break;
+        }
+    }
+
+    if let Some(trace) = req.extensions_mut().get_mut::<RoutingTrace>() {
+        let pattern = trace.prefix.clone();
+
+        for (idx, outcome) in outcomes.into_iter().enumerate() {
+            trace.push(pattern.clone(), Some(idx), outcome);
+        }
+    }
+
+    selected
+}
+
+/// Finishes a built-in default service response, explaining the routing trace in its body if
+/// `req` is being traced.
+pub(crate) fn respond(req: &ServiceRequest, mut res: HttpResponseBuilder) -> HttpResponse {
+    match req.extensions().get::<RoutingTrace>() {
+        Some(trace) => res
+            .content_type(mime::TEXT_PLAIN_UTF_8)
+            .body(trace.to_string()),
+        None => res.finish(),
+    }
+}
+
+fn check_guards(guards: &[Box<dyn Guard>], req: &ServiceRequest) -> RoutingOutcome {
+    match guards.iter().find(|guard| !guard.check(req.head())) {
+        Some(guard) => RoutingOutcome::GuardRejected(guard.name()),
+        None => RoutingOutcome::Matched,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dev::Service,
+        guard,
+        http::StatusCode,
+        test::{call_service, init_service, read_body, TestRequest},
+        web, App, HttpRequest,
+    };
+
+    async fn steps(req: HttpRequest) -> HttpResponse {
+        let trace = req.extensions().get::<RoutingTrace>().cloned().unwrap();
+        assert!(trace.is_matched());
+
+        let steps = trace
+            .steps()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        HttpResponse::Ok().body(steps.join("\n"))
+    }
+
+    fn app() -> App<
+        impl actix_service::ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = crate::dev::ServiceResponse,
+            Error = crate::Error,
+            InitError = (),
+        >,
+        actix_http::body::Body,
+    > {
+        App::new()
+            .debug_routing(true)
+            .service(
+                web::scope("/api")
+                    .guard(guard::Header("x-api-version", "2"))
+                    .route("/users", web::get().to(steps)),
+            )
+            .service(
+                web::scope("/api")
+                    .service(web::resource("/users/{id:u32}").route(web::get().to(steps)))
+                    .service(
+                        web::resource("/users/{name}")
+                            .route(web::post().to(steps))
+                            .route(web::get().guard(guard::Header("x-admin", "1")).to(steps)),
+                    ),
+            )
+    }
+
+    #[actix_rt::test]
+    async fn trace_matched() {
+        let srv = init_service(app()).await;
+
+        let req = TestRequest::with_uri("/api/users/42").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            read_body(res).await,
+            "/api: rejected by guard Header(x-api-version: \"2\")\n\
+             /api: matched\n\
+             /api/users/{id:u32}: matched\n\
+             /api/users/{id:u32} (route #0): matched"
+        );
+
+        let req = TestRequest::with_uri("/api/users/bob")
+            .insert_header(("x-admin", "1"))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            read_body(res).await,
+            "/api: rejected by guard Header(x-api-version: \"2\")\n\
+             /api: matched\n\
+             /api/users/{id:u32}: pattern did not match\n\
+             /api/users/{name}: matched\n\
+             /api/users/{name} (route #0): rejected by guard Method(POST)\n\
+             /api/users/{name} (route #1): matched"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn trace_not_matched() {
+        let srv = init_service(app()).await;
+
+        let req = TestRequest::with_uri("/api/users/bob").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(
+            read_body(res).await,
+            "No route matched GET /api/users/bob\n  \
+             /api: rejected by guard Header(x-api-version: \"2\")\n  \
+             /api: matched\n  \
+             /api/users/{id:u32}: pattern did not match\n  \
+             /api/users/{name}: matched\n  \
+             /api/users/{name} (route #0): rejected by guard Method(POST)\n  \
+             /api/users/{name} (route #1): rejected by guard Header(x-admin: \"1\")\n"
+        );
+
+        // the scopes are not tried, as their constant prefix does not match
+        let req = TestRequest::with_uri("/about").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(read_body(res).await, "No route matched GET /about\n");
+    }
+
+    #[actix_rt::test]
+    async fn disabled_by_default() {
+        let srv = init_service(App::new().service(web::resource("/").to(
+            |req: HttpRequest| {
+                assert!(req.extensions().get::<RoutingTrace>().is_none());
+                HttpResponse::Ok()
+            },
+        )))
+        .await;
+
+        let res = srv.call(TestRequest::default().to_request()).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("/about").to_request();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert!(read_body(res).await.is_empty());
+    }
+}
@@ -14,10 +14,11 @@ use crate::{
     config::ServiceConfig,
     data::Data,
     dev::{AppService, HttpServiceFactory},
-    guard::Guard,
+    guard::{self, Guard, HostPattern},
     rmap::ResourceMap,
+    routing_trace,
     service::{AppServiceFactory, ServiceFactoryWrapper, ServiceRequest, ServiceResponse},
-    Error, Resource, Route,
+    DynamicRoutes, Error, Resource, Route,
 };
 
 type Guards = Vec<Box<dyn Guard>>;
@@ -55,11 +56,13 @@ type HttpNewService = BoxServiceFactory<(), ServiceRequest, ServiceResponse, Err
 pub struct Scope<T = ScopeEndpoint> {
     endpoint: T,
     rdef: String,
+    host: Option<String>,
     app_data: Option<Extensions>,
     services: Vec<Box<dyn AppServiceFactory>>,
     guards: Vec<Box<dyn Guard>>,
     default: Option<Rc<HttpNewService>>,
     external: Vec<ResourceDef>,
+    dynamic: Option<DynamicRoutes>,
     factory_ref: Rc<RefCell<Option<ScopeFactory>>>,
 }
 
@@ -71,11 +74,13 @@ impl Scope {
         Scope {
             endpoint: ScopeEndpoint::new(Rc::clone(&factory_ref)),
             rdef: path.to_string(),
+            host: None,
             app_data: None,
             guards: Vec::new(),
             services: Vec::new(),
             default: None,
             external: Vec::new(),
+            dynamic: None,
             factory_ref,
         }
     }
@@ -116,6 +121,45 @@ where
         self
     }
 
+    /// Restricts the scope to requests for hosts matching a pattern.
+    ///
+    /// The pattern uses the same syntax as resource patterns and is matched case-insensitively
+    /// against the request's host name, without port. Dynamic segments match a single host label
+    /// unless given a custom regex or matcher. Their values are captured into the request's match info ahead of
+    /// the path segments, so they can be read with [`web::Path`](crate::web::Path) or
+    /// [`HttpRequest::match_info`].
+    ///
+    /// URLs generated for named resources within the scope include the host, whose segments are
+    /// filled before those of the path.
+    ///
+    /// [`HttpRequest::match_info`]: crate::HttpRequest::match_info
+    ///
+    /// ```
+    /// use actix_web::{web, App, HttpResponse};
+    ///
+    /// #[derive(serde::Deserialize)]
+    /// struct Info {
+    ///     tenant: String,
+    ///     id: u64,
+    /// }
+    ///
+    /// async fn invoice(info: web::Path<Info>) -> String {
+    ///     format!("Invoice {} of {}", info.id, info.tenant)
+    /// }
+    ///
+    /// let app = App::new()
+    ///     .service(
+    ///         web::scope("")
+    ///             .host("{tenant}.example.com")
+    ///             .route("/invoices/{id:u64}", web::get().to(invoice)),
+    ///     )
+    ///     .route("/", web::get().to(HttpResponse::Ok));
+    /// ```
+    pub fn host(mut self, pattern: &str) -> Self {
+        self.host = Some(pattern.to_owned());
+        self
+    }
+
     /// Add scope data.
     ///
     /// Data of different types from parent contexts will still be accessible. Any `Data<T>` types
@@ -295,6 +339,28 @@ where
         self
     }
 
+    /// Routes requests not matched by the scope's services through a table of dynamic routes.
+    ///
+    /// Routes can be registered in and removed from the table while the server is running; see
+    /// [`DynamicRoutes`] for details. Requests not matched by a dynamic route are handled by the
+    /// scope's default service.
+    ///
+    /// ```
+    /// use actix_web::{web, App, DynamicRoutes, HttpResponse};
+    ///
+    /// let plugins = DynamicRoutes::new();
+    ///
+    /// let app = App::new().service(
+    ///     web::scope("/plugins")
+    ///         .route("/", web::get().to(HttpResponse::Ok))
+    ///         .dynamic(plugins.clone()),
+    /// );
+    /// ```
+    pub fn dynamic(mut self, routes: DynamicRoutes) -> Self {
+        self.dynamic = Some(routes);
+        self
+    }
+
     /// Registers middleware, in the form of a middleware component (type),
     /// that runs during inbound processing in the request
     /// life-cycle (request -> response), modifying request as
@@ -328,11 +394,13 @@ where
         Scope {
             endpoint: apply(mw, self.endpoint),
             rdef: self.rdef,
+            host: self.host,
             app_data: self.app_data,
             guards: self.guards,
             services: self.services,
             default: self.default,
             external: self.external,
+            dynamic: self.dynamic,
             factory_ref: self.factory_ref,
         }
     }
@@ -388,11 +456,13 @@ where
         Scope {
             endpoint: apply_fn_factory(self.endpoint, mw),
             rdef: self.rdef,
+            host: self.host,
             app_data: self.app_data,
             guards: self.guards,
             services: self.services,
             default: self.default,
             external: self.external,
+            dynamic: self.dynamic,
             factory_ref: self.factory_ref,
         }
     }
@@ -409,16 +479,41 @@ where
         > + 'static,
 {
     fn register(mut self, config: &mut AppService) {
+        let has_default = self.default.is_some();
+
+        let host = self
+            .host
+            .as_deref()
+            .map(|host| HostPattern::new(host, config.segment_matchers()));
+
+        if let Some(ref host) = host {
+            self.guards.push(Box::new(host.clone()));
+        }
+
         // update default resource if needed
         let default = self.default.unwrap_or_else(|| config.default_service());
 
+        // route unmatched requests through the dynamic route table, if any
+        let default = match self.dynamic {
+            Some(routes) => {
+                Rc::new(routes.into_factory(default, config.segment_matchers().clone()))
+            }
+            None => default,
+        };
+
         // register nested services
         let mut cfg = config.clone_config();
         self.services
             .into_iter()
             .for_each(|mut srv| srv.register(&mut cfg));
 
-        let mut rmap = ResourceMap::new(ResourceDef::root_prefix(&self.rdef));
+        let rdef =
+            ResourceDef::root_prefix(&self.rdef).with_matchers(config.segment_matchers());
+
+        let mut rmap = ResourceMap::new(rdef.clone());
+        rmap.set_methods(guard::all_methods(&self.guards));
+        rmap.set_has_default(has_default);
+        rmap.set_host(host.clone());
 
         // external resources
         for mut rdef in mem::take(&mut self.external) {
@@ -432,8 +527,8 @@ where
                 .into_services()
                 .1
                 .into_iter()
-                .map(|(mut rdef, srv, guards, nested)| {
-                    rmap.add(&mut rdef, nested);
+                .map(|(mut rdef, srv, guards, nested, info)| {
+                    rmap.add_service(&mut rdef, nested, info);
                     (rdef, srv, RefCell::new(guards))
                 })
                 .collect::<Vec<_>>()
@@ -450,22 +545,22 @@ where
 
         let scope_data = self.app_data.map(Rc::new);
 
-        // wraps endpoint service (including middleware) call and injects app data for this scope
+        // wraps endpoint service (including middleware) call, injects app data for this scope and
+        // captures host segments
         let endpoint = apply_fn_factory(self.endpoint, move |mut req: ServiceRequest, srv| {
             if let Some(ref data) = scope_data {
                 req.add_data_container(Rc::clone(data));
             }
 
+            if let Some(ref host) = host {
+                host.capture(&mut req);
+            }
+
             srv.call(req)
         });
 
         // register final service
-        config.register_service(
-            ResourceDef::root_prefix(&self.rdef),
-            guards,
-            endpoint,
-            Some(Rc::new(rmap)),
-        )
+        config.register_service(rdef, guards, endpoint, Some(Rc::new(rmap)))
     }
 }
 
@@ -530,18 +625,7 @@ impl Service<ServiceRequest> for ScopeService {
     actix_service::always_ready!();
 
     fn call(&self, mut req: ServiceRequest) -> Self::Future {
-        let res = self.router.recognize_fn(&mut req, |req, guards| {
-            if let Some(ref guards) = guards {
-                for f in guards {
-                    if !f.check(req.head()) {
-                        return false;
-                    }
-                }
-            }
-            true
-        });
-
-        if let Some((srv, _info)) = res {
+        if let Some(srv) = routing_trace::recognize(&self.router, &mut req) {
             srv.call(req)
         } else {
             self.default.call(req)
@@ -764,6 +848,43 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::NOT_FOUND);
     }
 
+    #[actix_rt::test]
+    async fn test_scope_host() {
+        let srv = init_service(
+            App::new()
+                .service(web::scope("/{section}").host("{tenant}.example.com").route(
+                    "/{id}",
+                    web::get().to(|path: web::Path<(String, String, String)>| {
+                        let (tenant, section, id) = path.into_inner();
+                        HttpResponse::Ok().body(format!("{} {} {}", tenant, section, id))
+                    }),
+                ))
+                .default_service(web::to(HttpResponse::NotFound)),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/docs/1")
+            .insert_header((header::HOST, "acme.example.com"))
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"acme docs 1"));
+
+        // host names are matched case-insensitively
+        let req = TestRequest::with_uri("/docs/1")
+            .insert_header((header::HOST, "ACME.Example.com"))
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"acme docs 1"));
+
+        let req = TestRequest::with_uri("/docs/1")
+            .insert_header((header::HOST, "example.com"))
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
     #[actix_rt::test]
     async fn test_nested_scope() {
         let srv = init_service(App::new().service(web::scope("/app").service(
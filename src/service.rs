@@ -554,7 +554,8 @@ where
             ResourceDef::new(ensure_leading_slash(self.rdef))
         } else {
             ResourceDef::new(self.rdef)
-        };
+        }
+        .with_matchers(config.segment_matchers());
 
         if let Some(ref name) = self.name {
             rdef.set_name(name);
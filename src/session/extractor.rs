@@ -0,0 +1,192 @@
+use std::{
+    cell::{Ref, RefCell},
+    rc::Rc,
+};
+
+use actix_utils::future::{ready, Ready};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::SessionState;
+use crate::{dev::Payload, Error, FromRequest, HttpRequest};
+
+/// Session of a client, loaded by the [`SessionMiddleware`](super::SessionMiddleware).
+///
+/// Values are stored as JSON, under string keys. Changes made by handlers are saved by the
+/// middleware once the response is produced.
+///
+/// Extracting a `Session` without the middleware registered gives an empty session whose changes
+/// are discarded.
+///
+/// # Examples
+/// ```
+/// use actix_web::{session::Session, Error, HttpResponse};
+///
+/// async fn index(session: Session) -> Result<HttpResponse, Error> {
+///     let visits = session.get::<u32>("visits")?.unwrap_or(0) + 1;
+///     session.insert("visits", visits)?;
+///
+///     Ok(HttpResponse::Ok().body(format!("Visit #{}", visits)))
+/// }
+///
+/// async fn login(session: Session) -> Result<HttpResponse, Error> {
+///     // issue a new session key on privilege changes to prevent session fixation
+///     session.renew();
+///     session.insert("user_id", 42)?;
+///
+///     Ok(HttpResponse::Ok().finish())
+/// }
+///
+/// async fn logout(session: Session) -> HttpResponse {
+///     session.purge();
+///     HttpResponse::Ok().finish()
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Session(Rc<RefCell<Inner>>);
+
+#[derive(Default)]
+struct Inner {
+    state: SessionState,
+    status: SessionStatus,
+}
+
+/// Status of a [`Session`], telling the middleware how to persist it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionStatus {
+    /// The session was not modified.
+    Unchanged,
+
+    /// The session state was modified.
+    Changed,
+
+    /// The session is to be deleted, along with its cookie.
+    Purged,
+
+    /// The session is to be saved under a new session key.
+    Renewed,
+}
+
+impl Default for SessionStatus {
+    fn default() -> Self {
+        SessionStatus::Unchanged
+    }
+}
+
+impl Session {
+    pub(crate) fn new(state: SessionState) -> Session {
+        Session(Rc::new(RefCell::new(Inner {
+            state,
+            status: SessionStatus::Unchanged,
+        })))
+    }
+
+    /// Returns the value stored under `key`, if any.
+    ///
+    /// # Errors
+    /// Fails if the value cannot be deserialized as a `T`.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, Error> {
+        match self.0.borrow().state.get(key) {
+            Some(value) => Ok(Some(serde_json::from_str(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the session state.
+    pub fn entries(&self) -> Ref<'_, SessionState> {
+        Ref::map(self.0.borrow(), |inner| &inner.state)
+    }
+
+    /// Stores `value` under `key`, replacing any previous value.
+    ///
+    /// # Errors
+    /// Fails if the value cannot be serialized.
+    pub fn insert(&self, key: impl Into<String>, value: impl Serialize) -> Result<(), Error> {
+        let value = serde_json::to_string(&value)?;
+
+        let mut inner = self.0.borrow_mut();
+        inner.state.insert(key.into(), value);
+        inner.mark_changed();
+
+        Ok(())
+    }
+
+    /// Removes the value stored under `key`, returning it in its serialized form.
+    pub fn remove(&self, key: &str) -> Option<String> {
+        let mut inner = self.0.borrow_mut();
+        let value = inner.state.remove(key);
+
+        if value.is_some() {
+            inner.mark_changed();
+        }
+
+        value
+    }
+
+    /// Removes all values from the session, keeping its session key.
+    pub fn clear(&self) {
+        let mut inner = self.0.borrow_mut();
+        inner.state.clear();
+        inner.mark_changed();
+    }
+
+    /// Removes all values and deletes the session, removing its cookie.
+    ///
+    /// Values inserted after purging are discarded.
+    pub fn purge(&self) {
+        let mut inner = self.0.borrow_mut();
+        inner.state.clear();
+        inner.status = SessionStatus::Purged;
+    }
+
+    /// Moves the session state to a new session key, deleting the previous session.
+    ///
+    /// Renew sessions when the privileges of a client change, such as when logging in, so that a
+    /// session key planted by an attacker before the change cannot be used after it.
+    pub fn renew(&self) {
+        let mut inner = self.0.borrow_mut();
+
+        if inner.status != SessionStatus::Purged {
+            inner.status = SessionStatus::Renewed;
+        }
+    }
+
+    /// Returns the status of the session.
+    pub fn status(&self) -> SessionStatus {
+        self.0.borrow().status
+    }
+
+    /// Returns the status and state of the session, leaving it empty.
+    pub(crate) fn take(&self) -> (SessionStatus, SessionState) {
+        let mut inner = self.0.borrow_mut();
+        (inner.status, std::mem::take(&mut inner.state))
+    }
+}
+
+impl Inner {
+    fn mark_changed(&mut self) {
+        if self.status == SessionStatus::Unchanged {
+            self.status = SessionStatus::Changed;
+        }
+    }
+}
+
+impl FromRequest for Session {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(session) = req.extensions().get::<Session>() {
+            return ready(Ok(session.clone()));
+        }
+
+        log::debug!(
+            "Session middleware is not registered; changes to the session will be discarded. \
+             Request path: {:?}",
+            req.path()
+        );
+
+        let session = Session::new(SessionState::new());
+        req.extensions_mut().insert(session.clone());
+        ready(Ok(session))
+    }
+}
@@ -0,0 +1,573 @@
+use std::{fmt, rc::Rc, sync::Arc, time::Duration};
+
+use actix_utils::future::{ready, Ready};
+use futures_core::future::LocalBoxFuture;
+
+use super::{Session, SessionState, SessionStatus, SessionStore};
+use crate::{
+    cookie::{Cookie, CookieJar, Key, SameSite},
+    dev::{Service, Transform},
+    error::ErrorInternalServerError,
+    http::header::{self, HeaderValue},
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpMessage as _,
+};
+
+/// Maximum size of a `Set-Cookie` header value storing a session key, as cookies larger than
+/// 4096 bytes are dropped by browsers.
+const MAX_COOKIE_LEN: usize = 4096;
+
+/// How the session key is protected in the session cookie.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CookieContentSecurity {
+    /// The session key is encrypted and signed; clients can neither read nor modify it.
+    Private,
+
+    /// The session key is signed; clients can read it but not modify it.
+    Signed,
+}
+
+/// Middleware for loading and persisting [`Session`]s.
+///
+/// The session key identifying each client's session is kept in a cookie, by default named `id`,
+/// which is encrypted with the given [`Key`] or, with [`CookieContentSecurity::Signed`], only
+/// signed. The session state is kept by a [`SessionStore`], such as the [`CookieSessionStore`]
+/// storing it in the cookie itself.
+///
+/// Sessions expire after a TTL of one day, set with [`ttl`](Self::ttl), counted from the last
+/// time they were changed or, with [`renew_on_access`](Self::renew_on_access), from the last
+/// request. The session cookie is given the same `Max-Age`.
+///
+/// Keys can be rotated by setting the new key as the primary key and registering the previous
+/// one with [`fallback_key`](Self::fallback_key). Cookies protected with a fallback key are
+/// accepted and issued again with the primary key.
+///
+/// Session cookies are `Secure`, `HttpOnly` and `SameSite=Lax` by default.
+///
+/// [`CookieSessionStore`]: super::CookieSessionStore
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     cookie::Key,
+///     session::{CookieSessionStore, Session, SessionMiddleware},
+///     web, App, HttpResponse, HttpServer,
+/// };
+///
+/// # fn run() -> std::io::Result<()> {
+/// // load the key from configuration to keep sessions valid across restarts
+/// let key = Key::generate();
+///
+/// let server = HttpServer::new(move || {
+///     App::new()
+///         .wrap(SessionMiddleware::new(CookieSessionStore::new(), key.clone()))
+///         .route("/", web::get().to(|session: Session| async move {
+///             session.insert("seen", true)?;
+///             Ok::<_, actix_web::Error>(HttpResponse::Ok())
+///         }))
+/// });
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SessionMiddleware {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    store: Arc<dyn SessionStore>,
+    key: Key,
+    fallback_keys: Vec<Key>,
+    content_security: CookieContentSecurity,
+    cookie_name: String,
+    cookie_path: String,
+    cookie_domain: Option<String>,
+    cookie_secure: bool,
+    cookie_http_only: bool,
+    cookie_same_site: SameSite,
+    ttl: Duration,
+    renew_on_access: bool,
+}
+
+impl SessionMiddleware {
+    /// Constructs a `SessionMiddleware` keeping sessions in `store` and protecting session
+    /// cookies with `key`.
+    pub fn new<S>(store: S, key: Key) -> SessionMiddleware
+    where
+        S: SessionStore,
+    {
+        SessionMiddleware {
+            inner: Rc::new(Inner {
+                store: Arc::new(store),
+                key,
+                fallback_keys: Vec::new(),
+                content_security: CookieContentSecurity::Private,
+                cookie_name: "id".to_owned(),
+                cookie_path: "/".to_owned(),
+                cookie_domain: None,
+                cookie_secure: true,
+                cookie_http_only: true,
+                cookie_same_site: SameSite::Lax,
+                ttl: Duration::from_secs(24 * 60 * 60),
+                renew_on_access: false,
+            }),
+        }
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("Multiple `Inner` copies exist.")
+    }
+
+    /// Adds a key that session cookies are also checked against, for rotating keys.
+    pub fn fallback_key(mut self, key: Key) -> Self {
+        self.inner_mut().fallback_keys.push(key);
+        self
+    }
+
+    /// Sets how the session key is protected in the session cookie.
+    ///
+    /// Default is [`CookieContentSecurity::Private`].
+    pub fn content_security(mut self, content_security: CookieContentSecurity) -> Self {
+        self.inner_mut().content_security = content_security;
+        self
+    }
+
+    /// Sets the name of the session cookie. Default is `id`.
+    pub fn cookie_name(mut self, name: impl Into<String>) -> Self {
+        self.inner_mut().cookie_name = name.into();
+        self
+    }
+
+    /// Sets the `Path` attribute of the session cookie. Default is `/`.
+    pub fn cookie_path(mut self, path: impl Into<String>) -> Self {
+        self.inner_mut().cookie_path = path.into();
+        self
+    }
+
+    /// Sets the `Domain` attribute of the session cookie. By default it is not set.
+    pub fn cookie_domain(mut self, domain: impl Into<String>) -> Self {
+        self.inner_mut().cookie_domain = Some(domain.into());
+        self
+    }
+
+    /// Sets whether the session cookie is only sent over HTTPS. Default is `true`.
+    pub fn cookie_secure(mut self, secure: bool) -> Self {
+        self.inner_mut().cookie_secure = secure;
+        self
+    }
+
+    /// Sets whether the session cookie is hidden from scripts. Default is `true`.
+    pub fn cookie_http_only(mut self, http_only: bool) -> Self {
+        self.inner_mut().cookie_http_only = http_only;
+        self
+    }
+
+    /// Sets the `SameSite` attribute of the session cookie. Default is `Lax`.
+    pub fn cookie_same_site(mut self, same_site: SameSite) -> Self {
+        self.inner_mut().cookie_same_site = same_site;
+        self
+    }
+
+    /// Sets the time after which sessions expire. Default is one day.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.inner_mut().ttl = ttl;
+        self
+    }
+
+    /// Sets whether the expiry of sessions is extended on each request, and not only when they
+    /// change. Default is `false`.
+    pub fn renew_on_access(mut self, renew: bool) -> Self {
+        self.inner_mut().renew_on_access = renew;
+        self
+    }
+}
+
+impl fmt::Debug for SessionMiddleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionMiddleware")
+            .field("content_security", &self.inner.content_security)
+            .field("cookie_name", &self.inner.cookie_name)
+            .field("ttl", &self.inner.ttl)
+            .field("renew_on_access", &self.inner.renew_on_access)
+            .finish()
+    }
+}
+
+impl Inner {
+    /// Returns the session key found in the session cookie of `req`, and whether it was
+    /// protected with a fallback key.
+    fn session_key(&self, req: &ServiceRequest) -> Option<(String, bool)> {
+        let cookie = req.cookie(&self.cookie_name)?;
+
+        let mut jar = CookieJar::new();
+        jar.add_original(cookie);
+
+        let keys = std::iter::once(&self.key).chain(&self.fallback_keys);
+
+        for (idx, key) in keys.enumerate() {
+            let verified = match self.content_security {
+                CookieContentSecurity::Private => jar.private(key).get(&self.cookie_name),
+                CookieContentSecurity::Signed => jar.signed(key).get(&self.cookie_name),
+            };
+
+            if let Some(cookie) = verified {
+                return Some((cookie.value().to_owned(), idx > 0));
+            }
+        }
+
+        None
+    }
+
+    /// Returns a cookie with the session cookie attributes.
+    fn cookie(&self, value: String) -> Cookie<'static> {
+        let mut cookie = Cookie::build(self.cookie_name.clone(), value)
+            .path(self.cookie_path.clone())
+            .secure(self.cookie_secure)
+            .http_only(self.cookie_http_only)
+            .same_site(self.cookie_same_site)
+            .finish();
+
+        if let Some(domain) = &self.cookie_domain {
+            cookie.set_domain(domain.clone());
+        }
+
+        cookie
+    }
+
+    /// Returns the `Set-Cookie` header value storing `session_key`.
+    fn set_cookie(&self, session_key: String) -> Result<HeaderValue, Error> {
+        let mut jar = CookieJar::new();
+
+        match self.content_security {
+            CookieContentSecurity::Private => {
+                jar.private_mut(&self.key).add(self.cookie(session_key))
+            }
+            CookieContentSecurity::Signed => {
+                jar.signed_mut(&self.key).add(self.cookie(session_key))
+            }
+        }
+
+        let cookie = jar.delta().next().unwrap();
+
+        // `Max-Age` is written out directly since cookie uses a different version of `time`
+        let value = format!("{}; Max-Age={}", cookie.encoded(), self.ttl.as_secs());
+
+        if value.len() > MAX_COOKIE_LEN {
+            return Err(ErrorInternalServerError(
+                "Session key is too large to be stored in a cookie.",
+            ));
+        }
+
+        Ok(HeaderValue::from_str(&value)?)
+    }
+
+    /// Returns the `Set-Cookie` header value removing the session cookie.
+    fn removal_cookie(&self) -> Result<HeaderValue, Error> {
+        let mut cookie = self.cookie(String::new());
+        cookie.make_removal();
+        Ok(HeaderValue::from_str(&cookie.encoded().to_string())?)
+    }
+
+    /// Persists the session, returning the `Set-Cookie` header value to send, if any.
+    async fn persist(
+        &self,
+        session_key: Option<(String, bool)>,
+        status: SessionStatus,
+        state: SessionState,
+    ) -> Result<Option<HeaderValue>, Error> {
+        let store = &self.store;
+
+        let session_key = match (status, session_key) {
+            (SessionStatus::Purged, Some((key, _))) => {
+                store.delete(&key).await?;
+                return self.removal_cookie().map(Some);
+            }
+
+            (SessionStatus::Purged, None) => return Ok(None),
+
+            (SessionStatus::Renewed, session_key) => {
+                if let Some((key, _)) = session_key {
+                    store.delete(&key).await?;
+                }
+
+                store.save(state, self.ttl).await?
+            }
+
+            (SessionStatus::Changed, Some((key, _))) => {
+                store.update(key, state, self.ttl).await?
+            }
+
+            (SessionStatus::Changed, None) if state.is_empty() => return Ok(None),
+
+            (SessionStatus::Changed, None) => store.save(state, self.ttl).await?,
+
+            // issue cookies protected with a fallback key again with the primary key
+            (SessionStatus::Unchanged, Some((key, rotated)))
+                if rotated || self.renew_on_access =>
+            {
+                store.update(key, state, self.ttl).await?
+            }
+
+            (SessionStatus::Unchanged, _) => return Ok(None),
+        };
+
+        self.set_cookie(session_key).map(Some)
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for SessionMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = SessionService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(SessionService {
+            service: Rc::new(service),
+            inner: Rc::clone(&self.inner),
+        }))
+    }
+}
+
+pub struct SessionService<S> {
+    service: Rc<S>,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for SessionService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let inner = Rc::clone(&self.inner);
+
+        Box::pin(async move {
+            let mut session_key = inner.session_key(&req);
+
+            let state = match &session_key {
+                Some((key, _)) => inner.store.load(key).await?,
+                None => None,
+            };
+
+            // unknown or expired sessions start over with a new session key
+            if state.is_none() {
+                session_key = None;
+            }
+
+            let session = Session::new(state.unwrap_or_default());
+            req.extensions_mut().insert(session.clone());
+
+            let mut res = service.call(req).await?;
+
+            let (status, state) = session.take();
+
+            if let Some(cookie) = inner.persist(session_key, status, state).await? {
+                res.headers_mut().append(header::SET_COOKIE, cookie);
+            }
+
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::StatusCode,
+        session::CookieSessionStore,
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    async fn count(session: Session) -> Result<HttpResponse, Error> {
+        let count = session.get::<u32>("count")?.unwrap_or(0) + 1;
+        session.insert("count", count)?;
+        Ok(HttpResponse::Ok().body(count.to_string()))
+    }
+
+    async fn logout(session: Session) -> HttpResponse {
+        session.purge();
+        HttpResponse::Ok().finish()
+    }
+
+    async fn peek(session: Session) -> Result<HttpResponse, Error> {
+        let count = session.get::<u32>("count")?.unwrap_or(0);
+        Ok(HttpResponse::Ok().body(count.to_string()))
+    }
+
+    async fn fill(session: Session, len: web::Path<usize>) -> Result<HttpResponse, Error> {
+        session.insert("pad", " ".repeat(len.into_inner()))?;
+        Ok(HttpResponse::Ok().finish())
+    }
+
+    async fn init(
+        middleware: SessionMiddleware,
+    ) -> impl Service<actix_http::Request, Response = ServiceResponse, Error = Error> {
+        test::init_service(
+            App::new()
+                .wrap(middleware)
+                .route("/count", web::get().to(count))
+                .route("/peek", web::get().to(peek))
+                .route("/logout", web::get().to(logout))
+                .route("/fill/{len}", web::get().to(fill)),
+        )
+        .await
+    }
+
+    fn session_cookie(res: &ServiceResponse) -> Cookie<'static> {
+        let header = res.headers().get(header::SET_COOKIE).unwrap();
+        Cookie::parse_encoded(header.to_str().unwrap().to_owned()).unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn stores_state_in_cookie() {
+        let key = Key::generate();
+        let srv = init(SessionMiddleware::new(CookieSessionStore::new(), key)).await;
+
+        let req = TestRequest::with_uri("/count").to_request();
+        let res = test::call_service(&srv, req).await;
+        let cookie = session_cookie(&res);
+        assert_eq!(cookie.name(), "id");
+        assert_eq!(cookie.secure(), Some(true));
+        assert_eq!(cookie.http_only(), Some(true));
+        assert!(cookie.max_age().is_some());
+        assert!(!cookie.value().contains("count"));
+
+        let req = TestRequest::with_uri("/count").cookie(cookie).to_request();
+        let res = test::call_service(&srv, req).await;
+        let cookie = session_cookie(&res);
+        assert_eq!(test::read_body(res).await, "2");
+
+        // unchanged sessions do not set cookies
+        let req = TestRequest::with_uri("/peek")
+            .cookie(cookie.clone())
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert!(res.headers().get(header::SET_COOKIE).is_none());
+        assert_eq!(test::read_body(res).await, "2");
+
+        // tampered cookies are ignored
+        let tampered = Cookie::new("id", format!("{}x", cookie.value()));
+        let req = TestRequest::with_uri("/peek").cookie(tampered).to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(test::read_body(res).await, "0");
+
+        let req = TestRequest::with_uri("/logout").cookie(cookie).to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(session_cookie(&res).value(), "");
+    }
+
+    #[actix_rt::test]
+    async fn rotates_keys() {
+        let old_key = Key::generate();
+        let new_key = Key::generate();
+
+        let srv = init(SessionMiddleware::new(
+            CookieSessionStore::new(),
+            old_key.clone(),
+        ))
+        .await;
+        let req = TestRequest::with_uri("/count").to_request();
+        let res = test::call_service(&srv, req).await;
+        let old_cookie = session_cookie(&res);
+
+        let srv = init(
+            SessionMiddleware::new(CookieSessionStore::new(), new_key.clone())
+                .fallback_key(old_key),
+        )
+        .await;
+        let req = TestRequest::with_uri("/peek")
+            .cookie(old_cookie)
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        let new_cookie = session_cookie(&res);
+        assert_eq!(test::read_body(res).await, "1");
+
+        let srv = init(SessionMiddleware::new(CookieSessionStore::new(), new_key)).await;
+        let req = TestRequest::with_uri("/peek")
+            .cookie(new_cookie)
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(test::read_body(res).await, "1");
+    }
+
+    #[actix_rt::test]
+    async fn expires_sessions() {
+        let srv = init(
+            SessionMiddleware::new(CookieSessionStore::new(), Key::generate())
+                .ttl(Duration::from_secs(0)),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/count").to_request();
+        let res = test::call_service(&srv, req).await;
+        let cookie = session_cookie(&res);
+
+        let req = TestRequest::with_uri("/peek").cookie(cookie).to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(test::read_body(res).await, "0");
+    }
+
+    #[actix_rt::test]
+    async fn limits_cookie_size() {
+        use crate::session::store::MAX_COOKIE_STATE_LEN;
+
+        // length of the cookie store envelope around the padding
+        let envelope_len = r#"{"expires":1234567890,"state":{"pad":"\"\""}}"#.len();
+        let max_pad = MAX_COOKIE_STATE_LEN - envelope_len;
+
+        let srv = init(SessionMiddleware::new(
+            CookieSessionStore::new(),
+            Key::generate(),
+        ))
+        .await;
+
+        let req = TestRequest::with_uri(&format!("/fill/{}", max_pad)).to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let header = res.headers().get(header::SET_COOKIE).unwrap();
+        assert!(header.len() <= MAX_COOKIE_LEN);
+
+        let req = TestRequest::with_uri(&format!("/fill/{}", max_pad + 1)).to_request();
+        let err = srv.call(req).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+
+        // signed cookies are percent-encoded, tripling the size of the padding
+        let srv = init(
+            SessionMiddleware::new(CookieSessionStore::new(), Key::generate())
+                .content_security(CookieContentSecurity::Signed),
+        )
+        .await;
+
+        let req = TestRequest::with_uri(&format!("/fill/{}", max_pad)).to_request();
+        let err = srv.call(req).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+
+        let req = TestRequest::with_uri("/fill/1000").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
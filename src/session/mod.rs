@@ -0,0 +1,15 @@
+//! Cookie-backed sessions.
+//!
+//! The [`SessionMiddleware`] loads the session of each request, which handlers read and modify
+//! through the [`Session`] extractor. Session keys are kept in an encrypted or signed cookie and
+//! session state in a pluggable [`SessionStore`]; [`CookieSessionStore`] keeps it in the cookie.
+//!
+//! Requires the `secure-cookies` feature.
+
+mod extractor;
+mod middleware;
+mod store;
+
+pub use self::extractor::{Session, SessionStatus};
+pub use self::middleware::{CookieContentSecurity, SessionMiddleware};
+pub use self::store::{CookieSessionStore, SessionState, SessionStore};
@@ -0,0 +1,134 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use futures_core::future::LocalBoxFuture;
+use serde::{Deserialize, Serialize};
+
+use crate::{error::ErrorInternalServerError, Error};
+
+/// State of a session: its keys mapped to JSON-serialized values.
+pub type SessionState = HashMap<String, String>;
+
+/// Maximum size of the state stored by [`CookieSessionStore`], leaving room for the encryption
+/// and Base64 overhead of private cookies and the cookie attributes within the 4096 bytes accepted
+/// by browsers.
+pub(super) const MAX_COOKIE_STATE_LEN: usize = 2816;
+
+/// Storage backend of the [`SessionMiddleware`](super::SessionMiddleware).
+///
+/// Sessions are identified by a session key, which the middleware stores in the session cookie
+/// after signing or encrypting it. A store either keeps the state itself, returning a random key,
+/// or, as [`CookieSessionStore`] does, encodes the state in the key.
+///
+/// Stores are shared by all workers, so they must be thread-safe.
+pub trait SessionStore: Send + Sync + 'static {
+    /// Loads the state of the session identified by `session_key`.
+    ///
+    /// Returns `None` if the session does not exist or has expired.
+    fn load(
+        &self,
+        session_key: &str,
+    ) -> LocalBoxFuture<'static, Result<Option<SessionState>, Error>>;
+
+    /// Saves the state of a new session, expiring after `ttl`, and returns its session key.
+    fn save(
+        &self,
+        state: SessionState,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<String, Error>>;
+
+    /// Replaces the state of the session identified by `session_key`, extending its expiry to
+    /// `ttl`, and returns its (possibly new) session key.
+    fn update(
+        &self,
+        session_key: String,
+        state: SessionState,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<String, Error>>;
+
+    /// Deletes the session identified by `session_key`.
+    fn delete(&self, session_key: &str) -> LocalBoxFuture<'static, Result<(), Error>>;
+}
+
+/// Session store keeping the whole session state in the session cookie.
+///
+/// No server-side storage is needed, but the state is limited to less than 3 KB and is sent with
+/// every request. Deleting a session only removes the cookie; a copy of the cookie kept by a client
+/// stays valid until it expires, so use a server-side store where sessions must be revocable.
+#[derive(Debug, Clone, Default)]
+pub struct CookieSessionStore;
+
+impl CookieSessionStore {
+    /// Constructs a cookie session store.
+    pub fn new() -> CookieSessionStore {
+        CookieSessionStore
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CookieEnvelope {
+    /// Expiry, in seconds since the Unix epoch.
+    expires: u64,
+    state: SessionState,
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+fn encode(state: SessionState, ttl: Duration) -> Result<String, Error> {
+    let envelope = CookieEnvelope {
+        expires: unix_time().saturating_add(ttl.as_secs()),
+        state,
+    };
+
+    let key = serde_json::to_string(&envelope)?;
+
+    if key.len() > MAX_COOKIE_STATE_LEN {
+        return Err(ErrorInternalServerError(
+            "Session state is too large to be stored in a cookie.",
+        ));
+    }
+
+    Ok(key)
+}
+
+impl SessionStore for CookieSessionStore {
+    fn load(
+        &self,
+        session_key: &str,
+    ) -> LocalBoxFuture<'static, Result<Option<SessionState>, Error>> {
+        let state = serde_json::from_str::<CookieEnvelope>(session_key)
+            .ok()
+            .filter(|envelope| envelope.expires > unix_time())
+            .map(|envelope| envelope.state);
+
+        Box::pin(async move { Ok(state) })
+    }
+
+    fn save(
+        &self,
+        state: SessionState,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<String, Error>> {
+        let res = encode(state, ttl);
+        Box::pin(async move { res })
+    }
+
+    fn update(
+        &self,
+        _session_key: String,
+        state: SessionState,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<String, Error>> {
+        self.save(state, ttl)
+    }
+
+    fn delete(&self, _session_key: &str) -> LocalBoxFuture<'static, Result<(), Error>> {
+        Box::pin(async { Ok(()) })
+    }
+}
@@ -27,7 +27,7 @@ use crate::{
     rmap::ResourceMap,
     service::{ServiceRequest, ServiceResponse},
     web::{Bytes, BytesMut},
-    Error, HttpRequest, HttpResponse, HttpResponseBuilder,
+    App, Error, HttpRequest, HttpResponse, HttpResponseBuilder,
 };
 
 /// Create service that always responds with `HttpResponse::Ok()` and no body.
@@ -93,6 +93,46 @@ where
     srv.new_service(AppConfig::default()).await
 }
 
+/// Initialize application and return its resource map.
+///
+/// Useful for checking which resources an [`App`] registers; see [`ResourceMap::entries`].
+///
+/// ```
+/// use actix_web::{test, web, App, HttpResponse};
+///
+/// #[actix_rt::test]
+/// async fn test_routes() {
+///     let app = App::new()
+///         .service(web::resource("/test").name("test").to(HttpResponse::Ok));
+///
+///     let rmap = test::init_resource_map(app).await;
+///
+///     let entry = rmap.entries().next().unwrap();
+///     assert_eq!(entry.pattern(), "/test");
+///     assert_eq!(entry.name(), Some("test"));
+/// }
+/// ```
+pub async fn init_resource_map<T, B>(app: App<T, B>) -> Rc<ResourceMap>
+where
+    B: MessageBody,
+    T: ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<B>,
+        Error = Error,
+        InitError = (),
+    >,
+    T::Future: 'static,
+{
+    let srv = app
+        .into_factory()
+        .new_service(AppConfig::default())
+        .await
+        .expect("service initialization failed");
+
+    srv.resource_map()
+}
+
 /// Calls service and waits for response future completion.
 ///
 /// ```
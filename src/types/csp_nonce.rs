@@ -0,0 +1,79 @@
+//! For CSP nonce extractor documentation, see [`CspNonce`].
+
+use std::fmt;
+
+use actix_utils::future::{err, ok, Ready};
+
+use crate::{dev::Payload, error::ErrorInternalServerError, Error, FromRequest, HttpRequest};
+
+/// Per-request nonce allowed by the `Content-Security-Policy` header of the
+/// [`SecurityHeaders`](crate::middleware::SecurityHeaders) middleware.
+///
+/// Inline scripts and styles rendered with a `nonce` attribute set to this value are allowed to
+/// run by the policy.
+///
+/// Extracting a `CspNonce` fails with a *500 Internal Server Error* response if the middleware is
+/// not registered or does not generate nonces.
+///
+/// # Examples
+/// ```
+/// use actix_web::{http::header::ContentSecurityPolicy, middleware, web, App, HttpResponse};
+///
+/// async fn index(nonce: web::CspNonce) -> HttpResponse {
+///     HttpResponse::Ok().content_type("text/html").body(format!(
+///         r#"<script nonce="{}">console.log("allowed")</script>"#,
+///         nonce
+///     ))
+/// }
+///
+/// let app = App::new()
+///     .wrap(
+///         middleware::SecurityHeaders::new()
+///             .content_security_policy(ContentSecurityPolicy::new().default_src(&["'self'"]))
+///             .csp_nonce(true),
+///     )
+///     .route("/", web::get().to(index));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CspNonce(String);
+
+impl CspNonce {
+    pub(crate) fn new(nonce: String) -> CspNonce {
+        CspNonce(nonce)
+    }
+
+    /// Returns the nonce.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Consumes the `CspNonce`, returning the nonce.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl fmt::Display for CspNonce {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromRequest for CspNonce {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        match req.extensions().get::<CspNonce>() {
+            Some(nonce) => ok(nonce.clone()),
+            None => {
+                log::debug!(
+                    "Failed to extract CspNonce; the SecurityHeaders middleware is not registered \
+                     or does not generate nonces. Request path: {:?}",
+                    req.path()
+                );
+                err(ErrorInternalServerError("Missing CSP nonce."))
+            }
+        }
+    }
+}
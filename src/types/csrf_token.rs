@@ -0,0 +1,98 @@
+//! For CSRF token extractor documentation, see [`CsrfToken`].
+
+use std::fmt;
+
+use actix_utils::future::{err, ok, Ready};
+
+use crate::{
+    dev::Payload, error::ErrorInternalServerError, http::header::HeaderName, Error,
+    FromRequest, HttpRequest,
+};
+
+/// Token issued by the [`Csrf`](crate::middleware::Csrf) middleware, to be submitted with
+/// requests using unsafe methods.
+///
+/// Pages rendering forms include the token in a hidden field named
+/// [`field_name`](Self::field_name); scripts send it in the [`header_name`](Self::header_name)
+/// header.
+///
+/// Extracting a `CsrfToken` fails with a *500 Internal Server Error* response if the middleware
+/// is not registered.
+///
+/// # Examples
+/// ```
+/// use actix_web::{middleware, web, App, HttpResponse};
+///
+/// async fn form(token: web::CsrfToken) -> HttpResponse {
+///     HttpResponse::Ok().content_type("text/html").body(format!(
+///         r#"<form method="post"><input type="hidden" name="{}" value="{}"></form>"#,
+///         token.field_name(),
+///         token,
+///     ))
+/// }
+///
+/// let app = App::new()
+///     .wrap(middleware::Csrf::new())
+///     .route("/form", web::get().to(form));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsrfToken {
+    token: String,
+    header: HeaderName,
+    field: String,
+}
+
+impl CsrfToken {
+    pub(crate) fn new(token: String, header: HeaderName, field: String) -> CsrfToken {
+        CsrfToken {
+            token,
+            header,
+            field,
+        }
+    }
+
+    /// Returns the token.
+    pub fn as_str(&self) -> &str {
+        &self.token
+    }
+
+    /// Returns the name of the header the token can be submitted in.
+    pub fn header_name(&self) -> &HeaderName {
+        &self.header
+    }
+
+    /// Returns the name of the form field the token can be submitted in.
+    pub fn field_name(&self) -> &str {
+        &self.field
+    }
+
+    /// Consumes the `CsrfToken`, returning the token.
+    pub fn into_inner(self) -> String {
+        self.token
+    }
+}
+
+impl fmt::Display for CsrfToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.token)
+    }
+}
+
+impl FromRequest for CsrfToken {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        match req.extensions().get::<CsrfToken>() {
+            Some(token) => ok(token.clone()),
+            None => {
+                log::debug!(
+                    "Failed to extract CsrfToken; the Csrf middleware is not registered. \
+                     Request path: {:?}",
+                    req.path()
+                );
+                err(ErrorInternalServerError("Missing CSRF token."))
+            }
+        }
+    }
+}
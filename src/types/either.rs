@@ -12,6 +12,7 @@ use futures_core::ready;
 
 use crate::{
     dev,
+    openapi::Operation,
     web::{Form, Json},
     Error, FromRequest, HttpRequest, HttpResponse, Responder,
 };
@@ -151,6 +152,11 @@ where
             Either::Right(b) => b.respond_to(req),
         }
     }
+
+    fn schema(operation: &mut Operation) {
+        L::schema(operation);
+        R::schema(operation);
+    }
 }
 
 /// A composite error resulting from failure to extract an `Either<L, R>`.
@@ -196,6 +202,11 @@ where
             },
         }
     }
+
+    fn schema(operation: &mut Operation) {
+        L::schema(operation);
+        R::schema(operation);
+    }
 }
 
 #[pin_project::pin_project]
@@ -20,8 +20,12 @@ use serde::{de::DeserializeOwned, Serialize};
 #[cfg(feature = "__compress")]
 use crate::dev::Decompress;
 use crate::{
-    error::UrlencodedError, extract::FromRequest, http::header::CONTENT_LENGTH, web, Error,
-    HttpMessage, HttpRequest, HttpResponse, Responder,
+    error::UrlencodedError,
+    extract::FromRequest,
+    http::{header::CONTENT_LENGTH, StatusCode},
+    middleware::body_limit,
+    openapi::{self, Operation},
+    web, Error, HttpMessage, HttpRequest, HttpResponse, Responder,
 };
 
 /// URL encoded payload extractor and responder.
@@ -131,7 +135,8 @@ where
 
     #[inline]
     fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        let FormConfig { limit, err_handler } = FormConfig::from_req(req).clone();
+        let err_handler = FormConfig::from_req(req).err_handler.clone();
+        let limit = FormConfig::limit_for(req);
 
         FormExtractFut {
             fut: UrlEncoded::new(req, payload).limit(limit),
@@ -139,6 +144,13 @@ where
             err_handler,
         }
     }
+
+    fn schema(operation: &mut Operation) {
+        operation.request_body(
+            mime::APPLICATION_WWW_FORM_URLENCODED,
+            openapi::schema_for::<T>(),
+        );
+    }
 }
 
 type FormErrHandler = Option<Rc<dyn Fn(UrlencodedError, &HttpRequest) -> Error>>;
@@ -188,6 +200,15 @@ impl<T: Serialize> Responder for Form<T> {
             Err(err) => HttpResponse::from_error(UrlencodedError::Serialize(err)),
         }
     }
+
+    fn schema(operation: &mut Operation) {
+        // structure of serialize-only types is unknown
+        operation.response_content(
+            StatusCode::OK,
+            mime::APPLICATION_WWW_FORM_URLENCODED,
+            serde_json::json!({}),
+        );
+    }
 }
 
 /// [`Form`] extractor configuration.
@@ -219,7 +240,9 @@ pub struct FormConfig {
 }
 
 impl FormConfig {
-    /// Set maximum accepted payload size. By default this limit is 16kB.
+    /// Set maximum accepted payload size. By default this limit is 16kB, or the limit of the
+    /// [`BodyLimit`](crate::middleware::BodyLimit) middleware if it is used, which also caps
+    /// the limit set here.
     pub fn limit(mut self, limit: usize) -> Self {
         self.limit = limit;
         self
@@ -238,9 +261,22 @@ impl FormConfig {
     ///
     /// Checks both `T` and `Data<T>`, in that order, and falls back to the default payload config.
     fn from_req(req: &HttpRequest) -> &Self {
+        Self::registered(req).unwrap_or(&DEFAULT_CONFIG)
+    }
+
+    fn registered(req: &HttpRequest) -> Option<&Self> {
         req.app_data::<Self>()
             .or_else(|| req.app_data::<web::Data<Self>>().map(|d| d.as_ref()))
-            .unwrap_or(&DEFAULT_CONFIG)
+    }
+
+    /// Returns the limit of the registered form config, falling back to the limit of the
+    /// [`BodyLimit`](crate::middleware::BodyLimit) middleware and then to the default limit.
+    fn limit_for(req: &HttpRequest) -> usize {
+        body_limit::extractor_limit(
+            req,
+            Self::registered(req).map(|cfg| cfg.limit),
+            DEFAULT_CONFIG.limit,
+        )
     }
 }
 
@@ -5,7 +5,11 @@ use std::{fmt, ops};
 use actix_utils::future::{err, ok, Ready};
 
 use crate::{
-    dev::Payload, error::ParseError, extract::FromRequest, http::header::Header as ParseHeader,
+    dev::Payload,
+    error::ParseError,
+    extract::FromRequest,
+    http::header::Header as ParseHeader,
+    openapi::{Operation, Parameter, ParameterIn},
     HttpRequest,
 };
 
@@ -70,6 +74,13 @@ where
             Err(e) => err(e),
         }
     }
+
+    fn schema(operation: &mut Operation) {
+        operation.parameter(
+            Parameter::new(T::name().as_str(), ParameterIn::Header)
+                .schema(serde_json::json!({ "type": "string" })),
+        );
+    }
 }
 
 #[cfg(test)]
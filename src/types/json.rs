@@ -21,7 +21,9 @@ use crate::dev::Decompress;
 use crate::{
     error::{Error, JsonPayloadError},
     extract::FromRequest,
-    http::header::CONTENT_LENGTH,
+    http::{header::CONTENT_LENGTH, StatusCode},
+    middleware::body_limit,
+    openapi::{self, Operation},
     request::HttpRequest,
     web, HttpMessage, HttpResponse, Responder,
 };
@@ -124,6 +126,15 @@ impl<T: Serialize> Responder for Json<T> {
             Err(err) => HttpResponse::from_error(JsonPayloadError::Serialize(err)),
         }
     }
+
+    fn schema(operation: &mut Operation) {
+        // structure of serialize-only types is unknown
+        operation.response_content(
+            StatusCode::OK,
+            mime::APPLICATION_JSON,
+            serde_json::json!({}),
+        );
+    }
 }
 
 /// See [here](#extractor) for example of usage as an extractor.
@@ -135,7 +146,7 @@ impl<T: DeserializeOwned> FromRequest for Json<T> {
     fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
         let config = JsonConfig::from_req(req);
 
-        let limit = config.limit;
+        let limit = JsonConfig::limit_for(req);
         let ctype_required = config.content_type_required;
         let ctype_fn = config.content_type.as_deref();
         let err_handler = config.err_handler.clone();
@@ -146,6 +157,10 @@ impl<T: DeserializeOwned> FromRequest for Json<T> {
             err_handler,
         }
     }
+
+    fn schema(operation: &mut Operation) {
+        operation.request_body(mime::APPLICATION_JSON, openapi::schema_for::<T>());
+    }
 }
 
 type JsonErrorHandler =
@@ -229,7 +244,9 @@ pub struct JsonConfig {
 }
 
 impl JsonConfig {
-    /// Set maximum accepted payload size. By default this limit is 2MB.
+    /// Set maximum accepted payload size. By default this limit is 2MB, or the limit of the
+    /// [`BodyLimit`](crate::middleware::BodyLimit) middleware if it is used, which also caps
+    /// the limit set here.
     pub fn limit(mut self, limit: usize) -> Self {
         self.limit = limit;
         self
@@ -262,9 +279,22 @@ impl JsonConfig {
     /// Extract payload config from app data. Check both `T` and `Data<T>`, in that order, and fall
     /// back to the default payload config.
     fn from_req(req: &HttpRequest) -> &Self {
+        Self::registered(req).unwrap_or(&DEFAULT_CONFIG)
+    }
+
+    fn registered(req: &HttpRequest) -> Option<&Self> {
         req.app_data::<Self>()
             .or_else(|| req.app_data::<web::Data<Self>>().map(|d| d.as_ref()))
-            .unwrap_or(&DEFAULT_CONFIG)
+    }
+
+    /// Returns the limit of the registered JSON config, falling back to the limit of the
+    /// [`BodyLimit`](crate::middleware::BodyLimit) middleware and then to the default limit.
+    fn limit_for(req: &HttpRequest) -> usize {
+        body_limit::extractor_limit(
+            req,
+            Self::registered(req).map(|cfg| cfg.limit),
+            DEFAULT_LIMIT,
+        )
     }
 }
 
@@ -1,20 +1,30 @@
 //! Common extractors and responders.
 
 // TODO: review visibility
+mod csp_nonce;
+#[cfg(feature = "cookies")]
+mod csrf_token;
 mod either;
 pub(crate) mod form;
 mod header;
 pub(crate) mod json;
 mod path;
 pub(crate) mod payload;
+pub(crate) mod preconditions;
 mod query;
 pub(crate) mod readlines;
+mod request_id;
 
+pub use self::csp_nonce::CspNonce;
+#[cfg(feature = "cookies")]
+pub use self::csrf_token::CsrfToken;
 pub use self::either::{Either, EitherExtractError};
 pub use self::form::{Form, FormConfig};
 pub use self::header::Header;
 pub use self::json::{Json, JsonConfig};
 pub use self::path::{Path, PathConfig};
 pub use self::payload::{Payload, PayloadConfig};
+pub use self::preconditions::{Preconditions, PreconditionsConfig};
 pub use self::query::{Query, QueryConfig};
 pub use self::readlines::Readlines;
+pub use self::request_id::RequestId;
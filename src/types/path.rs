@@ -9,6 +9,7 @@ use serde::de;
 use crate::{
     dev::Payload,
     error::{Error, ErrorNotFound, PathError},
+    openapi::{Operation, Shape},
     FromRequest, HttpRequest,
 };
 
@@ -122,6 +123,10 @@ where
                 }),
         )
     }
+
+    fn schema(operation: &mut Operation) {
+        operation.set_path(Shape::of::<T>());
+    }
 }
 
 /// Path extractor configuration
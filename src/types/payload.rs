@@ -16,8 +16,8 @@ use futures_core::{ready, stream::Stream};
 use mime::Mime;
 
 use crate::{
-    dev, error::ErrorBadRequest, http::header, web, Error, FromRequest, HttpMessage,
-    HttpRequest,
+    dev, error::ErrorBadRequest, http::header, middleware::body_limit, openapi::Operation, web,
+    Error, FromRequest, HttpMessage, HttpRequest,
 };
 
 /// Extract a request's raw payload stream.
@@ -71,6 +71,10 @@ impl FromRequest for Payload {
     fn from_request(_: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
         ready(Ok(Payload(payload.take())))
     }
+
+    fn schema(operation: &mut Operation) {
+        binary_body_schema(operation);
+    }
 }
 
 /// Extract binary data from a request's payload.
@@ -103,9 +107,20 @@ impl FromRequest for Bytes {
         }
 
         Either::left(BytesExtractFut {
-            body_fut: HttpMessageBody::new(req, payload).limit(cfg.limit),
+            body_fut: HttpMessageBody::new(req, payload).limit(PayloadConfig::limit_for(req)),
         })
     }
+
+    fn schema(operation: &mut Operation) {
+        binary_body_schema(operation);
+    }
+}
+
+fn binary_body_schema(operation: &mut Operation) {
+    operation.request_body(
+        mime::APPLICATION_OCTET_STREAM,
+        serde_json::json!({ "type": "string", "format": "binary" }),
+    );
 }
 
 /// Future for `Bytes` extractor.
@@ -154,11 +169,15 @@ impl FromRequest for String {
             Ok(enc) => enc,
             Err(err) => return Either::right(ready(Err(err.into()))),
         };
-        let limit = cfg.limit;
+        let limit = PayloadConfig::limit_for(req);
         let body_fut = HttpMessageBody::new(req, payload).limit(limit);
 
         Either::left(StringExtractFut { body_fut, encoding })
     }
+
+    fn schema(operation: &mut Operation) {
+        operation.request_body(mime::TEXT_PLAIN, serde_json::json!({ "type": "string" }));
+    }
 }
 
 /// Future for `String` extractor.
@@ -200,7 +219,9 @@ fn bytes_to_string(body: Bytes, encoding: &'static Encoding) -> Result<String, E
 /// conformance with this configuration to allow more flexibility when
 /// building extractors on top of [`Payload`].
 ///
-/// By default, the payload size limit is 256kB and there is no mime type condition.
+/// By default, the payload size limit is 256kB, or the limit of the
+/// [`BodyLimit`](crate::middleware::BodyLimit) middleware if it is used, and there is no mime
+/// type condition. The limit of the middleware also caps the limit set here.
 ///
 /// To use this, add an instance of it to your [`app`](crate::App), [`scope`](crate::Scope)
 /// or [`resource`](crate::Resource) through the associated `.app_data()` method.
@@ -254,9 +275,22 @@ impl PayloadConfig {
     /// Extract payload config from app data. Check both `T` and `Data<T>`, in that order, and fall
     /// back to the default payload config if neither is found.
     fn from_req(req: &HttpRequest) -> &Self {
+        Self::registered(req).unwrap_or(&DEFAULT_CONFIG)
+    }
+
+    fn registered(req: &HttpRequest) -> Option<&Self> {
         req.app_data::<Self>()
             .or_else(|| req.app_data::<web::Data<Self>>().map(|d| d.as_ref()))
-            .unwrap_or(&DEFAULT_CONFIG)
+    }
+
+    /// Returns the limit of the registered payload config, falling back to the limit of the
+    /// [`BodyLimit`](crate::middleware::BodyLimit) middleware and then to the default limit.
+    fn limit_for(req: &HttpRequest) -> usize {
+        body_limit::extractor_limit(
+            req,
+            Self::registered(req).map(|cfg| cfg.limit),
+            DEFAULT_CONFIG_LIMIT,
+        )
     }
 }
 
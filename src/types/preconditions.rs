@@ -0,0 +1,382 @@
+//! For preconditions extractor documentation, see [`Preconditions`].
+
+use actix_utils::future::{ready, Ready};
+
+use crate::{
+    dev::Payload,
+    error::{ParseError, PreconditionError},
+    http::{
+        header::{EntityTag, Header, HttpDate, IfMatch, IfNoneMatch, IfUnmodifiedSince},
+        Method, StatusCode,
+    },
+    middleware::conditional_get::unix_secs,
+    openapi::{Operation, Parameter, ParameterIn},
+    web, Error, FromRequest, HttpRequest,
+};
+
+/// Conditional headers of a request, for evaluating its preconditions against the current state
+/// of the target resource.
+///
+/// Handlers of state-changing requests such as `PUT`, `PATCH` and `DELETE` look up the current
+/// validators of the resource, its `ETag` and last modification time, and pass them to
+/// [`check`](Self::check), or call [`check_missing`](Self::check_missing) when the resource does
+/// not exist. The `If-Match`, `If-Unmodified-Since` and `If-None-Match` headers are evaluated in
+/// the order of [RFC 9110 §13.2.2](https://www.rfc-editor.org/rfc/rfc9110#section-13.2.2), and a
+/// [`PreconditionError::Failed`] error, answered with *412 Precondition Failed*, is returned if
+/// one of them is false. This allows clients to avoid overwriting concurrent changes.
+///
+/// `If-None-Match` is only evaluated for state-changing methods; conditional `GET` and `HEAD`
+/// requests are handled by [`ConditionalGet`](crate::middleware::ConditionalGet).
+///
+/// Use [`PreconditionsConfig`] to require preconditions on state-changing requests. Extraction
+/// then fails with [`PreconditionError::Required`], answered with *428 Precondition Required*,
+/// for requests without conditional headers. Malformed conditional headers are rejected with
+/// *400 Bad Request*.
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     http::header::{EntityTag, ETag},
+///     web, App, Error, HttpResponse,
+/// };
+///
+/// async fn update(pre: web::Preconditions, body: String) -> Result<HttpResponse, Error> {
+///     // the current version of the document
+///     let etag = EntityTag::strong("v1".to_owned());
+///
+///     pre.check(Some(&etag), None)?;
+///
+///     // ... store `body` as the new version
+///     Ok(HttpResponse::Ok()
+///         .insert_header(ETag(EntityTag::strong("v2".to_owned())))
+///         .finish())
+/// }
+///
+/// let app = App::new()
+///     .app_data(web::PreconditionsConfig::default().required(true))
+///     .route("/document", web::put().to(update));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Preconditions {
+    method: Method,
+    if_match: Option<IfMatch>,
+    if_unmodified_since: Option<HttpDate>,
+    if_none_match: Option<IfNoneMatch>,
+}
+
+impl Preconditions {
+    /// Parses the conditional headers of `req`.
+    pub fn from_headers(req: &HttpRequest) -> Result<Preconditions, ParseError> {
+        Ok(Preconditions {
+            method: req.method().clone(),
+            if_match: parse_etag_header(req)?,
+            if_unmodified_since: parse_header::<IfUnmodifiedSince>(req)?.map(|h| h.0),
+            if_none_match: parse_etag_header(req)?,
+        })
+    }
+
+    /// Returns `true` if the request has no conditional headers evaluated by this type.
+    pub fn is_empty(&self) -> bool {
+        self.if_match.is_none()
+            && self.if_unmodified_since.is_none()
+            && (self.if_none_match.is_none() || is_safe(&self.method))
+    }
+
+    /// Evaluates the preconditions against an existing resource with the given validators.
+    ///
+    /// Resources that have no `ETag` or no modification time pass `None`.
+    pub fn check(
+        &self,
+        etag: Option<&EntityTag>,
+        last_modified: Option<HttpDate>,
+    ) -> Result<(), PreconditionError> {
+        self.evaluate(Some((etag, last_modified)))
+    }
+
+    /// Evaluates the preconditions against a resource that does not exist.
+    ///
+    /// Any `If-Match` precondition fails, while `If-None-Match: *` succeeds, allowing clients to
+    /// create resources without overwriting existing ones.
+    pub fn check_missing(&self) -> Result<(), PreconditionError> {
+        self.evaluate(None)
+    }
+
+    fn evaluate(
+        &self,
+        current: Option<(Option<&EntityTag>, Option<HttpDate>)>,
+    ) -> Result<(), PreconditionError> {
+        let etag = current.and_then(|(etag, _)| etag);
+        let last_modified = current.and_then(|(_, last_modified)| last_modified);
+
+        // If-Unmodified-Since is only evaluated without If-Match
+        if let Some(ref if_match) = self.if_match {
+            let matches = match if_match {
+                IfMatch::Any => current.is_some(),
+                IfMatch::Items(items) => match etag {
+                    Some(etag) => items.iter().any(|item| item.strong_eq(etag)),
+                    None => false,
+                },
+            };
+
+            if !matches {
+                return Err(PreconditionError::Failed);
+            }
+        } else if let (Some(since), Some(modified)) = (self.if_unmodified_since, last_modified)
+        {
+            match (unix_secs(modified), unix_secs(since)) {
+                (Some(modified), Some(since)) if modified <= since => {}
+                _ => return Err(PreconditionError::Failed),
+            }
+        }
+
+        if is_safe(&self.method) {
+            return Ok(());
+        }
+
+        if let Some(ref if_none_match) = self.if_none_match {
+            let matches = match if_none_match {
+                IfNoneMatch::Any => current.is_some(),
+                IfNoneMatch::Items(items) => match etag {
+                    Some(etag) => items.iter().any(|item| item.weak_eq(etag)),
+                    None => false,
+                },
+            };
+
+            if matches {
+                return Err(PreconditionError::Failed);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the header `H` of `req`, or `None` if it is missing.
+fn parse_header<H: Header>(req: &HttpRequest) -> Result<Option<H>, ParseError> {
+    if req.headers().contains_key(H::name()) {
+        H::parse(req).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Returns the entity tag list header `H` of `req`, or `None` if it is missing.
+///
+/// Unlike `H::parse`, which skips items that are not entity tags, fails if the header contains
+/// any such item.
+fn parse_etag_header<H: Header>(req: &HttpRequest) -> Result<Option<H>, ParseError> {
+    for val in req.headers().get_all(H::name()) {
+        let val = val.to_str().map_err(|_| ParseError::Header)?;
+
+        let valid = val
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .all(|item| item == "*" || item.parse::<EntityTag>().is_ok());
+
+        if !valid {
+            return Err(ParseError::Header);
+        }
+    }
+
+    parse_header(req)
+}
+
+/// Returns whether requests with `method` do not change the state of resources.
+fn is_safe(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    )
+}
+
+impl FromRequest for Preconditions {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let preconditions = match Preconditions::from_headers(req) {
+            Ok(preconditions) => preconditions,
+            Err(err) => return ready(Err(err.into())),
+        };
+
+        let config = PreconditionsConfig::from_req(req);
+
+        if config.required && !is_safe(req.method()) && preconditions.is_empty() {
+            log::debug!(
+                "Request without required preconditions. Request path: {:?}",
+                req.path()
+            );
+            return ready(Err(PreconditionError::Required.into()));
+        }
+
+        ready(Ok(preconditions))
+    }
+
+    fn schema(operation: &mut Operation) {
+        for name in &["If-Match", "If-None-Match", "If-Unmodified-Since"] {
+            operation.parameter(
+                Parameter::new(*name, ParameterIn::Header)
+                    .schema(serde_json::json!({ "type": "string" })),
+            );
+        }
+
+        operation.response(StatusCode::PRECONDITION_FAILED, "Precondition Failed");
+    }
+}
+
+/// Configuration of the [`Preconditions`] extractor.
+///
+/// # Examples
+/// ```
+/// use actix_web::{web, App, HttpResponse};
+///
+/// let app = App::new().service(
+///     web::resource("/document")
+///         .app_data(web::PreconditionsConfig::default().required(true))
+///         .route(web::put().to(|_: web::Preconditions| HttpResponse::Ok())),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct PreconditionsConfig {
+    required: bool,
+}
+
+impl PreconditionsConfig {
+    /// Sets whether state-changing requests must have conditional headers.
+    ///
+    /// Default is `false`.
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// Extract preconditions config from app data. Check both `T` and `Data<T>`, in that order,
+    /// and fall back to the default config.
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|d| d.as_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+/// Allow shared refs used as default.
+const DEFAULT_CONFIG: PreconditionsConfig = PreconditionsConfig { required: false };
+
+impl Default for PreconditionsConfig {
+    fn default() -> Self {
+        DEFAULT_CONFIG.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::*;
+    use crate::{
+        http::header,
+        test::{self, TestRequest},
+        App, HttpResponse,
+    };
+
+    fn preconditions(req: TestRequest) -> Preconditions {
+        Preconditions::from_headers(&req.to_http_request()).unwrap()
+    }
+
+    #[test]
+    fn evaluates_if_match() {
+        let etag = EntityTag::strong("v1".to_owned());
+        let weak = EntityTag::weak("v1".to_owned());
+
+        let pre = preconditions(
+            TestRequest::put().insert_header((header::IF_MATCH, "\"v0\", \"v1\"")),
+        );
+        assert!(pre.check(Some(&etag), None).is_ok());
+        assert!(pre.check(Some(&weak), None).is_err());
+        assert!(pre.check(None, None).is_err());
+        assert!(pre.check_missing().is_err());
+
+        let pre = preconditions(TestRequest::delete().insert_header((header::IF_MATCH, "*")));
+        assert!(pre.check(None, None).is_ok());
+        assert!(pre.check_missing().is_err());
+
+        let pre = preconditions(TestRequest::put().insert_header((header::IF_NONE_MATCH, "*")));
+        assert!(pre.check(Some(&etag), None).is_err());
+        assert!(pre.check_missing().is_ok());
+
+        let pre = preconditions(TestRequest::get().insert_header((header::IF_NONE_MATCH, "*")));
+        assert!(pre.is_empty());
+        assert!(pre.check(Some(&etag), None).is_ok());
+    }
+
+    #[test]
+    fn evaluates_if_unmodified_since() {
+        let modified = HttpDate::from(UNIX_EPOCH + Duration::from_secs(1_000_000));
+
+        let pre = preconditions(
+            TestRequest::patch()
+                .insert_header((header::IF_UNMODIFIED_SINCE, "Mon, 12 Jan 1970 13:46:40 GMT")),
+        );
+        assert!(pre.check(None, Some(modified)).is_ok());
+        assert!(pre.check(None, None).is_ok());
+
+        let pre = preconditions(
+            TestRequest::patch()
+                .insert_header((header::IF_UNMODIFIED_SINCE, "Mon, 12 Jan 1970 13:46:39 GMT")),
+        );
+        assert!(pre.check(None, Some(modified)).is_err());
+
+        // ignored when If-Match is present
+        let pre = preconditions(
+            TestRequest::patch()
+                .insert_header((header::IF_MATCH, "*"))
+                .insert_header((header::IF_UNMODIFIED_SINCE, "Mon, 12 Jan 1970 13:46:39 GMT")),
+        );
+        assert!(pre.check(None, Some(modified)).is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn rejects_requests() {
+        let srv = test::init_service(
+            App::new()
+                .app_data(PreconditionsConfig::default().required(true))
+                .route(
+                    "/",
+                    web::put().to(|pre: Preconditions| async move {
+                        pre.check(Some(&EntityTag::strong("v1".to_owned())), None)?;
+                        Ok::<_, Error>(HttpResponse::NoContent().finish())
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::put().to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_REQUIRED);
+
+        let req = TestRequest::put()
+            .insert_header((header::IF_MATCH, "\"v0\""))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+
+        let req = TestRequest::put()
+            .insert_header((header::IF_MATCH, "\"v1\""))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+        let req = TestRequest::put()
+            .insert_header((header::IF_MATCH, "v1"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::put()
+            .insert_header((header::IF_MATCH, "\"v1\", v2"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+}
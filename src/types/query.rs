@@ -5,7 +5,12 @@ use std::{fmt, ops, sync::Arc};
 use actix_utils::future::{err, ok, Ready};
 use serde::de::DeserializeOwned;
 
-use crate::{dev::Payload, error::QueryPayloadError, Error, FromRequest, HttpRequest};
+use crate::{
+    dev::Payload,
+    error::QueryPayloadError,
+    openapi::{Operation, Parameter, ParameterIn, Shape},
+    Error, FromRequest, HttpRequest,
+};
 
 /// Extract typed information from the request's query.
 ///
@@ -136,6 +141,19 @@ impl<T: DeserializeOwned> FromRequest for Query<T> {
                 err(e)
             })
     }
+
+    fn schema(operation: &mut Operation) {
+        // only named fields can be described as query parameters
+        if let Shape::Struct(fields) = Shape::of::<T>() {
+            for (name, shape) in fields {
+                operation.parameter(
+                    Parameter::new(name, ParameterIn::Query)
+                        .required(!shape.is_optional())
+                        .schema(shape.to_schema()),
+                );
+            }
+        }
+    }
 }
 
 /// Query extractor configuration.
@@ -0,0 +1,105 @@
+//! For request ID extractor documentation, see [`RequestId`].
+
+use std::{convert::TryFrom, fmt};
+
+use actix_utils::future::{err, ok, Ready};
+
+use crate::{
+    dev::Payload,
+    error::ErrorInternalServerError,
+    http::header::{HeaderName, HeaderValue, IntoHeaderPair, InvalidHeaderValue},
+    Error, FromRequest, HttpRequest,
+};
+
+/// Identifier of a request, assigned by the [`RequestId`](crate::middleware::RequestId)
+/// middleware.
+///
+/// Extracting a `RequestId` fails with a *500 Internal Server Error* response if the middleware
+/// is not registered; use `Option<RequestId>` where it is optional.
+///
+/// The middleware forwards the ID on `awc` requests created while the request is handled. Other
+/// clients, or tasks spawned by the handler, can pass it on by inserting it as a header of the
+/// outgoing request; it is sent in the same header that the middleware reads from incoming
+/// requests.
+///
+/// # Examples
+/// ```
+/// use actix_web::{middleware, web, App, HttpResponse};
+///
+/// async fn index(request_id: web::RequestId) -> HttpResponse {
+///     log::info!("handling request {}", request_id);
+///
+///     HttpResponse::Ok().finish()
+/// }
+///
+/// let app = App::new()
+///     .wrap(middleware::RequestId::new())
+///     .route("/", web::get().to(index));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId {
+    header: HeaderName,
+    id: String,
+}
+
+impl RequestId {
+    pub(crate) fn new(header: HeaderName, id: String) -> RequestId {
+        RequestId { header, id }
+    }
+
+    /// Returns the request ID.
+    pub fn as_str(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the name of the header carrying the request ID.
+    pub fn header_name(&self) -> &HeaderName {
+        &self.header
+    }
+
+    /// Consumes the `RequestId`, returning the ID.
+    pub fn into_inner(self) -> String {
+        self.id
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.id)
+    }
+}
+
+impl IntoHeaderPair for RequestId {
+    type Error = InvalidHeaderValue;
+
+    fn try_into_header_pair(self) -> Result<(HeaderName, HeaderValue), Self::Error> {
+        Ok((self.header, HeaderValue::try_from(self.id)?))
+    }
+}
+
+impl IntoHeaderPair for &RequestId {
+    type Error = InvalidHeaderValue;
+
+    fn try_into_header_pair(self) -> Result<(HeaderName, HeaderValue), Self::Error> {
+        Ok((self.header.clone(), HeaderValue::from_str(&self.id)?))
+    }
+}
+
+impl FromRequest for RequestId {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        match req.extensions().get::<RequestId>() {
+            Some(request_id) => ok(request_id.clone()),
+            None => {
+                log::debug!(
+                    "Failed to extract RequestId; the RequestId middleware is not registered. \
+                     Request path: {:?}",
+                    req.path()
+                );
+                err(ErrorInternalServerError("Missing request ID."))
+            }
+        }
+    }
+}